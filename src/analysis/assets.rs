@@ -0,0 +1,214 @@
+use crate::dom::Dom;
+use crate::luau::lexer::{tokenize, TokenKind};
+use crate::properties::PropertyValue;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+#[derive(Serialize)]
+pub struct AssetReference {
+    pub path: String,
+    /// The property holding the URL, or for script literals the property it is assigned to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub property: Option<String>,
+    /// Set for references found in script sources.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+#[derive(Serialize)]
+pub struct Asset {
+    pub id: u64,
+    pub kind: &'static str,
+    pub references: Vec<AssetReference>,
+}
+
+#[derive(Serialize)]
+pub struct AssetInventory {
+    pub assets: Vec<Asset>,
+}
+
+/// Asset kind guessed from the property name, e.g. `MeshId` or `SoundId`.
+pub fn kind_for_property(property: &str) -> &'static str {
+    let name = property.to_ascii_lowercase();
+    let has = |part: &str| name.contains(part);
+    if has("mesh") {
+        "Mesh"
+    } else if has("sound") || has("audio") {
+        "Audio"
+    } else if has("animation") {
+        "Animation"
+    } else if has("video") {
+        "Video"
+    } else if has("font") || has("family") {
+        "Font"
+    } else if has("package") || has("linkedsource") {
+        "Package"
+    } else if has("image") || has("texture") || has("template") || has("skybox") || has("map") || has("graphic") || has("face") || has("decal") || has("icon") || has("sun") || has("moon") {
+        "Image"
+    } else {
+        "Unknown"
+    }
+}
+
+/// Numeric asset ids referenced by a content string: `rbxassetid://123`, `rbxthumb://...id=123`
+/// and web URLs such as `https://www.roblox.com/asset/?id=123`. Built-in `rbxasset://` files are skipped.
+pub fn asset_ids(text: &str) -> Vec<u64> {
+    let mut ids = Vec::new();
+    let lower = text.to_ascii_lowercase();
+    for marker in ["rbxassetid://", "id="] {
+        let mut from = 0;
+        while let Some(found) = lower[from..].find(marker) {
+            let start = from + found + marker.len();
+            from = start;
+            // `id=` only counts inside asset URLs, not in arbitrary text like `userid=`.
+            if marker == "id=" {
+                let before = &lower[..start - marker.len()];
+                let is_asset_url = before.contains("roblox.com/") || before.contains("rbxthumb://");
+                let at_param = before.ends_with('?') || before.ends_with('&');
+                if !is_asset_url || !at_param {
+                    continue;
+                }
+            }
+            let digits: String = lower[start..].chars().skip_while(|c| *c == ' ').take_while(|c| c.is_ascii_digit()).collect();
+            if let Ok(id) = digits.parse::<u64>() {
+                if id > 0 && !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+    }
+    ids
+}
+
+pub fn analyze(dom: &Dom) -> AssetInventory {
+    let mut assets: BTreeMap<u64, Asset> = BTreeMap::new();
+    let mut add = |id: u64, kind: &'static str, reference: AssetReference| {
+        let asset = assets.entry(id).or_insert_with(|| Asset { id, kind, references: Vec::new() });
+        if asset.kind == "Unknown" {
+            asset.kind = kind;
+        }
+        asset.references.push(reference);
+    };
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        for property in &instance.properties {
+            let text = match &property.value {
+                PropertyValue::Content(Some(url)) => url.as_str(),
+                PropertyValue::String(s) => s.as_str(),
+                PropertyValue::Font { family: Some(family), .. } => family.as_str(),
+                _ => continue,
+            };
+            for asset in asset_ids(text) {
+                add(asset, kind_for_property(&property.name), AssetReference { path: dom.path(id), property: Some(property.name.clone()), line: None });
+            }
+        }
+
+        let Some(source) = &instance.source else { continue };
+        let tokens = tokenize(source);
+        for (i, token) in tokens.iter().enumerate() {
+            if token.kind != TokenKind::String {
+                continue;
+            }
+            let Some(value) = token.string_value() else { continue };
+            let ids = asset_ids(&value);
+            if ids.is_empty() {
+                continue;
+            }
+            // `sound.SoundId = "rbxassetid://..."` or `{ Image = "rbxassetid://..." }`.
+            let assigned = i >= 3
+                && tokens[i - 1].is_symbol("=")
+                && tokens[i - 2].kind == TokenKind::Name
+                && [".", "{", ",", ";"].iter().any(|s| tokens[i - 3].is_symbol(s));
+            let property = assigned.then(|| tokens[i - 2].text.to_string());
+            let kind = property.as_deref().map_or("Unknown", kind_for_property);
+            for asset in ids {
+                add(asset, kind, AssetReference { path: dom.path(id), property: property.clone(), line: Some(token.line) });
+            }
+        }
+    }
+
+    AssetInventory { assets: assets.into_values().collect() }
+}
+
+pub fn summary(inventory: &AssetInventory) -> String {
+    let references: usize = inventory.assets.iter().map(|a| a.references.len()).sum();
+    let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+    for asset in &inventory.assets {
+        *kinds.entry(asset.kind).or_default() += 1;
+    }
+    let kinds: Vec<String> = kinds.into_iter().map(|(kind, n)| format!("{} {}", n, kind)).collect();
+    format!("Assets: {} ids, {} references ({})", inventory.assets.len(), references, kinds.join(", "))
+}
+
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per reference, so the same id appears once for every place that uses it.
+pub fn to_csv(inventory: &AssetInventory) -> String {
+    let mut out = String::from("id,kind,path,property,line\n");
+    for asset in &inventory.assets {
+        for reference in &asset.references {
+            let _ = writeln!(
+                out,
+                "{},{},{},{},{}",
+                asset.id,
+                asset.kind,
+                csv_field(&reference.path),
+                csv_field(reference.property.as_deref().unwrap_or("")),
+                reference.line.map(|l| l.to_string()).unwrap_or_default()
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_ids_in_asset_urls() {
+        assert_eq!(asset_ids("rbxassetid://123"), vec![123]);
+        assert_eq!(asset_ids("RBXASSETID:// 45"), vec![45]);
+        assert_eq!(asset_ids("https://www.roblox.com/asset/?id=678"), vec![678]);
+        assert_eq!(asset_ids("rbxthumb://type=Asset&id=9&w=150&h=150"), vec![9]);
+        assert_eq!(asset_ids("rbxassetid://1 rbxassetid://2 rbxassetid://1"), vec![1, 2]);
+    }
+
+    #[test]
+    fn ignores_other_numbers() {
+        assert!(asset_ids("rbxasset://textures/face.png").is_empty());
+        assert!(asset_ids("https://example.com/?userid=5&id=6").is_empty());
+        assert!(asset_ids("https://www.roblox.com/games/?placeid=7").is_empty());
+        assert!(asset_ids("rbxassetid://0").is_empty());
+        assert!(asset_ids("rbxassetid://").is_empty());
+    }
+
+    #[test]
+    fn guesses_kind_from_property() {
+        assert_eq!(kind_for_property("MeshId"), "Mesh");
+        assert_eq!(kind_for_property("SoundId"), "Audio");
+        assert_eq!(kind_for_property("TextureID"), "Image");
+        assert_eq!(kind_for_property("Value"), "Unknown");
+    }
+
+    #[test]
+    fn reads_ids_from_unfinished_long_strings() {
+        let tokens = tokenize("Image = [[rbxassetid://12€");
+        let value = tokens[2].string_value().unwrap();
+        assert_eq!(asset_ids(&value), vec![12]);
+    }
+
+    #[test]
+    fn quotes_csv_fields() {
+        assert_eq!(csv_field("a/b"), "a/b");
+        assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+    }
+}
@@ -0,0 +1,355 @@
+use crate::analysis::deps::is_script_class;
+use crate::dom::Dom;
+use crate::luau::lexer::{tokenize, Token, TokenKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    fn weight(self) -> u32 {
+        match self {
+            Severity::Low => 1,
+            Severity::Medium => 4,
+            Severity::High => 10,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct Finding {
+    pub severity: Severity,
+    pub rule: &'static str,
+    /// `None` for findings about where the script lives rather than what it contains.
+    pub line: Option<usize>,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct ScriptAudit {
+    pub path: String,
+    pub class: String,
+    pub score: u32,
+    pub findings: Vec<Finding>,
+}
+
+/// Scripts with at least one finding, riskiest first.
+#[derive(Serialize)]
+pub struct AuditReport {
+    pub scanned: usize,
+    pub scripts: Vec<ScriptAudit>,
+}
+
+/// Services where scripts normally live. Anything else (Lighting, SoundService, Teams...) is a
+/// common hiding place for scripts inserted by free models.
+const SCRIPT_CONTAINERS: &[&str] = &[
+    "Workspace",
+    "ServerScriptService",
+    "ServerStorage",
+    "ReplicatedStorage",
+    "ReplicatedFirst",
+    "StarterPlayer",
+    "StarterGui",
+    "StarterPack",
+];
+
+/// Names that make a script look like an ordinary object in the Explorer.
+const DECOY_NAMES: &[&str] = &[
+    "Weld", "WeldConstraint", "Motor6D", "Camera", "ThumbnailCamera", "Part", "Handle", "Mesh",
+    "SpecialMesh", "Attachment", "Decal", "Texture", "Sound", "Configuration", "Value", "Humanoid",
+    "Animator", "TouchInterest", "Smooth", "Light", "PointLight",
+];
+
+const HTTP_METHODS: &[&str] = &["GetAsync", "PostAsync", "RequestAsync", "HttpGet", "HttpGetAsync", "HttpPost", "HttpPostAsync"];
+
+const MIN_ESCAPES: usize = 10;
+const MIN_ENCODED_LENGTH: usize = 512;
+const MIN_CHAR_CODES: usize = 10;
+
+pub fn audit(dom: &Dom) -> AuditReport {
+    let mut report = AuditReport { scanned: 0, scripts: Vec::new() };
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        if !is_script_class(&instance.class) {
+            continue;
+        }
+        report.scanned += 1;
+
+        let mut findings = placement_findings(dom, id);
+        if let Some(source) = &instance.source {
+            findings.extend(source_findings(source));
+        }
+        if findings.is_empty() {
+            continue;
+        }
+        findings.sort_by(|a, b| b.severity.cmp(&a.severity).then(a.line.cmp(&b.line)));
+        report.scripts.push(ScriptAudit {
+            path: dom.path(id),
+            class: instance.class.clone(),
+            score: findings.iter().map(|f| f.severity.weight()).sum(),
+            findings,
+        });
+    }
+
+    report.scripts.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    report
+}
+
+pub fn summary(report: &AuditReport) -> String {
+    let findings: Vec<&Finding> = report.scripts.iter().flat_map(|s| &s.findings).collect();
+    let high = findings.iter().filter(|f| f.severity == Severity::High).count();
+    format!(
+        "Audit: {} findings in {} of {} scripts ({} high)",
+        findings.len(),
+        report.scripts.len(),
+        report.scanned,
+        high
+    )
+}
+
+fn placement_findings(dom: &Dom, id: usize) -> Vec<Finding> {
+    let instance = &dom.instances[id];
+    let mut findings = Vec::new();
+
+    if instance.name.trim().is_empty() {
+        findings.push(Finding { severity: Severity::Medium, rule: "blank-name", line: None, detail: "script has an empty or whitespace-only name".to_string() });
+    } else if DECOY_NAMES.contains(&instance.name.as_str()) {
+        findings.push(Finding {
+            severity: Severity::Medium,
+            rule: "decoy-name",
+            line: None,
+            detail: format!("{} is named like a regular '{}' object", instance.class, instance.name),
+        });
+    }
+
+    let mut root = id;
+    while let Some(parent) = dom.instances[root].parent {
+        root = parent;
+    }
+    let service = &dom.instances[root].class;
+    if root != id && !SCRIPT_CONTAINERS.contains(&service.as_str()) {
+        findings.push(Finding {
+            severity: Severity::Medium,
+            rule: "hidden-location",
+            line: None,
+            detail: format!("scripts do not normally run from {}", service),
+        });
+    }
+
+    if let Some(parent) = instance.parent {
+        let parent_class = &dom.instances[parent].class;
+        if is_script_class(parent_class) && instance.class != "ModuleScript" {
+            findings.push(Finding {
+                severity: Severity::Low,
+                rule: "nested-script",
+                line: None,
+                detail: format!("{} is parented to another script", instance.class),
+            });
+        } else if matches!(parent_class.as_str(), "Weld" | "WeldConstraint" | "Motor6D" | "Attachment" | "Decal" | "Texture" | "Sound" | "SpecialMesh" | "Camera" | "Humanoid") {
+            findings.push(Finding {
+                severity: Severity::Low,
+                rule: "odd-parent",
+                line: None,
+                detail: format!("parented to a {}", parent_class),
+            });
+        }
+    }
+    findings
+}
+
+fn is_call_of(tokens: &[Token], i: usize, name: &str) -> bool {
+    tokens[i].is_name(name)
+        && tokens.get(i + 1).is_some_and(|t| t.is_symbol("(") || t.kind == TokenKind::String)
+        && !(i > 0 && (tokens[i - 1].is_symbol(".") || tokens[i - 1].is_symbol(":")))
+}
+
+fn source_findings(source: &str) -> Vec<Finding> {
+    let tokens = tokenize(source);
+    let mut findings = Vec::new();
+    // `local id = 123456` followed later by `require(id)`.
+    let mut numeric_locals: HashMap<&str, &str> = HashMap::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if token.is_keyword("local")
+            && tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::Name)
+            && tokens.get(i + 2).is_some_and(|t| t.is_symbol("="))
+        {
+            let name = tokens[i + 1].text;
+            if value_is_last_number(&tokens, i + 3) {
+                numeric_locals.insert(name, tokens[i + 3].text);
+            } else {
+                numeric_locals.remove(name);
+            }
+        }
+
+        if is_call_of(&tokens, i, "require") && tokens.get(i + 1).is_some_and(|t| t.is_symbol("(")) {
+            let arg = tokens.get(i + 2);
+            let closed = tokens.get(i + 3).is_some_and(|t| t.is_symbol(")"));
+            let asset = match arg {
+                Some(a) if closed && a.kind == TokenKind::Number => Some(a.text),
+                Some(a) if closed && a.kind == TokenKind::Name => numeric_locals.get(a.text).copied(),
+                Some(a) if a.is_name("tonumber") => Some("tonumber(...)"),
+                _ => None,
+            };
+            if let Some(asset) = asset {
+                findings.push(Finding {
+                    severity: Severity::High,
+                    rule: "numeric-require",
+                    line: Some(token.line),
+                    detail: format!("require({}) loads a module from the website", asset),
+                });
+            }
+        }
+
+        if is_call_of(&tokens, i, "loadstring") {
+            findings.push(Finding { severity: Severity::High, rule: "loadstring", line: Some(token.line), detail: "compiles and runs code from a string".to_string() });
+        }
+
+        for name in ["getfenv", "setfenv"] {
+            if is_call_of(&tokens, i, name) {
+                findings.push(Finding {
+                    severity: Severity::Medium,
+                    rule: "fenv",
+                    line: Some(token.line),
+                    detail: format!("{} can read or replace a function's environment", name),
+                });
+            }
+        }
+
+        if token.is_symbol(":") && tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::Name && HTTP_METHODS.contains(&t.text)) && tokens.get(i + 2).is_some_and(|t| t.is_symbol("(")) {
+            if let Some(url) = literal_url(&tokens, i + 2) {
+                findings.push(Finding {
+                    severity: if tokens[i + 1].text.starts_with("Http") { Severity::High } else { Severity::Medium },
+                    rule: "http-url",
+                    line: Some(token.line),
+                    detail: format!("{} to hard-coded {}", tokens[i + 1].text, url),
+                });
+            }
+        }
+
+        if token.kind == TokenKind::String {
+            if let Some(finding) = string_finding(token) {
+                findings.push(finding);
+            }
+        }
+
+        if token.is_name("string") && tokens.get(i + 1).is_some_and(|t| t.is_symbol(".")) && tokens.get(i + 2).is_some_and(|t| t.is_name("char")) && tokens.get(i + 3).is_some_and(|t| t.is_symbol("(")) {
+            let codes = tokens[i + 4..].iter().take_while(|t| t.kind == TokenKind::Number || t.is_symbol(",")).filter(|t| t.kind == TokenKind::Number).count();
+            if codes >= MIN_CHAR_CODES {
+                findings.push(Finding {
+                    severity: Severity::Medium,
+                    rule: "obfuscated-string",
+                    line: Some(token.line),
+                    detail: format!("string.char with {} character codes", codes),
+                });
+            }
+        }
+    }
+    findings
+}
+
+/// True when the number at `index` is the whole right-hand side of the assignment.
+fn value_is_last_number(tokens: &[Token], index: usize) -> bool {
+    tokens.get(index).is_some_and(|t| t.kind == TokenKind::Number)
+        && tokens.get(index + 1).is_some_and(|t| t.is_symbol(";") || t.kind != TokenKind::Symbol)
+}
+
+/// The first string literal starting with `http://` or `https://` inside the call's parentheses.
+fn literal_url(tokens: &[Token], open: usize) -> Option<String> {
+    let mut depth = 0;
+    for token in &tokens[open..] {
+        if token.is_symbol("(") {
+            depth += 1;
+        } else if token.is_symbol(")") {
+            depth -= 1;
+            if depth == 0 {
+                break;
+            }
+        } else if token.kind == TokenKind::Eof {
+            break;
+        } else if let Some(value) = token.string_value() {
+            if value.starts_with("http://") || value.starts_with("https://") {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+fn string_finding(token: &Token) -> Option<Finding> {
+    let raw = token.text;
+    let bytes = raw.as_bytes();
+    let mut escapes = 0;
+    let mut escaped_bytes = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            let start = i;
+            i += 1;
+            if bytes.get(i).is_some_and(|b| b.is_ascii_digit()) {
+                while i < bytes.len() && i - start <= 3 && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            } else if bytes.get(i) == Some(&b'x') {
+                i += 3;
+            } else {
+                i += 1;
+                continue;
+            }
+            escapes += 1;
+            escaped_bytes += i.min(bytes.len()) - start;
+            continue;
+        }
+        i += 1;
+    }
+    if escapes >= MIN_ESCAPES && escaped_bytes * 2 > raw.len() {
+        return Some(Finding {
+            severity: Severity::Medium,
+            rule: "obfuscated-string",
+            line: Some(token.line),
+            detail: format!("string made of {} numeric escape sequences", escapes),
+        });
+    }
+
+    let value = token.string_value()?;
+    let encoded = value.len() >= MIN_ENCODED_LENGTH
+        && value.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'=' | b'-' | b'_'));
+    encoded.then(|| Finding {
+        severity: Severity::Low,
+        rule: "encoded-blob",
+        line: Some(token.line),
+        detail: format!("{} character string without spaces, looks encoded", value.len()),
+    })
+}
+
+pub fn render_text(report: &AuditReport) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Security audit");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", summary(report));
+    for (rank, script) in report.scripts.iter().enumerate() {
+        let _ = writeln!(out);
+        let _ = writeln!(out, "## {}. {} ({}), score {}", rank + 1, script.path, script.class, script.score);
+        for finding in &script.findings {
+            let location = finding.line.map(|l| format!("line {}", l)).unwrap_or_else(|| "placement".to_string());
+            let _ = writeln!(out, "- [{}] {} `{}`: {}", finding.severity.label(), location, finding.rule, finding.detail);
+        }
+    }
+    out
+}
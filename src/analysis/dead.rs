@@ -0,0 +1,301 @@
+use crate::analysis::deps::{self, is_script_class};
+use crate::dom::Dom;
+use crate::properties::PropertyValue;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+
+/// Where a `Script` with the Legacy run context is executed, either directly or after the engine
+/// copies it into a player, character or `PlayerGui`.
+const LEGACY_SCRIPT_CONTAINERS: &[&str] = &["Workspace", "ServerScriptService", "StarterGui", "StarterPack"];
+
+/// Where a `LocalScript` is executed, again counting the copies made for every player.
+const LOCAL_SCRIPT_CONTAINERS: &[&str] = &["StarterGui", "StarterPack", "ReplicatedFirst"];
+
+/// Folders of `StarterPlayer` whose contents are copied into each player or character.
+const STARTER_PLAYER_FOLDERS: &[&str] = &["StarterPlayerScripts", "StarterCharacterScripts"];
+
+/// Services that are never replicated to clients, so Client run context scripts in them never start.
+const SERVER_ONLY_SERVICES: &[&str] = &["ServerScriptService", "ServerStorage"];
+
+/// `Enum.RunContext` values as stored in the `RunContext` token.
+const RUN_CONTEXT_LEGACY: u32 = 0;
+const RUN_CONTEXT_CLIENT: u32 = 2;
+const RUN_CONTEXT_PLUGIN: u32 = 3;
+
+#[derive(Serialize)]
+pub struct DeadScript {
+    pub path: String,
+    pub class: String,
+    pub rule: &'static str,
+    pub detail: String,
+}
+
+#[derive(Serialize)]
+pub struct DeadScriptReport {
+    pub scripts: usize,
+    pub dead: Vec<DeadScript>,
+    /// `require` calls the dependency graph could not follow; modules they load may be listed as unused.
+    pub unresolved_requires: usize,
+}
+
+fn run_context(dom: &Dom, id: usize) -> u32 {
+    match dom.instances[id].property("RunContext") {
+        Some(PropertyValue::Token(value)) => *value,
+        _ => RUN_CONTEXT_LEGACY,
+    }
+}
+
+fn is_disabled(dom: &Dom, id: usize) -> bool {
+    dom.instances[id].properties.iter().any(|p| match (p.name.as_str(), &p.value) {
+        ("Disabled", PropertyValue::Bool(disabled)) => *disabled,
+        ("Enabled", PropertyValue::Bool(enabled)) => !*enabled,
+        _ => false,
+    })
+}
+
+/// The class of the top-level service and, for `StarterPlayer`, the name of the folder below it.
+fn location(dom: &Dom, id: usize) -> (&str, Option<&str>) {
+    let mut chain = vec![id];
+    while let Some(parent) = dom.instances[*chain.last().unwrap()].parent {
+        chain.push(parent);
+    }
+    let root = &dom.instances[chain[chain.len() - 1]];
+    let folder = (chain.len() >= 3).then(|| dom.instances[chain[chain.len() - 2]].name.as_str());
+    (root.class.as_str(), folder)
+}
+
+/// Why a `Script` or `LocalScript` never starts, if it does not.
+fn never_runs(dom: &Dom, id: usize) -> Option<(&'static str, String)> {
+    let instance = &dom.instances[id];
+    if is_disabled(dom, id) {
+        return Some(("disabled", format!("{} is disabled", instance.class)));
+    }
+    if instance.parent.is_none() {
+        return Some(("not-run-here", format!("{} is not inside any service", instance.class)));
+    }
+    let (service, folder) = location(dom, id);
+    let in_starter_folder = service == "StarterPlayer" && folder.is_some_and(|f| STARTER_PLAYER_FOLDERS.contains(&f));
+
+    match instance.class.as_str() {
+        "Script" => match run_context(dom, id) {
+            RUN_CONTEXT_LEGACY => {
+                let runs = LEGACY_SCRIPT_CONTAINERS.contains(&service) || (service == "StarterPlayer" && folder == Some("StarterCharacterScripts"));
+                (!runs).then(|| ("not-run-here", format!("Script with the Legacy run context does not run in {}", describe(service, folder))))
+            }
+            RUN_CONTEXT_PLUGIN => Some(("plugin-context", "Script with the Plugin run context only runs as a plugin".to_string())),
+            RUN_CONTEXT_CLIENT if SERVER_ONLY_SERVICES.contains(&service) => {
+                Some(("not-run-here", format!("Script with the Client run context does not run in {}, which clients never see", service)))
+            }
+            _ => (service == "ServerStorage").then(|| ("not-run-here", "scripts do not run in ServerStorage".to_string())),
+        },
+        "LocalScript" => {
+            let runs = LOCAL_SCRIPT_CONTAINERS.contains(&service) || in_starter_folder;
+            (!runs).then(|| ("not-run-here", format!("LocalScript does not run in {}", describe(service, folder))))
+        }
+        _ => None,
+    }
+}
+
+fn describe(service: &str, folder: Option<&str>) -> String {
+    match folder {
+        Some(folder) if service == "StarterPlayer" => format!("StarterPlayer.{}", folder),
+        _ => service.to_string(),
+    }
+}
+
+pub fn analyze(dom: &Dom) -> DeadScriptReport {
+    let scripts: Vec<usize> = dom.iter_depth_first().into_iter().filter(|&id| is_script_class(&dom.instances[id].class)).collect();
+    let mut dead: BTreeMap<usize, (&'static str, String)> = BTreeMap::new();
+
+    let mut live_roots = Vec::new();
+    for &id in &scripts {
+        if dom.instances[id].class == "ModuleScript" {
+            continue;
+        }
+        match never_runs(dom, id) {
+            Some(reason) => {
+                dead.insert(id, reason);
+            }
+            None => live_roots.push(id),
+        }
+    }
+
+    // A module is live when a running script requires it, directly or through other modules.
+    let graph = deps::analyze(dom);
+    let mut requires: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut required = HashSet::new();
+    for edge in &graph.edges {
+        requires.entry(edge.from).or_default().push(edge.to);
+        required.insert(edge.to);
+    }
+    let mut live: HashSet<usize> = live_roots.iter().copied().collect();
+    let mut stack = live_roots;
+    while let Some(id) = stack.pop() {
+        for &module in requires.get(&id).into_iter().flatten() {
+            if live.insert(module) {
+                stack.push(module);
+            }
+        }
+    }
+    for &id in &scripts {
+        if dom.instances[id].class != "ModuleScript" || live.contains(&id) {
+            continue;
+        }
+        let detail = if required.contains(&id) { "only required by scripts that never run" } else { "never required by any script" };
+        dead.insert(id, ("unused-module", detail.to_string()));
+    }
+
+    let order: HashMap<usize, usize> = scripts.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+    let mut dead: Vec<(usize, &'static str, String)> = dead.into_iter().map(|(id, (rule, detail))| (id, rule, detail)).collect();
+    dead.sort_by_key(|(id, rule, _)| (*rule, order[id]));
+
+    DeadScriptReport {
+        scripts: scripts.len(),
+        dead: dead.into_iter()
+            .map(|(id, rule, detail)| DeadScript { path: dom.path(id), class: dom.instances[id].class.clone(), rule, detail })
+            .collect(),
+        unresolved_requires: graph.unresolved.len(),
+    }
+}
+
+pub fn summary(report: &DeadScriptReport) -> String {
+    let count = |rule: &str| report.dead.iter().filter(|d| d.rule == rule).count();
+    format!(
+        "Dead scripts: {} of {} scripts never run ({} misplaced, {} disabled, {} plugin-only, {} unused modules; {} unresolved requires)",
+        report.dead.len(),
+        report.scripts,
+        count("not-run-here"),
+        count("disabled"),
+        count("plugin-context"),
+        count("unused-module"),
+        report.unresolved_requires
+    )
+}
+
+pub fn render_text(report: &DeadScriptReport) -> String {
+    let mut out = String::new();
+    let mut current = None;
+    for script in &report.dead {
+        if current != Some(script.rule) {
+            if current.is_some() {
+                let _ = writeln!(out);
+            }
+            let _ = writeln!(out, "# {}", script.rule);
+            current = Some(script.rule);
+        }
+        let _ = writeln!(out, "{} ({}): {}", script.path, script.class, script.detail);
+    }
+    if report.unresolved_requires > 0 {
+        let _ = writeln!(out, "\n{} require calls could not be resolved; modules loaded by them may be listed as unused.", report.unresolved_requires);
+    }
+    let _ = writeln!(out, "\n{}", summary(report));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(class: &str, name: &str, properties: &str, source: &str) -> String {
+        format!(r#"<Item class="{class}" referent="{name}"><Properties><string name="Name">{name}</string>{properties}<ProtectedString name="Source">{source}</ProtectedString></Properties></Item>"#)
+    }
+
+    fn service(class: &str, children: &[String]) -> String {
+        format!(r#"<Item class="{class}" referent="{class}"><Properties><string name="Name">{class}</string></Properties>{}</Item>"#, children.concat())
+    }
+
+    fn context(value: u32) -> String {
+        format!(r#"<token name="RunContext">{value}</token>"#)
+    }
+
+    /// The rule and detail of every dead script by path.
+    fn dead(services: &[String]) -> BTreeMap<String, (&'static str, String)> {
+        let dom = Dom::parse(&format!(r#"<roblox version="4">{}</roblox>"#, services.concat()));
+        analyze(&dom).dead.into_iter().map(|d| (d.path, (d.rule, d.detail))).collect()
+    }
+
+    fn rules(services: &[String]) -> Vec<(String, &'static str)> {
+        dead(services).into_iter().map(|(path, (rule, _))| (path, rule)).collect()
+    }
+
+    #[test]
+    fn legacy_scripts_run_in_their_containers() {
+        let dead = rules(&[
+            service("Workspace", &[script("Script", "Live", "", ""), script("LocalScript", "InWorld", "", "")]),
+            service("ServerScriptService", &[script("Script", "Live", "", "")]),
+            service("ReplicatedStorage", &[script("Script", "Stored", "", ""), script("LocalScript", "StoredLocal", "", "")]),
+            service("ReplicatedFirst", &[script("LocalScript", "Loading", "", "")]),
+            service("StarterGui", &[script("LocalScript", "Menu", "", "")]),
+        ]);
+        assert_eq!(dead, [
+            ("ReplicatedStorage.Stored".to_string(), "not-run-here"),
+            ("ReplicatedStorage.StoredLocal".to_string(), "not-run-here"),
+            ("Workspace.InWorld".to_string(), "not-run-here"),
+        ]);
+    }
+
+    #[test]
+    fn starter_player_folders() {
+        let folder = |name: &str, children: &[String]| {
+            format!(r#"<Item class="Folder" referent="{name}"><Properties><string name="Name">{name}</string></Properties>{}</Item>"#, children.concat())
+        };
+        let dead = dead(&[service("StarterPlayer", &[
+            folder("StarterPlayerScripts", &[script("LocalScript", "Camera", "", ""), script("Script", "Server", "", "")]),
+            folder("StarterCharacterScripts", &[script("LocalScript", "Animate", "", ""), script("Script", "Health", "", "")]),
+            script("LocalScript", "Loose", "", ""),
+        ])]);
+        let paths: Vec<&str> = dead.keys().map(String::as_str).collect();
+        assert_eq!(paths, ["StarterPlayer.Loose", "StarterPlayer.StarterPlayerScripts.Server"]);
+        assert_eq!(dead["StarterPlayer.StarterPlayerScripts.Server"].1, "Script with the Legacy run context does not run in StarterPlayer.StarterPlayerScripts");
+    }
+
+    #[test]
+    fn run_contexts() {
+        let dead = dead(&[
+            service("ServerScriptService", &[script("Script", "Client", &context(RUN_CONTEXT_CLIENT), ""), script("Script", "Server", &context(1), "")]),
+            service("ServerStorage", &[script("Script", "Server", &context(1), "")]),
+            service("ReplicatedStorage", &[script("Script", "Client", &context(RUN_CONTEXT_CLIENT), ""), script("Script", "Plugin", &context(RUN_CONTEXT_PLUGIN), "")]),
+        ]);
+        let rules: Vec<(&str, &str)> = dead.iter().map(|(path, (rule, _))| (path.as_str(), *rule)).collect();
+        assert_eq!(rules, [
+            ("ReplicatedStorage.Plugin", "plugin-context"),
+            ("ServerScriptService.Client", "not-run-here"),
+            ("ServerStorage.Server", "not-run-here"),
+        ]);
+        assert!(dead["ServerScriptService.Client"].1.contains("Client run context"));
+    }
+
+    #[test]
+    fn disabled_scripts() {
+        let dead = rules(&[service("ServerScriptService", &[
+            script("Script", "Disabled", r#"<bool name="Disabled">true</bool>"#, ""),
+            script("Script", "NotEnabled", r#"<bool name="Enabled">false</bool>"#, ""),
+            script("Script", "Enabled", r#"<bool name="Enabled">true</bool>"#, ""),
+        ])]);
+        assert_eq!(dead, [
+            ("ServerScriptService.Disabled".to_string(), "disabled"),
+            ("ServerScriptService.NotEnabled".to_string(), "disabled"),
+        ]);
+    }
+
+    #[test]
+    fn modules_follow_the_scripts_that_require_them() {
+        let dead = dead(&[
+            service("ReplicatedStorage", &[
+                script("ModuleScript", "Used", "", "return require(game.ReplicatedStorage.Nested)"),
+                script("ModuleScript", "Nested", "", "return {}"),
+                script("ModuleScript", "OnlyDead", "", "return {}"),
+                script("ModuleScript", "Unused", "", "return {}"),
+            ]),
+            service("ServerScriptService", &[script("Script", "Main", "", "require(game.ReplicatedStorage.Used)")]),
+            service("ServerStorage", &[script("Script", "Old", "", "require(game.ReplicatedStorage.OnlyDead)")]),
+        ]);
+        let details: Vec<(&str, &str)> = dead.iter().map(|(path, (_, detail))| (path.as_str(), detail.as_str())).collect();
+        assert_eq!(details, [
+            ("ReplicatedStorage.OnlyDead", "only required by scripts that never run"),
+            ("ReplicatedStorage.Unused", "never required by any script"),
+            ("ServerStorage.Old", "Script with the Legacy run context does not run in ServerStorage"),
+        ]);
+    }
+}
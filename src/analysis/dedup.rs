@@ -0,0 +1,233 @@
+use crate::analysis::deps::is_script_class;
+use crate::dom::Dom;
+use crate::luau::lexer::{tokenize, TokenKind};
+use crate::path_policy::fnv1a;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Folder at the output root holding sources shared by several scripts.
+pub const SOURCES_DIR: &str = "sources";
+/// Written instead of `script.lua` when the source lives in [`SOURCES_DIR`]; holds its relative path.
+pub const SCRIPT_REF_FILE: &str = "script.ref";
+
+#[derive(Serialize)]
+pub struct Cluster {
+    pub hash: String,
+    pub lines: usize,
+    pub bytes: usize,
+    /// Number of distinct exact sources in the cluster; always 1 for exact clusters.
+    pub variants: usize,
+    pub paths: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DedupReport {
+    pub scripts: usize,
+    pub unique_exact: usize,
+    pub unique_normalized: usize,
+    /// Scripts with byte-identical sources.
+    pub exact: Vec<Cluster>,
+    /// Scripts that differ only in whitespace and comments.
+    pub normalized: Vec<Cluster>,
+}
+
+pub fn content_hash(source: &str) -> String {
+    format!("{:016x}", fnv1a(source.as_bytes()))
+}
+
+/// The source with comments dropped and every run of whitespace collapsed, so formatting-only
+/// edits compare equal. Whitespace inside strings is kept.
+pub fn normalize(source: &str) -> String {
+    let mut out = String::with_capacity(source.len());
+    for token in tokenize(source) {
+        if token.kind == TokenKind::Eof {
+            break;
+        }
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(token.text);
+    }
+    out
+}
+
+pub fn analyze(dom: &Dom) -> DedupReport {
+    let scripts: Vec<(usize, &str)> = dom.iter_depth_first().into_iter()
+        .filter(|&id| is_script_class(&dom.instances[id].class))
+        .map(|id| (id, dom.instances[id].source.as_deref().unwrap_or("")))
+        .collect();
+
+    let mut exact: HashMap<&str, Vec<usize>> = HashMap::new();
+    for &(id, source) in &scripts {
+        exact.entry(source).or_default().push(id);
+    }
+
+    // Sources in document order, so a cluster is described by the first script that uses it.
+    let mut normalized: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut seen = HashSet::new();
+    for &(_, source) in &scripts {
+        if seen.insert(source) {
+            normalized.entry(normalize(source)).or_default().push(source);
+        }
+    }
+
+    let mut report = DedupReport {
+        scripts: scripts.len(),
+        unique_exact: exact.len(),
+        unique_normalized: normalized.len(),
+        exact: Vec::new(),
+        normalized: Vec::new(),
+    };
+
+    for (source, ids) in &exact {
+        if ids.len() > 1 {
+            report.exact.push(cluster(dom, content_hash(source), source, 1, ids.clone()));
+        }
+    }
+    for (key, sources) in &normalized {
+        if sources.len() > 1 {
+            let mut ids: Vec<usize> = sources.iter().flat_map(|s| exact[s].iter().copied()).collect();
+            ids.sort_unstable();
+            report.normalized.push(cluster(dom, content_hash(key), sources[0], sources.len(), ids));
+        }
+    }
+
+    for clusters in [&mut report.exact, &mut report.normalized] {
+        clusters.sort_by(|a, b| b.paths.len().cmp(&a.paths.len()).then_with(|| a.paths[0].cmp(&b.paths[0])));
+    }
+    report
+}
+
+fn cluster(dom: &Dom, hash: String, source: &str, variants: usize, ids: Vec<usize>) -> Cluster {
+    Cluster {
+        hash,
+        lines: source.lines().count(),
+        bytes: source.len(),
+        variants,
+        paths: ids.into_iter().map(|id| dom.path(id)).collect(),
+    }
+}
+
+pub fn summary(report: &DedupReport) -> String {
+    let copies: usize = report.exact.iter().map(|c| c.paths.len() - 1).sum();
+    format!(
+        "Duplicates: {} scripts, {} unique sources ({} ignoring formatting), {} exact copies in {} clusters",
+        report.scripts,
+        report.unique_exact,
+        report.unique_normalized,
+        copies,
+        report.exact.len()
+    )
+}
+
+pub fn render_text(report: &DedupReport) -> String {
+    let mut out = String::new();
+    let sections = [
+        ("Exact copies", &report.exact),
+        ("Same after removing whitespace and comments", &report.normalized),
+    ];
+    for (title, clusters) in sections {
+        let _ = writeln!(out, "# {}", title);
+        for cluster in clusters {
+            let _ = write!(out, "\n{}: {} scripts, {} lines", cluster.hash, cluster.paths.len(), cluster.lines);
+            if cluster.variants > 1 {
+                let _ = write!(out, ", {} variants", cluster.variants);
+            }
+            let _ = writeln!(out);
+            for path in &cluster.paths {
+                let _ = writeln!(out, "  {}", path);
+            }
+        }
+        let _ = writeln!(out);
+    }
+    let _ = writeln!(out, "{}", summary(report));
+    out
+}
+
+/// Sources used by more than one script, written once under [`SOURCES_DIR`] during extraction.
+pub struct SharedSources {
+    files: HashMap<String, PathBuf>,
+    written: Mutex<HashSet<PathBuf>>,
+}
+
+impl SharedSources {
+    pub fn collect(dom: &Dom) -> Self {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for instance in &dom.instances {
+            if let Some(source) = &instance.source {
+                *counts.entry(source).or_default() += 1;
+            }
+        }
+
+        let mut shared: Vec<&str> = counts.into_iter().filter(|&(_, n)| n > 1).map(|(s, _)| s).collect();
+        shared.sort_unstable();
+        let mut taken = HashSet::new();
+        let mut files = HashMap::new();
+        for source in shared {
+            // Distinct sources with the same hash get a numbered suffix.
+            let hash = content_hash(source);
+            let mut name = format!("{}.lua", hash);
+            let mut n = 2;
+            while !taken.insert(name.clone()) {
+                name = format!("{}~{}.lua", hash, n);
+                n += 1;
+            }
+            files.insert(source.to_string(), Path::new(SOURCES_DIR).join(name));
+        }
+        Self { files, written: Mutex::new(HashSet::new()) }
+    }
+
+    /// The shared file for `source`, or `None` if only one script uses it. The second value is
+    /// true for the first caller, who is responsible for writing the file.
+    pub fn claim(&self, source: &str) -> Option<(&Path, bool)> {
+        let path = self.files.get(source)?;
+        let first = self.written.lock().unwrap().insert(path.clone());
+        Some((path, first))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn place(sources: &[&str]) -> Dom {
+        let scripts: String = sources.iter().enumerate()
+            .map(|(i, source)| format!(r#"<Item class="Script" referent="S{i}"><Properties><string name="Name">S{i}</string><ProtectedString name="Source">{source}</ProtectedString></Properties></Item>"#))
+            .collect();
+        Dom::parse(&format!(r#"<roblox version="4"><Item class="ServerScriptService" referent="R"><Properties><string name="Name">SSS</string></Properties>{}</Item></roblox>"#, scripts))
+    }
+
+    #[test]
+    fn normalize_drops_comments_and_formatting() {
+        assert_eq!(normalize("local  x=1 -- one\n\n--[[ block ]]return x"), normalize("local x = 1\nreturn x"));
+        assert_ne!(normalize("print('a  b')"), normalize("print('a b')"));
+    }
+
+    #[test]
+    fn finds_exact_and_normalized_copies() {
+        let report = analyze(&place(&["print(1)", "print(1)", "print( 1 )", "print(2)"]));
+        assert_eq!((report.scripts, report.unique_exact, report.unique_normalized), (4, 3, 2));
+        assert_eq!(report.exact[0].paths, vec!["SSS.S0", "SSS.S1"]);
+        assert_eq!(report.normalized[0].paths, vec!["SSS.S0", "SSS.S1", "SSS.S2"]);
+        assert_eq!(report.normalized[0].variants, 2);
+    }
+
+    #[test]
+    fn normalized_cluster_is_described_by_its_first_script() {
+        let long = "local x = 1\n\n\nprint(x)\n";
+        let short = "local x = 1 print(x)";
+        for _ in 0..10 {
+            let report = analyze(&place(&[long, short]));
+            assert_eq!((report.normalized[0].lines, report.normalized[0].bytes), (4, long.len()));
+            let report = analyze(&place(&[short, long]));
+            assert_eq!((report.normalized[0].lines, report.normalized[0].bytes), (1, short.len()));
+        }
+    }
+}
@@ -0,0 +1,272 @@
+use crate::analysis::refs::{matching_paren, parse_chain, resolve, span, Aliases, Base, Chain, Step};
+use crate::dom::Dom;
+use crate::luau::lexer::{tokenize, TokenKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+#[derive(Serialize)]
+pub struct GraphNode {
+    pub id: usize,
+    pub path: String,
+    pub class: String,
+}
+
+#[derive(Serialize)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub line: usize,
+}
+
+#[derive(Serialize)]
+pub struct UnresolvedRequire {
+    pub from: usize,
+    pub line: usize,
+    pub expression: String,
+    pub reason: String,
+}
+
+/// Scripts and the modules they `require`, keyed by instance id. `cycles` lists groups of
+/// modules that require each other, directly or through other modules.
+#[derive(Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub unresolved: Vec<UnresolvedRequire>,
+    pub cycles: Vec<Vec<usize>>,
+}
+
+pub fn is_script_class(class: &str) -> bool {
+    matches!(class, "Script" | "LocalScript" | "ModuleScript")
+}
+
+pub fn analyze(dom: &Dom) -> DependencyGraph {
+    let mut graph = DependencyGraph { nodes: Vec::new(), edges: Vec::new(), unresolved: Vec::new(), cycles: Vec::new() };
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        let Some(source) = &instance.source else { continue };
+        if !is_script_class(&instance.class) {
+            continue;
+        }
+        graph.nodes.push(GraphNode { id, path: dom.path(id), class: instance.class.clone() });
+
+        for require in find_requires(source) {
+            match require.target {
+                RequireTarget::Chain(chain) => match resolve(dom, id, &chain) {
+                    Ok(target) if dom.instances[target].class == "ModuleScript" => {
+                        graph.edges.push(GraphEdge { from: id, to: target, line: require.line });
+                    }
+                    Ok(target) => graph.unresolved.push(UnresolvedRequire {
+                        from: id,
+                        line: require.line,
+                        expression: require.expression,
+                        reason: format!("{} is a {}, not a ModuleScript", dom.path(target), dom.instances[target].class),
+                    }),
+                    Err(reason) => graph.unresolved.push(UnresolvedRequire { from: id, line: require.line, expression: require.expression, reason }),
+                },
+                RequireTarget::Unsupported(reason) => {
+                    graph.unresolved.push(UnresolvedRequire { from: id, line: require.line, expression: require.expression, reason });
+                }
+            }
+        }
+    }
+
+    graph.cycles = find_cycles(dom, &graph);
+    graph
+}
+
+pub fn summary(graph: &DependencyGraph) -> String {
+    format!(
+        "Dependencies: {} scripts, {} requires, {} unresolved, {} cycles",
+        graph.nodes.len(),
+        graph.edges.len(),
+        graph.unresolved.len(),
+        graph.cycles.len()
+    )
+}
+
+enum RequireTarget {
+    Chain(Chain),
+    Unsupported(String),
+}
+
+struct Require {
+    line: usize,
+    expression: String,
+    target: RequireTarget,
+}
+
+fn find_requires(source: &str) -> Vec<Require> {
+    let tokens = tokenize(source);
+    let mut aliases = Aliases::default();
+    let mut requires = Vec::new();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+
+        // `local Name = <instance chain>` lets later requires go through the alias.
+        if let Some(next) = aliases.observe(&tokens, i) {
+            i = next;
+            continue;
+        }
+
+        if token.is_name("require") && tokens.get(i + 1).is_some_and(|t| t.is_symbol("(")) {
+            let close = matching_paren(&tokens, i + 1);
+            let expression = match close {
+                Some(close) if close > i + 2 => span(source, &tokens[i + 2], &tokens[close - 1]),
+                _ => String::new(),
+            };
+            let arg = &tokens[i + 2];
+            let target = if arg.kind == TokenKind::Number && close == Some(i + 3) {
+                RequireTarget::Unsupported(format!("asset id {} is loaded from the website", arg.text))
+            } else if arg.kind == TokenKind::String && close == Some(i + 3) {
+                match string_chain(&arg.string_value().unwrap_or_default()) {
+                    Some(chain) => RequireTarget::Chain(chain),
+                    None => RequireTarget::Unsupported("unsupported require path string".to_string()),
+                }
+            } else {
+                match parse_chain(&tokens, i + 2, &aliases) {
+                    Some((chain, next)) if Some(next) == close => RequireTarget::Chain(chain),
+                    _ => RequireTarget::Unsupported("dynamic expression".to_string()),
+                }
+            };
+            requires.push(Require { line: token.line, expression, target });
+        }
+
+        i += 1;
+    }
+    requires
+}
+
+/// `require("./Sibling")` and `require("../Other/Module")` are relative to the requiring script.
+fn string_chain(path: &str) -> Option<Chain> {
+    let mut steps = Vec::new();
+    let mut segments = path.split('/');
+    match segments.next()? {
+        "@self" => {}
+        "." => steps.push(Step::Parent),
+        ".." => {
+            steps.push(Step::Parent);
+            steps.push(Step::Parent);
+        }
+        _ => return None,
+    }
+    for segment in segments {
+        match segment {
+            "" | "." => {}
+            ".." => steps.push(Step::Parent),
+            name => steps.push(Step::Child(name.to_string())),
+        }
+    }
+    Some(Chain { base: Base::Script, steps })
+}
+
+fn find_cycles(dom: &Dom, graph: &DependencyGraph) -> Vec<Vec<usize>> {
+    let mut adjacency: HashMap<usize, Vec<usize>> = HashMap::new();
+    for edge in &graph.edges {
+        adjacency.entry(edge.from).or_default().push(edge.to);
+    }
+
+    struct Tarjan<'a> {
+        adjacency: &'a HashMap<usize, Vec<usize>>,
+        index: HashMap<usize, usize>,
+        low: HashMap<usize, usize>,
+        on_stack: HashMap<usize, bool>,
+        stack: Vec<usize>,
+        next: usize,
+        components: Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, v: usize) {
+            self.index.insert(v, self.next);
+            self.low.insert(v, self.next);
+            self.next += 1;
+            self.stack.push(v);
+            self.on_stack.insert(v, true);
+
+            for &w in self.adjacency.get(&v).map(|v| v.as_slice()).unwrap_or(&[]) {
+                if !self.index.contains_key(&w) {
+                    self.visit(w);
+                    let low = self.low[&v].min(self.low[&w]);
+                    self.low.insert(v, low);
+                } else if self.on_stack.get(&w).copied().unwrap_or(false) {
+                    let low = self.low[&v].min(self.index[&w]);
+                    self.low.insert(v, low);
+                }
+            }
+
+            if self.low[&v] == self.index[&v] {
+                let mut component = Vec::new();
+                while let Some(w) = self.stack.pop() {
+                    self.on_stack.insert(w, false);
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                self.components.push(component);
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        adjacency: &adjacency,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        on_stack: HashMap::new(),
+        stack: Vec::new(),
+        next: 0,
+        components: Vec::new(),
+    };
+    for node in &graph.nodes {
+        if !tarjan.index.contains_key(&node.id) {
+            tarjan.visit(node.id);
+        }
+    }
+
+    let mut cycles: Vec<Vec<usize>> = tarjan.components.into_iter()
+        .filter(|c| c.len() > 1 || adjacency.get(&c[0]).is_some_and(|targets| targets.contains(&c[0])))
+        .collect();
+    for cycle in &mut cycles {
+        cycle.sort_by_key(|&id| dom.path(id));
+    }
+    cycles.sort_by_key(|c| dom.path(c[0]));
+    cycles
+}
+
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn to_dot(graph: &DependencyGraph) -> String {
+    let in_cycle: std::collections::HashSet<usize> = graph.cycles.iter().flatten().copied().collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph dependencies {{");
+    let _ = writeln!(out, "  rankdir=LR;");
+    let _ = writeln!(out, "  node [shape=box, fontname=\"Helvetica\"];");
+    for node in &graph.nodes {
+        let shape = if node.class == "ModuleScript" { "box" } else { "ellipse" };
+        let color = if in_cycle.contains(&node.id) { ", color=red" } else { "" };
+        let _ = writeln!(out, "  n{} [label=\"{}\", shape={}{}];", node.id, dot_escape(&node.path), shape, color);
+    }
+    for edge in &graph.edges {
+        let color = if in_cycle.contains(&edge.from) && in_cycle.contains(&edge.to) { ", color=red" } else { "" };
+        let _ = writeln!(out, "  n{} -> n{} [label=\"{}\"{}];", edge.from, edge.to, edge.line, color);
+    }
+    for (i, unresolved) in graph.unresolved.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "  u{} [label=\"{}\", shape=note, style=dashed, color=gray, tooltip=\"{}\"];",
+            i,
+            dot_escape(&unresolved.expression),
+            dot_escape(&unresolved.reason)
+        );
+        let _ = writeln!(out, "  n{} -> u{} [label=\"{}\", style=dashed, color=orange];", unresolved.from, i, unresolved.line);
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
@@ -0,0 +1,136 @@
+pub mod assets;
+pub mod audit;
+pub mod dead;
+pub mod dedup;
+pub mod deps;
+pub mod packages;
+pub mod refs;
+pub mod remotes;
+pub mod sounds;
+pub mod stats;
+pub mod syntax;
+
+use crate::dom::Dom;
+use crate::sink::OutputSink;
+use std::path::Path;
+
+pub const REPORTS_DIR: &str = "reports";
+
+/// Analyses that run after an extraction and write their results into `reports/` of the output.
+#[derive(Clone, Default)]
+pub struct ReportSettings {
+    pub dependencies: bool,
+    pub audit: bool,
+    pub assets: bool,
+    pub sounds: bool,
+    pub duplicates: bool,
+    pub remotes: bool,
+    pub packages: bool,
+    pub dead_scripts: bool,
+    pub syntax: bool,
+    pub stats: bool,
+    /// Also write `reports/ast/<script>.json` for every script that parsed.
+    pub syntax_ast: bool,
+}
+
+impl ReportSettings {
+    pub fn any(&self) -> bool {
+        self.dependencies || self.audit || self.assets || self.sounds || self.duplicates || self.remotes || self.packages || self.dead_scripts || self.syntax || self.stats
+    }
+}
+
+/// What the finished screen shows about the written reports.
+#[derive(Default)]
+pub struct ReportResults {
+    /// One line per report.
+    pub summaries: Vec<String>,
+    pub stats: Option<stats::PlaceStats>,
+}
+
+/// Writes every enabled report into `reports/` of the output.
+pub fn write_reports(dom: &Dom, reports: &ReportSettings, sink: &dyn OutputSink) -> anyhow::Result<ReportResults> {
+    let dir = Path::new(REPORTS_DIR);
+    sink.create_dir(dir)?;
+    let mut summaries = Vec::new();
+
+    if reports.dependencies {
+        let graph = deps::analyze(dom);
+        sink.write_file(&dir.join("dependencies.json"), &serde_json::to_vec_pretty(&graph)?)?;
+        sink.write_file(&dir.join("dependencies.dot"), deps::to_dot(&graph).as_bytes())?;
+        summaries.push(deps::summary(&graph));
+    }
+
+    if reports.audit {
+        let report = audit::audit(dom);
+        sink.write_file(&dir.join("audit.json"), &serde_json::to_vec_pretty(&report)?)?;
+        sink.write_file(&dir.join("audit.md"), audit::render_text(&report).as_bytes())?;
+        summaries.push(audit::summary(&report));
+    }
+
+    if reports.assets {
+        let inventory = assets::analyze(dom);
+        sink.write_file(&dir.join("assets.json"), &serde_json::to_vec_pretty(&inventory)?)?;
+        sink.write_file(&dir.join("assets.csv"), assets::to_csv(&inventory).as_bytes())?;
+        summaries.push(assets::summary(&inventory));
+    }
+
+    if reports.sounds {
+        let manifest = sounds::analyze(dom);
+        sink.write_file(&dir.join("sounds.json"), &serde_json::to_vec_pretty(&manifest)?)?;
+        sink.write_file(&dir.join("sounds.csv"), sounds::to_csv(&manifest).as_bytes())?;
+        summaries.push(sounds::summary(&manifest));
+    }
+
+    if reports.duplicates {
+        let report = dedup::analyze(dom);
+        sink.write_file(&dir.join("duplicates.json"), &serde_json::to_vec_pretty(&report)?)?;
+        sink.write_file(&dir.join("duplicates.txt"), dedup::render_text(&report).as_bytes())?;
+        summaries.push(dedup::summary(&report));
+    }
+
+    if reports.remotes {
+        let report = remotes::analyze(dom);
+        sink.write_file(&dir.join("remotes.json"), &serde_json::to_vec_pretty(&report)?)?;
+        sink.write_file(&dir.join("remotes.txt"), remotes::render_text(&report).as_bytes())?;
+        summaries.push(remotes::summary(&report));
+    }
+
+    if reports.packages {
+        let report = packages::analyze(dom);
+        sink.write_file(&dir.join("packages.json"), &serde_json::to_vec_pretty(&report)?)?;
+        sink.write_file(&dir.join("packages.txt"), packages::render_text(&report).as_bytes())?;
+        summaries.push(packages::summary(&report));
+        summaries.extend(report.warnings.iter().map(|w| format!("Warning: package {}", w)));
+    }
+
+    if reports.dead_scripts {
+        let report = dead::analyze(dom);
+        sink.write_file(&dir.join("dead_scripts.json"), &serde_json::to_vec_pretty(&report)?)?;
+        sink.write_file(&dir.join("dead_scripts.txt"), dead::render_text(&report).as_bytes())?;
+        summaries.push(dead::summary(&report));
+    }
+
+    if reports.syntax {
+        let (report, parsed) = syntax::check(dom, reports.syntax_ast)?;
+        sink.write_file(&dir.join("syntax.json"), &serde_json::to_vec_pretty(&report)?)?;
+        if !parsed.is_empty() {
+            let ast_dir = dir.join(syntax::AST_DIR);
+            sink.create_dir(&ast_dir)?;
+            for (script, name) in parsed.iter().zip(syntax::ast_file_names(&parsed)) {
+                sink.write_file(&ast_dir.join(name), &script.ast_json)?;
+            }
+        }
+        summaries.push(syntax::summary(&report));
+    }
+
+    let mut place_stats = None;
+    if reports.stats {
+        let place = stats::analyze(dom);
+        sink.write_file(&dir.join("stats.json"), &serde_json::to_vec_pretty(&place)?)?;
+        sink.write_file(&dir.join("stats.md"), stats::render_markdown(&place).as_bytes())?;
+        summaries.push(stats::summary(&place));
+        place_stats = Some(place);
+    }
+
+    Ok(ReportResults { summaries, stats: place_stats })
+}
@@ -0,0 +1,394 @@
+use crate::analysis::assets::asset_ids;
+use crate::dom::Dom;
+use crate::export::geometry::{cframe, Transform};
+use crate::path_policy::{fnv1a, PathPolicy};
+use crate::properties::PropertyValue;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// Folder at the output root holding one copy of every package.
+pub const PACKAGES_DIR: &str = "packages";
+/// Written next to `properties.json` of a package root whose children live in [`PACKAGES_DIR`]; holds its relative path.
+pub const PACKAGE_REF_FILE: &str = "package.ref";
+/// Properties that differ between copies of a package without anyone editing it.
+const VOLATILE_PROPERTIES: &[&str] = &["UniqueId", "HistoryId", "SourceAssetId"];
+
+#[derive(Serialize)]
+pub struct PackageUsage {
+    pub path: String,
+    /// Which of the package's distinct contents this copy has, starting at 1.
+    pub variant: usize,
+}
+
+#[derive(Serialize)]
+pub struct Package {
+    pub name: String,
+    pub package_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    pub auto_update: bool,
+    pub instances: usize,
+    /// Number of distinct contents among the copies; more than one means some copy was edited.
+    pub variants: usize,
+    pub usages: Vec<PackageUsage>,
+}
+
+#[derive(Serialize)]
+pub struct LinkedScript {
+    pub path: String,
+    pub linked_source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct PackageReport {
+    pub packages: Vec<Package>,
+    pub linked_scripts: Vec<LinkedScript>,
+    pub warnings: Vec<String>,
+}
+
+/// Copies of one package version, in tree order.
+struct PackageGroup {
+    package_id: String,
+    version: Option<i64>,
+    auto_update: bool,
+    /// Package roots with the variant of their contents, starting at 0.
+    usages: Vec<(usize, usize)>,
+    variants: usize,
+}
+
+fn package_link(dom: &Dom, id: usize) -> Option<usize> {
+    dom.instances[id].children.iter().copied().find(|&c| dom.instances[c].class == "PackageLink")
+}
+
+/// The frame the package's CFrames are compared in, so copies placed elsewhere still match.
+fn reference_frame(dom: &Dom, root: usize) -> Option<Transform> {
+    let instance = &dom.instances[root];
+    if let Some(PropertyValue::CFrame { position, orientation }) = instance.property("WorldPivotData") {
+        return Some(Transform { position: *position, rotation: *orientation });
+    }
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        if let Some(transform) = cframe(&dom.instances[id]) {
+            return Some(transform);
+        }
+        stack.extend(dom.instances[id].children.iter().rev());
+    }
+    None
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0 + 0.0
+}
+
+/// A hash of everything in the package that an edit could change: classes, names, properties and sources.
+fn fingerprint(dom: &Dom, root: usize) -> u64 {
+    let to_local = reference_frame(dom, root).map(|frame| frame.inverse());
+    let mut text = String::new();
+    let mut stack = vec![(root, 0)];
+    while let Some((id, depth)) = stack.pop() {
+        let instance = &dom.instances[id];
+        if instance.class == "PackageLink" {
+            continue;
+        }
+        stack.extend(instance.children.iter().rev().map(|&c| (c, depth + 1)));
+        let _ = writeln!(text, "{} {} {}", depth, instance.class, instance.name);
+        for property in &instance.properties {
+            if VOLATILE_PROPERTIES.contains(&property.name.as_str()) || matches!(property.value, PropertyValue::Ref(_)) {
+                continue;
+            }
+            let value = match (&property.value, &to_local) {
+                (PropertyValue::CFrame { position, orientation }, Some(to_local)) => {
+                    let local = to_local.then(&Transform { position: *position, rotation: *orientation });
+                    PropertyValue::CFrame { position: local.position.map(round), orientation: local.rotation.map(|row| row.map(round)) }
+                }
+                (value, _) => value.clone(),
+            };
+            let _ = writeln!(text, "  {}={}", property.name, serde_json::to_string(&value).unwrap_or_default());
+        }
+        if let Some(source) = &instance.source {
+            let _ = writeln!(text, "  source={}", source);
+        }
+    }
+    fnv1a(text.as_bytes())
+}
+
+fn groups(dom: &Dom) -> Vec<PackageGroup> {
+    let mut groups: Vec<PackageGroup> = Vec::new();
+    let mut fingerprints: Vec<Vec<u64>> = Vec::new();
+    for id in dom.iter_depth_first() {
+        let Some(link) = package_link(dom, id) else { continue };
+        let link = &dom.instances[link];
+        // Place files store the link under its serialized names.
+        let property = |names: &[&str]| names.iter().find_map(|name| link.property(name));
+        let package_id = match property(&["PackageId", "PackageIdSerialize"]) {
+            Some(PropertyValue::Content(Some(url)) | PropertyValue::String(url)) => url.clone(),
+            _ => String::new(),
+        };
+        let version = match property(&["VersionNumber", "VersionIdSerialize"]) {
+            Some(PropertyValue::Int(version)) => Some(*version),
+            _ => None,
+        };
+        let auto_update = matches!(link.property("AutoUpdate"), Some(PropertyValue::Bool(true)));
+
+        let index = match groups.iter().position(|g| g.package_id == package_id && g.version == version) {
+            Some(index) => index,
+            None => {
+                groups.push(PackageGroup { package_id, version, auto_update, usages: Vec::new(), variants: 0 });
+                fingerprints.push(Vec::new());
+                groups.len() - 1
+            }
+        };
+        let hash = fingerprint(dom, id);
+        let known = &mut fingerprints[index];
+        let variant = known.iter().position(|&h| h == hash).unwrap_or_else(|| {
+            known.push(hash);
+            known.len() - 1
+        });
+        let group = &mut groups[index];
+        group.auto_update |= auto_update;
+        group.variants = known.len();
+        group.usages.push((id, variant));
+    }
+    groups
+}
+
+fn subtree_size(dom: &Dom, root: usize) -> usize {
+    1 + dom.instances[root].children.iter().map(|&c| subtree_size(dom, c)).sum::<usize>()
+}
+
+fn label(package: &Package) -> String {
+    match package.version {
+        Some(version) => format!("{} ({} v{})", package.name, package.package_id, version),
+        None => format!("{} ({})", package.name, package.package_id),
+    }
+}
+
+fn package(dom: &Dom, group: &PackageGroup) -> Package {
+    let (first, _) = group.usages[0];
+    Package {
+        name: dom.instances[first].name.clone(),
+        package_id: group.package_id.clone(),
+        asset_id: asset_ids(&group.package_id).first().copied(),
+        version: group.version,
+        auto_update: group.auto_update,
+        instances: subtree_size(dom, first),
+        variants: group.variants,
+        usages: group.usages.iter().map(|&(id, variant)| PackageUsage { path: dom.path(id), variant: variant + 1 }).collect(),
+    }
+}
+
+fn warnings(packages: &[Package]) -> Vec<String> {
+    packages.iter()
+        .filter(|p| p.variants > 1)
+        .map(|p| format!("{}: {} copies with {} different contents", label(p), p.usages.len(), p.variants))
+        .collect()
+}
+
+pub fn analyze(dom: &Dom) -> PackageReport {
+    let packages: Vec<Package> = groups(dom).iter().map(|group| package(dom, group)).collect();
+    let warnings = warnings(&packages);
+
+    let linked_scripts = dom.iter_depth_first().into_iter()
+        .filter_map(|id| match dom.instances[id].property("LinkedSource") {
+            Some(PropertyValue::Content(Some(url))) if !url.is_empty() => {
+                Some(LinkedScript { path: dom.path(id), asset_id: asset_ids(url).first().copied(), linked_source: url.clone() })
+            }
+            _ => None,
+        })
+        .collect();
+
+    PackageReport { packages, linked_scripts, warnings }
+}
+
+pub fn summary(report: &PackageReport) -> String {
+    let usages: usize = report.packages.iter().map(|p| p.usages.len()).sum();
+    format!(
+        "Packages: {} packages, {} copies, {} with differing copies, {} linked scripts",
+        report.packages.len(),
+        usages,
+        report.warnings.len(),
+        report.linked_scripts.len()
+    )
+}
+
+pub fn render_text(report: &PackageReport) -> String {
+    let mut out = String::new();
+    for package in &report.packages {
+        let auto_update = if package.auto_update { ", auto-update" } else { "" };
+        let _ = writeln!(out, "{} — {} instances{}", label(package), package.instances, auto_update);
+        for usage in &package.usages {
+            if package.variants > 1 {
+                let _ = writeln!(out, "  {}  (variant {})", usage.path, usage.variant);
+            } else {
+                let _ = writeln!(out, "  {}", usage.path);
+            }
+        }
+    }
+    if !report.linked_scripts.is_empty() {
+        let _ = writeln!(out, "\nLinked scripts");
+        for script in &report.linked_scripts {
+            let _ = writeln!(out, "  {}  {}", script.path, script.linked_source);
+        }
+    }
+    if !report.warnings.is_empty() {
+        let _ = writeln!(out, "\nWarnings");
+        for warning in &report.warnings {
+            let _ = writeln!(out, "  {}", warning);
+        }
+    }
+    let _ = writeln!(out, "\n{}", summary(report));
+    out
+}
+
+/// Package contents written once under [`PACKAGES_DIR`] during extraction, one folder per distinct content.
+pub struct SharedPackages {
+    /// Folder of each package root, by referent.
+    folders: HashMap<String, PathBuf>,
+    /// The first copy of every distinct content in document order; these write the folder's contents.
+    writers: HashSet<String>,
+    warnings: Vec<String>,
+}
+
+impl SharedPackages {
+    /// Names the package folders through `policy`, so they get the same Windows-safe names as instance folders.
+    pub fn collect(dom: &Dom, policy: &PathPolicy) -> Self {
+        let mut folders = HashMap::new();
+        let mut writers = HashSet::new();
+        let groups = groups(dom);
+        for group in &groups {
+            let (first, _) = group.usages[0];
+            let root = &dom.instances[first];
+            let id = asset_ids(&group.package_id).first().map(|id| id.to_string()).unwrap_or_else(|| format!("{:016x}", fnv1a(group.package_id.as_bytes())));
+            let mut base = format!("{}_{}", root.name, id);
+            if let Some(version) = group.version {
+                let _ = write!(base, "_v{}", version);
+            }
+            let paths: Vec<PathBuf> = (0..group.variants)
+                .map(|variant| {
+                    let name = if variant == 0 { base.clone() } else { format!("{}~{}", base, variant + 1) };
+                    policy.allocate(Path::new(PACKAGES_DIR), &name, &root.class)
+                })
+                .collect();
+            let mut written = vec![false; group.variants];
+            for &(usage, variant) in &group.usages {
+                let Some(referent) = &dom.instances[usage].referent else { continue };
+                folders.insert(referent.clone(), paths[variant].clone());
+                if !written[variant] {
+                    written[variant] = true;
+                    writers.insert(referent.clone());
+                }
+            }
+        }
+        let packages: Vec<Package> = groups.iter().map(|group| package(dom, group)).collect();
+        let warnings = warnings(&packages);
+        Self { folders, writers, warnings }
+    }
+
+    /// The shared folder of the package rooted at `referent`, or `None` if it isn't a package. The second value
+    /// is true for the copy that writes the contents: the first one with them in document order.
+    pub fn claim(&self, referent: &str) -> Option<(&Path, bool)> {
+        let path = self.folders.get(referent)?;
+        Some((path, self.writers.contains(referent)))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.folders.is_empty()
+    }
+
+    /// Packages whose copies differ; each variant gets a folder of its own.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cframe(tag: &str, name: &str, x: f64, y: f64) -> String {
+        format!(
+            r#"<{tag} name="{name}"><X>{x}</X><Y>{y}</Y><Z>0</Z><R00>1</R00><R01>0</R01><R02>0</R02><R10>0</R10><R11>1</R11><R12>0</R12><R20>0</R20><R21>0</R21><R22>1</R22></{tag}>"#
+        )
+    }
+
+    /// A lamp package copy placed at `x`, whose switch script runs `source`.
+    fn lamp(id: &str, x: f64, source: &str) -> String {
+        let pivot = format!(r#"<OptionalCoordinateFrame name="WorldPivotData">{}</OptionalCoordinateFrame>"#, cframe("CFrame", "CFrame", x, 0.0));
+        let bulb = cframe("CoordinateFrame", "CFrame", x, 5.0);
+        format!(
+            r#"<Item class="Model" referent="{id}"><Properties><string name="Name">Lamp</string>{pivot}<UniqueId name="UniqueId">{id}u</UniqueId></Properties>
+                <Item class="PackageLink" referent="{id}l"><Properties><string name="Name">PackageLink</string><Content name="PackageIdSerialize"><url>rbxassetid://111</url></Content><int64 name="VersionIdSerialize">3</int64><bool name="AutoUpdate">false</bool></Properties></Item>
+                <Item class="Part" referent="{id}b"><Properties><string name="Name">Bulb</string>{bulb}<UniqueId name="UniqueId">{id}bu</UniqueId></Properties></Item>
+                <Item class="Script" referent="{id}s"><Properties><string name="Name">Switch</string><ProtectedString name="Source">{source}</ProtectedString></Properties></Item>
+            </Item>"#,
+        )
+    }
+
+    fn place(items: &[String]) -> Dom {
+        Dom::parse(&format!(
+            r#"<roblox version="4"><Item class="Workspace" referent="W"><Properties><string name="Name">Workspace</string></Properties>{}</Item></roblox>"#,
+            items.concat()
+        ))
+    }
+
+    fn referent(dom: &Dom, referent: &str) -> usize {
+        dom.instances.iter().position(|i| i.referent.as_deref() == Some(referent)).unwrap()
+    }
+
+    #[test]
+    fn copies_placed_elsewhere_have_the_same_fingerprint() {
+        let dom = place(&[lamp("A", 0.0, "print('on')"), lamp("B", 20.0, "print('on')"), lamp("C", 40.0, "print('off')")]);
+        let fingerprint = |id| fingerprint(&dom, referent(&dom, id));
+        assert_eq!(fingerprint("A"), fingerprint("B"));
+        assert_ne!(fingerprint("A"), fingerprint("C"));
+    }
+
+    #[test]
+    fn moving_a_part_inside_a_copy_changes_it() {
+        let moved = lamp("B", 20.0, "print('on')").replace("<X>20</X><Y>5</Y>", "<X>21</X><Y>5</Y>");
+        let dom = place(&[lamp("A", 0.0, "print('on')"), moved]);
+        assert_ne!(fingerprint(&dom, referent(&dom, "A")), fingerprint(&dom, referent(&dom, "B")));
+    }
+
+    #[test]
+    fn reports_differing_copies() {
+        let dom = place(&[lamp("A", 0.0, "print('on')"), lamp("B", 20.0, "print('on')"), lamp("C", 40.0, "print('off')")]);
+        let report = analyze(&dom);
+        assert_eq!(report.packages.len(), 1);
+        let package = &report.packages[0];
+        assert_eq!((package.asset_id, package.version, package.variants, package.instances), (Some(111), Some(3), 2, 4));
+        let variants: Vec<_> = package.usages.iter().map(|u| u.variant).collect();
+        assert_eq!(variants, vec![1, 1, 2]);
+        assert_eq!(report.warnings, vec!["Lamp (rbxassetid://111 v3): 3 copies with 2 different contents"]);
+    }
+
+    #[test]
+    fn first_copy_of_each_variant_writes_it() {
+        let dom = place(&[lamp("A", 0.0, "print('off')"), lamp("B", 20.0, "print('on')"), lamp("C", 40.0, "print('off')"), lamp("D", 60.0, "print('on')")]);
+        let policy = PathPolicy::new(Path::new("out"), crate::path_policy::DEFAULT_MAX_PATH_LENGTH, false);
+        let packages = SharedPackages::collect(&dom, &policy);
+        let claims: Vec<_> = ["A", "B", "C", "D"].iter()
+            .map(|id| packages.claim(id).map(|(path, writer)| (path.to_string_lossy().replace('\\', "/"), writer)).unwrap())
+            .collect();
+        let first = "packages/Lamp_111_v3 [Model]".to_string();
+        let second = "packages/Lamp_111_v3~2 [Model]".to_string();
+        assert_eq!(claims, vec![(first.clone(), true), (second.clone(), true), (first, false), (second, false)]);
+        assert!(packages.claim("Ab").is_none());
+    }
+
+    #[test]
+    fn package_folders_follow_the_path_policy() {
+        let dom = place(&[lamp("A", 0.0, "").replace("<string name=\"Name\">Lamp</string>", "<string name=\"Name\">Lamp:1?</string>")]);
+        let policy = PathPolicy::new(Path::new("out"), crate::path_policy::DEFAULT_MAX_PATH_LENGTH, false);
+        let packages = SharedPackages::collect(&dom, &policy);
+        let (path, _) = packages.claim("A").unwrap();
+        assert_eq!(path, Path::new("packages").join("Lamp1_111_v3 [Model]"));
+        assert_eq!(policy.into_mappings()[0].reasons, vec!["sanitized"]);
+    }
+}
@@ -0,0 +1,183 @@
+use crate::dom::Dom;
+use crate::luau::lexer::{Token, TokenKind};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub enum Step {
+    Child(String),
+    Parent,
+    Service(String),
+    Ancestor(String),
+    ChildOfClass(String),
+    AncestorOfClass(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Base {
+    Script,
+    Game,
+    Workspace,
+}
+
+#[derive(Clone, Debug)]
+pub struct Chain {
+    pub base: Base,
+    pub steps: Vec<Step>,
+}
+
+/// Locals bound to an instance chain, e.g. `local Remotes = ReplicatedStorage:WaitForChild("Remotes")`.
+#[derive(Default)]
+pub struct Aliases<'a> {
+    map: HashMap<&'a str, Chain>,
+}
+
+impl<'a> Aliases<'a> {
+    /// Records `local Name = <chain>` starting at `tokens[i]` and returns the index after the chain.
+    /// Any other assignment to a known alias forgets it.
+    pub fn observe(&mut self, tokens: &[Token<'a>], i: usize) -> Option<usize> {
+        let token = &tokens[i];
+        if !(token.is_keyword("local") && tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::Name) && tokens.get(i + 2).is_some_and(|t| t.is_symbol("="))) {
+            return None;
+        }
+        let name = tokens[i + 1].text;
+        if let Some((chain, next)) = parse_chain(tokens, i + 3, self) {
+            if !tokens.get(next).is_some_and(|t| t.is_symbol("(") || t.is_symbol(":") || t.is_symbol("..")) {
+                self.map.insert(name, chain);
+                return Some(next);
+            }
+        }
+        self.map.remove(name);
+        None
+    }
+}
+
+pub fn span(source: &str, first: &Token, last: &Token) -> String {
+    source[first.offset..last.offset + last.text.len()].to_string()
+}
+
+pub fn matching_paren(tokens: &[Token], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        if token.is_symbol("(") {
+            depth += 1;
+        } else if token.is_symbol(")") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i);
+            }
+        } else if token.kind == TokenKind::Eof {
+            break;
+        }
+    }
+    None
+}
+
+fn string_arg(tokens: &[Token], open: usize) -> Option<(String, usize)> {
+    if !tokens.get(open)?.is_symbol("(") {
+        return None;
+    }
+    let arg = tokens.get(open + 1)?;
+    if arg.kind != TokenKind::String {
+        return None;
+    }
+    let close = matching_paren(tokens, open)?;
+    Some((arg.string_value()?, close + 1))
+}
+
+/// Parses `script.Parent.Foo`, `game:GetService("X").Bar`, `workspace:WaitForChild("Y")` and
+/// chains starting from a known alias. Returns the chain and the index of the first token after it.
+pub fn parse_chain(tokens: &[Token], start: usize, aliases: &Aliases) -> Option<(Chain, usize)> {
+    let first = tokens.get(start)?;
+    let mut chain = if first.is_name("script") {
+        Chain { base: Base::Script, steps: Vec::new() }
+    } else if first.is_name("game") {
+        Chain { base: Base::Game, steps: Vec::new() }
+    } else if first.is_name("workspace") {
+        Chain { base: Base::Workspace, steps: Vec::new() }
+    } else if first.kind == TokenKind::Name {
+        aliases.map.get(first.text)?.clone()
+    } else {
+        return None;
+    };
+
+    let mut i = start + 1;
+    while let Some(token) = tokens.get(i) {
+        if token.is_symbol(".") {
+            match tokens.get(i + 1) {
+                Some(name) if name.kind == TokenKind::Name => {
+                    chain.steps.push(if name.text == "Parent" { Step::Parent } else { Step::Child(name.text.to_string()) });
+                    i += 2;
+                }
+                _ => break,
+            }
+        } else if token.is_symbol("[") && tokens.get(i + 1).is_some_and(|t| t.kind == TokenKind::String) && tokens.get(i + 2).is_some_and(|t| t.is_symbol("]")) {
+            chain.steps.push(Step::Child(tokens[i + 1].string_value().unwrap_or_default()));
+            i += 3;
+        } else if token.is_symbol(":") {
+            let Some(method) = tokens.get(i + 1) else { break };
+            let Some((arg, next)) = string_arg(tokens, i + 2) else { break };
+            let step = match method.text {
+                "WaitForChild" | "FindFirstChild" => Step::Child(arg),
+                "GetService" | "FindService" => Step::Service(arg),
+                "FindFirstAncestor" => Step::Ancestor(arg),
+                "FindFirstChildOfClass" | "FindFirstChildWhichIsA" => Step::ChildOfClass(arg),
+                "FindFirstAncestorOfClass" | "FindFirstAncestorWhichIsA" => Step::AncestorOfClass(arg),
+                _ => break,
+            };
+            chain.steps.push(step);
+            i = next;
+        } else {
+            break;
+        }
+    }
+    Some((chain, i))
+}
+
+pub fn resolve(dom: &Dom, script: usize, chain: &Chain) -> Result<usize, String> {
+    // `None` stands for the DataModel itself (`game`).
+    let mut current = match chain.base {
+        Base::Script => Some(script),
+        Base::Game => None,
+        Base::Workspace => Some(dom.find_service("Workspace").ok_or("Workspace is not in the place")?),
+    };
+    let describe = |current: Option<usize>| current.map(|id| dom.path(id)).unwrap_or_else(|| "game".to_string());
+
+    for step in &chain.steps {
+        current = match step {
+            Step::Child(name) => {
+                let found = match current {
+                    Some(id) => dom.find_child(id, name),
+                    None => dom.find_root(name).or_else(|| dom.find_service(name)),
+                };
+                Some(found.ok_or_else(|| format!("no child '{}' under {}", name, describe(current)))?)
+            }
+            Step::Parent => match current {
+                Some(id) => dom.instances[id].parent,
+                None => return Err("game has no parent".to_string()),
+            },
+            Step::Service(class) => Some(dom.find_service(class).ok_or_else(|| format!("service {} is not in the place", class))?),
+            Step::ChildOfClass(class) => {
+                let children = match current {
+                    Some(id) => dom.instances[id].children.clone(),
+                    None => dom.roots.clone(),
+                };
+                let found = children.into_iter().find(|&c| dom.instances[c].class == *class);
+                Some(found.ok_or_else(|| format!("no {} child under {}", class, describe(current)))?)
+            }
+            Step::Ancestor(name) | Step::AncestorOfClass(name) => {
+                let by_class = matches!(step, Step::AncestorOfClass(_));
+                let mut ancestor = current.and_then(|id| dom.instances[id].parent);
+                while let Some(a) = ancestor {
+                    let instance = &dom.instances[a];
+                    if (by_class && instance.class == *name) || (!by_class && instance.name == *name) {
+                        break;
+                    }
+                    ancestor = instance.parent;
+                }
+                Some(ancestor.ok_or_else(|| format!("no ancestor '{}' above {}", name, describe(current)))?)
+            }
+        };
+    }
+
+    current.ok_or_else(|| "resolves to game".to_string())
+}
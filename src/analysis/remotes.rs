@@ -0,0 +1,187 @@
+use crate::analysis::deps::is_script_class;
+use crate::analysis::refs::{parse_chain, resolve, span, Aliases, Step};
+use crate::dom::Dom;
+use crate::luau::lexer::{tokenize, TokenKind};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+
+pub const REMOTE_CLASSES: &[&str] = &[
+    "RemoteEvent",
+    "RemoteFunction",
+    "UnreliableRemoteEvent",
+    "BindableEvent",
+    "BindableFunction",
+];
+
+/// Methods called with `:`, e.g. `Remote:FireServer(...)`.
+const METHODS: &[&str] = &[
+    "FireServer", "FireClient", "FireAllClients", "InvokeServer", "InvokeClient", "Fire", "Invoke",
+];
+
+/// Signals and callbacks accessed with `.`, e.g. `Remote.OnServerEvent:Connect(...)` or `Remote.OnServerInvoke = ...`.
+const MEMBERS: &[&str] = &[
+    "OnServerEvent", "OnClientEvent", "OnServerInvoke", "OnClientInvoke", "Event", "OnInvoke",
+];
+
+/// Members that only exist on remotes, so a call we can not resolve is still worth listing.
+const REMOTE_ONLY: &[&str] = &[
+    "FireServer", "FireClient", "FireAllClients", "InvokeServer", "InvokeClient",
+    "OnServerEvent", "OnClientEvent", "OnServerInvoke", "OnClientInvoke",
+];
+
+#[derive(Serialize)]
+pub struct Usage {
+    pub script: String,
+    pub line: usize,
+    pub member: String,
+}
+
+#[derive(Serialize)]
+pub struct Remote {
+    pub path: String,
+    pub class: String,
+    pub usages: Vec<Usage>,
+}
+
+#[derive(Serialize)]
+pub struct UnresolvedUsage {
+    pub script: String,
+    pub line: usize,
+    pub member: String,
+    pub expression: String,
+}
+
+#[derive(Serialize)]
+pub struct RemotesReport {
+    pub remotes: Vec<Remote>,
+    pub unresolved: Vec<UnresolvedUsage>,
+}
+
+pub fn analyze(dom: &Dom) -> RemotesReport {
+    let mut usages: BTreeMap<usize, Vec<Usage>> = BTreeMap::new();
+    let mut unresolved = Vec::new();
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        if REMOTE_CLASSES.contains(&instance.class.as_str()) {
+            usages.entry(id).or_default();
+        }
+    }
+
+    for script in dom.iter_depth_first() {
+        let instance = &dom.instances[script];
+        let Some(source) = &instance.source else { continue };
+        if !is_script_class(&instance.class) {
+            continue;
+        }
+        let tokens = tokenize(source);
+        let mut aliases = Aliases::default();
+        let mut resolved_members = HashSet::new();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            if let Some(next) = aliases.observe(&tokens, i) {
+                i = next;
+                continue;
+            }
+            let token = &tokens[i];
+            let starts_chain = token.kind == TokenKind::Name && !(i > 0 && (tokens[i - 1].is_symbol(".") || tokens[i - 1].is_symbol(":")));
+            let Some((mut chain, next)) = starts_chain.then(|| parse_chain(&tokens, i, &aliases)).flatten() else {
+                i += 1;
+                continue;
+            };
+
+            // `parse_chain` reads `.OnServerEvent` as a child; take it back off as the member.
+            let (member, member_index) = match chain.steps.last() {
+                Some(Step::Child(name)) if MEMBERS.contains(&name.as_str()) => {
+                    let name = name.clone();
+                    chain.steps.pop();
+                    (name, next - 1)
+                }
+                _ if tokens.get(next).is_some_and(|t| t.is_symbol(":")) && tokens.get(next + 1).is_some_and(|t| METHODS.contains(&t.text)) => {
+                    (tokens[next + 1].text.to_string(), next + 1)
+                }
+                _ => {
+                    i = next.max(i + 1);
+                    continue;
+                }
+            };
+
+            match resolve(dom, script, &chain) {
+                Ok(target) => {
+                    if let Some(list) = usages.get_mut(&target) {
+                        list.push(Usage { script: dom.path(script), line: token.line, member });
+                        resolved_members.insert(member_index);
+                    }
+                }
+                Err(_) if REMOTE_ONLY.contains(&member.as_str()) => {
+                    unresolved.push(UnresolvedUsage {
+                        script: dom.path(script),
+                        line: token.line,
+                        member,
+                        expression: span(source, token, &tokens[member_index]),
+                    });
+                    resolved_members.insert(member_index);
+                }
+                Err(_) => {}
+            }
+            i = next.max(i + 1);
+        }
+
+        // Remote calls on expressions we can not follow at all, e.g. `remotes[name]:FireServer()`.
+        for (i, token) in tokens.iter().enumerate() {
+            if i == 0 || resolved_members.contains(&i) || token.kind != TokenKind::Name || !REMOTE_ONLY.contains(&token.text) {
+                continue;
+            }
+            let previous = &tokens[i - 1];
+            if previous.is_symbol(":") || previous.is_symbol(".") {
+                unresolved.push(UnresolvedUsage {
+                    script: dom.path(script),
+                    line: token.line,
+                    member: token.text.to_string(),
+                    expression: source.lines().nth(token.line - 1).unwrap_or("").trim().to_string(),
+                });
+            }
+        }
+    }
+
+    let remotes = usages.into_iter()
+        .map(|(id, usages)| Remote { path: dom.path(id), class: dom.instances[id].class.clone(), usages })
+        .collect();
+    unresolved.sort_by(|a, b| a.script.cmp(&b.script).then(a.line.cmp(&b.line)));
+    RemotesReport { remotes, unresolved }
+}
+
+pub fn summary(report: &RemotesReport) -> String {
+    let usages: usize = report.remotes.iter().map(|r| r.usages.len()).sum();
+    let unused = report.remotes.iter().filter(|r| r.usages.is_empty()).count();
+    format!(
+        "Remotes: {} remotes and bindables, {} usages, {} unused, {} unresolved calls",
+        report.remotes.len(),
+        usages,
+        unused,
+        report.unresolved.len()
+    )
+}
+
+pub fn render_text(report: &RemotesReport) -> String {
+    let mut out = String::new();
+    for remote in &report.remotes {
+        let _ = writeln!(out, "{} ({})", remote.path, remote.class);
+        if remote.usages.is_empty() {
+            let _ = writeln!(out, "  (not referenced by any script)");
+        }
+        for usage in &remote.usages {
+            let _ = writeln!(out, "  {}  {}:{}", usage.member, usage.script, usage.line);
+        }
+    }
+    if !report.unresolved.is_empty() {
+        let _ = writeln!(out, "\nUnresolved");
+        for usage in &report.unresolved {
+            let _ = writeln!(out, "  {}  {}:{}  {}", usage.member, usage.script, usage.line, usage.expression);
+        }
+    }
+    let _ = writeln!(out, "\n{}", summary(report));
+    out
+}
@@ -0,0 +1,229 @@
+use crate::analysis::assets::{asset_ids, csv_field};
+use crate::dom::Dom;
+use crate::export::geometry::{cframe, Transform, BASIC_PART_CLASSES, PLACEHOLDER_CLASSES};
+use crate::properties::PropertyValue;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+/// Instances whose CFrame is relative to their parent rather than to the world.
+const RELATIVE_CLASSES: &[&str] = &["Attachment", "Bone"];
+
+#[derive(Serialize)]
+pub struct SoundEntry {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<u64>,
+    pub volume: f64,
+    pub playback_speed: f64,
+    pub looped: bool,
+    pub playing: bool,
+    pub roll_off_mode: &'static str,
+    pub roll_off_min_distance: f64,
+    pub roll_off_max_distance: f64,
+    /// Path of the SoundGroup the sound plays through.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sound_group: Option<String>,
+    /// Path of the part or attachment the sound plays from. Without one the sound isn't positional.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emitter: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<[f64; 3]>,
+}
+
+#[derive(Serialize)]
+pub struct SoundManifest {
+    pub sounds: Vec<SoundEntry>,
+}
+
+/// Enum.RollOffMode
+fn roll_off_mode(value: Option<&PropertyValue>) -> &'static str {
+    match value {
+        Some(PropertyValue::Token(1)) => "Linear",
+        Some(PropertyValue::Token(2)) => "InverseTapered",
+        Some(PropertyValue::Token(3)) => "LinearSquare",
+        _ => "Inverse",
+    }
+}
+
+/// The world transform of an instance with a CFrame, following attachments up to their part.
+fn world_transform(dom: &Dom, id: usize) -> Option<Transform> {
+    let instance = &dom.instances[id];
+    let local = cframe(instance)?;
+    if !RELATIVE_CLASSES.contains(&instance.class.as_str()) {
+        return Some(local);
+    }
+    let parent = world_transform(dom, instance.parent?)?;
+    Some(parent.then(&local))
+}
+
+/// The parent a sound plays from and its world position. Roblox only plays a sound positionally when it sits
+/// directly under a part or an attachment; anywhere else, even deeper under a part, it plays everywhere.
+fn emitter(dom: &Dom, id: usize) -> Option<(usize, [f64; 3])> {
+    let parent = dom.instances[id].parent?;
+    let class = dom.instances[parent].class.as_str();
+    if !BASIC_PART_CLASSES.contains(&class) && !PLACEHOLDER_CLASSES.contains(&class) && !RELATIVE_CLASSES.contains(&class) {
+        return None;
+    }
+    Some((parent, world_transform(dom, parent)?.position))
+}
+
+pub fn analyze(dom: &Dom) -> SoundManifest {
+    let referents: HashMap<&str, usize> = dom.instances.iter().enumerate()
+        .filter_map(|(id, instance)| Some((instance.referent.as_deref()?, id)))
+        .collect();
+    let mut sounds = Vec::new();
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        if instance.class != "Sound" {
+            continue;
+        }
+        // Properties keep their old serialized names in place files: `Pitch`, `xmlRead_MinDistance_3` and so on.
+        let property = |names: &[&str]| names.iter().find_map(|name| instance.property(name));
+        let number = |names: &[&str], default: f64| match property(names) {
+            Some(PropertyValue::Float(value)) => *value,
+            Some(PropertyValue::Int(value)) => *value as f64,
+            _ => default,
+        };
+        let flag = |name: &str| matches!(instance.property(name), Some(PropertyValue::Bool(true)));
+
+        let sound_id = match instance.property("SoundId") {
+            Some(PropertyValue::Content(Some(url))) if !url.is_empty() => Some(url.clone()),
+            _ => None,
+        };
+        let sound_group = match instance.property("SoundGroup") {
+            Some(PropertyValue::Ref(Some(referent))) => referents.get(referent.as_str()).map(|&group| dom.path(group)),
+            _ => None,
+        };
+        let emitter = emitter(dom, id);
+
+        sounds.push(SoundEntry {
+            path: dom.path(id),
+            asset_id: sound_id.as_deref().and_then(|url| asset_ids(url).first().copied()),
+            sound_id,
+            volume: number(&["Volume"], 0.5),
+            playback_speed: number(&["PlaybackSpeed", "Pitch"], 1.0),
+            looped: flag("Looped"),
+            playing: flag("Playing"),
+            roll_off_mode: roll_off_mode(instance.property("RollOffMode")),
+            roll_off_min_distance: number(&["RollOffMinDistance", "xmlRead_MinDistance_3", "EmitterSize"], 10.0),
+            roll_off_max_distance: number(&["RollOffMaxDistance", "xmlRead_MaxDistance_3", "MaxDistance"], 10000.0),
+            sound_group,
+            emitter: emitter.map(|(part, _)| dom.path(part)),
+            position: emitter.map(|(_, position)| position),
+        });
+    }
+
+    SoundManifest { sounds }
+}
+
+pub fn summary(manifest: &SoundManifest) -> String {
+    let positional = manifest.sounds.iter().filter(|s| s.position.is_some()).count();
+    let mut ids: Vec<u64> = manifest.sounds.iter().filter_map(|s| s.asset_id).collect();
+    ids.sort_unstable();
+    ids.dedup();
+    format!("Sounds: {} ({} positional, {} distinct asset ids)", manifest.sounds.len(), positional, ids.len())
+}
+
+pub fn to_csv(manifest: &SoundManifest) -> String {
+    let mut out = String::from(
+        "path,sound_id,asset_id,volume,playback_speed,looped,playing,roll_off_mode,roll_off_min_distance,roll_off_max_distance,sound_group,emitter,x,y,z\n",
+    );
+    for sound in &manifest.sounds {
+        let position = sound.position.map(|p| p.map(|v| v.to_string())).unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            csv_field(&sound.path),
+            csv_field(sound.sound_id.as_deref().unwrap_or("")),
+            sound.asset_id.map(|id| id.to_string()).unwrap_or_default(),
+            sound.volume,
+            sound.playback_speed,
+            sound.looped,
+            sound.playing,
+            sound.roll_off_mode,
+            sound.roll_off_min_distance,
+            sound.roll_off_max_distance,
+            csv_field(sound.sound_group.as_deref().unwrap_or("")),
+            csv_field(sound.emitter.as_deref().unwrap_or("")),
+            position[0],
+            position[1],
+            position[2]
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cframe_xml(name: &str, [x, y, z]: [f64; 3]) -> String {
+        format!(
+            "<CoordinateFrame name=\"{name}\"><X>{x}</X><Y>{y}</Y><Z>{z}</Z><R00>1</R00><R01>0</R01><R02>0</R02><R10>0</R10><R11>1</R11><R12>0</R12><R20>0</R20><R21>0</R21><R22>1</R22></CoordinateFrame>"
+        )
+    }
+
+    fn sound(name: &str) -> String {
+        format!(r#"<Item class="Sound" referent="{name}"><Properties><string name="Name">{name}</string><Content name="SoundId"><url>rbxassetid://{}</url></Content></Properties></Item>"#, name.len())
+    }
+
+    fn analyze_place() -> SoundManifest {
+        let part = cframe_xml("CFrame", [10.0, 0.0, 0.0]);
+        let attachment = cframe_xml("CFrame", [0.0, 2.0, 0.0]);
+        let camera = cframe_xml("CFrame", [0.0, 50.0, 0.0]);
+        let dom = Dom::parse(&format!(
+            r#"<roblox version="4"><Item class="Workspace" referent="W"><Properties><string name="Name">Workspace</string></Properties>
+<Item class="Part" referent="P"><Properties><string name="Name">Part</string>{part}</Properties>
+{}
+<Item class="Attachment" referent="A"><Properties><string name="Name">Attachment</string>{attachment}</Properties>{}</Item>
+<Item class="Folder" referent="F"><Properties><string name="Name">Folder</string></Properties>{}</Item>
+</Item>
+<Item class="Camera" referent="C"><Properties><string name="Name">Camera</string>{camera}</Properties>{}</Item>
+</Item>
+<Item class="SoundService" referent="S"><Properties><string name="Name">SoundService</string></Properties>{}</Item>
+</roblox>"#,
+            sound("OnPart"), sound("OnAttachment"), sound("InFolder"), sound("OnCamera"), sound("Music")
+        ));
+        analyze(&dom)
+    }
+
+    fn find<'a>(manifest: &'a SoundManifest, path: &str) -> &'a SoundEntry {
+        manifest.sounds.iter().find(|s| s.path == path).unwrap()
+    }
+
+    #[test]
+    fn plays_from_parts_and_attachments() {
+        let manifest = analyze_place();
+        let on_part = find(&manifest, "Workspace.Part.OnPart");
+        assert_eq!((on_part.emitter.as_deref(), on_part.position), (Some("Workspace.Part"), Some([10.0, 0.0, 0.0])));
+        // Attachments are placed relative to their part.
+        let on_attachment = find(&manifest, "Workspace.Part.Attachment.OnAttachment");
+        assert_eq!((on_attachment.emitter.as_deref(), on_attachment.position), (Some("Workspace.Part.Attachment"), Some([10.0, 2.0, 0.0])));
+    }
+
+    #[test]
+    fn other_parents_are_not_positional() {
+        let manifest = analyze_place();
+        for path in ["Workspace.Part.Folder.InFolder", "Workspace.Camera.OnCamera", "SoundService.Music"] {
+            let sound = find(&manifest, path);
+            assert!(sound.emitter.is_none() && sound.position.is_none(), "{} is positional", path);
+        }
+        assert_eq!(summary(&manifest), "Sounds: 5 (2 positional, 4 distinct asset ids)");
+    }
+
+    #[test]
+    fn reads_old_property_names() {
+        let dom = Dom::parse(r#"<roblox version="4"><Item class="Sound" referent="S"><Properties><string name="Name">Old</string>
+<float name="Pitch">1.5</float><float name="xmlRead_MinDistance_3">5</float><float name="xmlRead_MaxDistance_3">200</float><token name="RollOffMode">1</token><bool name="Looped">true</bool>
+</Properties></Item></roblox>"#);
+        let manifest = analyze(&dom);
+        let sound = &manifest.sounds[0];
+        assert_eq!((sound.playback_speed, sound.roll_off_min_distance, sound.roll_off_max_distance), (1.5, 5.0, 200.0));
+        assert_eq!((sound.roll_off_mode, sound.looped, sound.volume), ("Linear", true, 0.5));
+        assert!(sound.sound_id.is_none());
+    }
+}
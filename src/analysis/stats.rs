@@ -0,0 +1,221 @@
+use crate::analysis::deps::is_script_class;
+use crate::dom::Dom;
+use crate::plan::format_bytes;
+use crate::properties::PropertyValue;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+/// How many entries the "largest" and "deepest" lists keep.
+const TOP_N: usize = 10;
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct Count {
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct ServiceStats {
+    pub name: String,
+    pub class: String,
+    pub instances: usize,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct ScriptKindStats {
+    pub kind: String,
+    pub count: usize,
+    pub lines: usize,
+    pub average_lines: f64,
+    pub bytes: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct PathStats {
+    pub path: String,
+    pub class: String,
+    /// Instances in the subtree for `largest_subtrees`, nesting depth for `deepest_paths`.
+    pub value: usize,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct ByteStats {
+    pub scripts: u64,
+    pub binary_strings: u64,
+    pub binary_string_count: usize,
+    /// Decoded size of the `<SharedStrings>` payloads that are still referenced.
+    pub shared_strings: u64,
+    pub shared_string_count: usize,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct PlaceStats {
+    pub instances: usize,
+    pub classes: Vec<Count>,
+    pub services: Vec<ServiceStats>,
+    pub scripts: Vec<ScriptKindStats>,
+    pub largest_subtrees: Vec<PathStats>,
+    pub deepest_paths: Vec<PathStats>,
+    pub property_types: Vec<Count>,
+    pub bytes: ByteStats,
+}
+
+fn base64_decoded_len(data: &str) -> u64 {
+    let padding = data.bytes().rev().take_while(|b| *b == b'=').count().min(2);
+    (data.len() / 4 * 3).saturating_sub(padding) as u64
+}
+
+fn sorted_counts(counts: HashMap<&str, usize>) -> Vec<Count> {
+    let mut counts: Vec<Count> = counts.into_iter().map(|(name, count)| Count { name: name.to_string(), count }).collect();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+pub fn analyze(dom: &Dom) -> PlaceStats {
+    let order = dom.iter_depth_first();
+
+    let mut depth = vec![0usize; dom.instances.len()];
+    for &id in &order {
+        if let Some(parent) = dom.instances[id].parent {
+            depth[id] = depth[parent] + 1;
+        }
+    }
+    let mut size = vec![1usize; dom.instances.len()];
+    for &id in order.iter().rev() {
+        if let Some(parent) = dom.instances[id].parent {
+            size[parent] += size[id];
+        }
+    }
+
+    let mut classes: HashMap<&str, usize> = HashMap::new();
+    let mut property_types: HashMap<&str, usize> = HashMap::new();
+    let mut scripts: HashMap<&str, (usize, usize, u64)> = HashMap::new();
+    let mut bytes = ByteStats { scripts: 0, binary_strings: 0, binary_string_count: 0, shared_strings: 0, shared_string_count: 0 };
+    let mut shared_keys = HashSet::new();
+
+    for instance in &dom.instances {
+        *classes.entry(&instance.class).or_default() += 1;
+        for property in &instance.properties {
+            *property_types.entry(property.value.type_name()).or_default() += 1;
+            match &property.value {
+                PropertyValue::BinaryString(data) => {
+                    bytes.binary_strings += base64_decoded_len(data);
+                    bytes.binary_string_count += 1;
+                }
+                PropertyValue::SharedString(key) => {
+                    shared_keys.insert(key.as_str());
+                }
+                _ => {}
+            }
+        }
+        if is_script_class(&instance.class) {
+            let source = instance.source.as_deref().unwrap_or("");
+            let entry = scripts.entry(&instance.class).or_default();
+            entry.0 += 1;
+            entry.1 += source.lines().count();
+            entry.2 += source.len() as u64;
+            bytes.scripts += source.len() as u64;
+        }
+    }
+    for key in shared_keys {
+        if let Some(data) = dom.shared_strings.get(key) {
+            bytes.shared_strings += base64_decoded_len(data);
+            bytes.shared_string_count += 1;
+        }
+    }
+
+    let mut script_stats: Vec<ScriptKindStats> = scripts.into_iter()
+        .map(|(kind, (count, lines, bytes))| ScriptKindStats {
+            kind: kind.to_string(),
+            count,
+            lines,
+            average_lines: lines as f64 / count as f64,
+            bytes,
+        })
+        .collect();
+    script_stats.sort_by(|a, b| a.kind.cmp(&b.kind));
+
+    let path_stats = |id: usize, value: usize| PathStats { path: dom.path(id), class: dom.instances[id].class.clone(), value };
+
+    let mut nested: Vec<usize> = order.iter().copied().filter(|&id| dom.instances[id].parent.is_some()).collect();
+    nested.sort_by(|&a, &b| size[b].cmp(&size[a]).then(a.cmp(&b)));
+    let largest_subtrees = nested.iter().take(TOP_N).map(|&id| path_stats(id, size[id])).collect();
+
+    let mut deepest = order.clone();
+    deepest.sort_by(|&a, &b| depth[b].cmp(&depth[a]).then(a.cmp(&b)));
+    let deepest_paths = deepest.iter().take(TOP_N).map(|&id| path_stats(id, depth[id])).collect();
+
+    PlaceStats {
+        instances: dom.instances.len(),
+        classes: sorted_counts(classes),
+        services: dom.roots.iter()
+            .map(|&id| ServiceStats { name: dom.instances[id].name.clone(), class: dom.instances[id].class.clone(), instances: size[id] })
+            .collect(),
+        scripts: script_stats,
+        largest_subtrees,
+        deepest_paths,
+        property_types: sorted_counts(property_types),
+        bytes,
+    }
+}
+
+pub fn summary(stats: &PlaceStats) -> String {
+    let scripts: usize = stats.scripts.iter().map(|s| s.count).sum();
+    let lines: usize = stats.scripts.iter().map(|s| s.lines).sum();
+    format!(
+        "Stats: {} instances of {} classes, {} scripts with {} lines",
+        stats.instances,
+        stats.classes.len(),
+        scripts,
+        lines
+    )
+}
+
+/// Instance names may contain `|`, which would split a table cell.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+pub fn render_markdown(stats: &PlaceStats) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Place statistics\n");
+    let _ = writeln!(out, "{}\n", summary(stats));
+
+    let _ = writeln!(out, "## Services\n\n| Service | Class | Instances |\n|---|---|---:|");
+    for service in &stats.services {
+        let _ = writeln!(out, "| {} | {} | {} |", cell(&service.name), service.class, service.instances);
+    }
+
+    let _ = writeln!(out, "\n## Scripts\n\n| Kind | Count | Lines | Average lines | Size |\n|---|---:|---:|---:|---:|");
+    for script in &stats.scripts {
+        let _ = writeln!(out, "| {} | {} | {} | {:.1} | {} |", script.kind, script.count, script.lines, script.average_lines, format_bytes(script.bytes));
+    }
+
+    let _ = writeln!(out, "\n## Classes\n\n| Class | Instances |\n|---|---:|");
+    for class in &stats.classes {
+        let _ = writeln!(out, "| {} | {} |", class.name, class.count);
+    }
+
+    let _ = writeln!(out, "\n## Largest subtrees\n\n| Path | Class | Instances |\n|---|---|---:|");
+    for entry in &stats.largest_subtrees {
+        let _ = writeln!(out, "| {} | {} | {} |", cell(&entry.path), entry.class, entry.value);
+    }
+
+    let _ = writeln!(out, "\n## Deepest paths\n\n| Path | Class | Depth |\n|---|---|---:|");
+    for entry in &stats.deepest_paths {
+        let _ = writeln!(out, "| {} | {} | {} |", cell(&entry.path), entry.class, entry.value);
+    }
+
+    let _ = writeln!(out, "\n## Property types\n\n| Type | Properties |\n|---|---:|");
+    for property in &stats.property_types {
+        let _ = writeln!(out, "| {} | {} |", property.name, property.count);
+    }
+
+    let bytes = &stats.bytes;
+    let _ = writeln!(out, "\n## Sizes\n\n| Data | Count | Size |\n|---|---:|---:|");
+    let _ = writeln!(out, "| Script sources | {} | {} |", stats.scripts.iter().map(|s| s.count).sum::<usize>(), format_bytes(bytes.scripts));
+    let _ = writeln!(out, "| BinaryString properties | {} | {} |", bytes.binary_string_count, format_bytes(bytes.binary_strings));
+    let _ = writeln!(out, "| Shared strings | {} | {} |", bytes.shared_string_count, format_bytes(bytes.shared_strings));
+    out
+}
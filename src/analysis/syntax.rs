@@ -0,0 +1,169 @@
+use crate::analysis::deps::is_script_class;
+use crate::dom::Dom;
+use crate::luau::lexer::SyntaxError;
+use crate::luau::parser::parse;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::panic;
+use std::thread;
+
+pub const AST_DIR: &str = "ast";
+
+/// Keeps AST file names comfortably below the path limits of the output.
+const MAX_AST_NAME_LENGTH: usize = 120;
+
+/// The parser and the AST serializer recurse once per nesting level, up to the parser's
+/// depth limit; unoptimized builds need far more than the default thread stack for that.
+const PARSER_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+#[derive(Serialize)]
+pub struct ScriptError {
+    pub path: String,
+    pub class: String,
+    /// 0 together with `column` when the parser itself failed on the script.
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+#[derive(Serialize)]
+pub struct SyntaxReport {
+    pub valid: usize,
+    pub invalid: usize,
+    pub errors: Vec<ScriptError>,
+}
+
+pub struct ParsedScript {
+    pub path: String,
+    /// The AST as JSON, serialized while the parser's large stack is still available.
+    pub ast_json: Vec<u8>,
+}
+
+/// Parses every script in the place. ASTs of the valid ones are returned only when `keep_ast` is set.
+/// A script the parser fails on is listed as invalid rather than failing the whole check.
+pub fn check(dom: &Dom, keep_ast: bool) -> anyhow::Result<(SyntaxReport, Vec<ParsedScript>)> {
+    thread::scope(|scope| {
+        thread::Builder::new()
+            .name("luau-parser".to_string())
+            .stack_size(PARSER_STACK_SIZE)
+            .spawn_scoped(scope, || check_scripts(dom, keep_ast))?
+            .join()
+            .map_err(|_| anyhow::anyhow!("the Luau parser panicked"))
+    })
+}
+
+/// The AST as JSON when `keep_ast` is set.
+fn parse_script(source: &str, keep_ast: bool) -> Result<Option<Vec<u8>>, SyntaxError> {
+    let ast = parse(source)?;
+    if !keep_ast {
+        return Ok(None);
+    }
+    serde_json::to_vec(&ast)
+        .map(Some)
+        .map_err(|e| SyntaxError { line: 0, column: 0, message: format!("could not serialize the AST: {}", e) })
+}
+
+fn check_scripts(dom: &Dom, keep_ast: bool) -> (SyntaxReport, Vec<ParsedScript>) {
+    let mut report = SyntaxReport { valid: 0, invalid: 0, errors: Vec::new() };
+    let mut parsed = Vec::new();
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        if !is_script_class(&instance.class) {
+            continue;
+        }
+        let source = instance.source.as_deref().unwrap_or("");
+        let result = panic::catch_unwind(|| parse_script(source, keep_ast))
+            .unwrap_or_else(|_| Err(SyntaxError { line: 0, column: 0, message: "the Luau parser panicked".to_string() }));
+        match result {
+            Ok(ast_json) => {
+                report.valid += 1;
+                if let Some(ast_json) = ast_json {
+                    parsed.push(ParsedScript { path: dom.path(id), ast_json });
+                }
+            }
+            Err(error) => {
+                report.invalid += 1;
+                report.errors.push(ScriptError {
+                    path: dom.path(id),
+                    class: instance.class.clone(),
+                    line: error.line,
+                    column: error.column,
+                    message: error.message,
+                });
+            }
+        }
+    }
+    (report, parsed)
+}
+
+pub fn summary(report: &SyntaxReport) -> String {
+    format!("Syntax: {} valid, {} invalid scripts", report.valid, report.invalid)
+}
+
+/// One line per broken script, `path:line:column: message`.
+pub fn render_text(report: &SyntaxReport) -> String {
+    let mut out = String::new();
+    for error in &report.errors {
+        let _ = writeln!(out, "{}:{}:{}: {}", error.path, error.line, error.column, error.message);
+    }
+    let _ = writeln!(out, "{}", summary(report));
+    out
+}
+
+/// File names for the AST dumps, derived from the dotted instance path and made unique.
+pub fn ast_file_names(scripts: &[ParsedScript]) -> Vec<String> {
+    let mut taken = HashSet::new();
+    scripts.iter()
+        .map(|script| {
+            let mut stem = sanitize_filename::sanitize(&script.path);
+            if stem.len() > MAX_AST_NAME_LENGTH {
+                let mut end = MAX_AST_NAME_LENGTH;
+                while !stem.is_char_boundary(end) {
+                    end -= 1;
+                }
+                stem.truncate(end);
+            }
+            let mut name = format!("{}.json", stem);
+            let mut n = 2;
+            while !taken.insert(name.to_lowercase()) {
+                name = format!("{}~{}.json", stem, n);
+                n += 1;
+            }
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_invalid_scripts_and_keeps_valid_asts() {
+        let dom = Dom::parse(
+            r#"<roblox version="4">
+                <Item class="ServerScriptService" referent="A"><Properties><string name="Name">ServerScriptService</string></Properties>
+                    <Item class="Script" referent="B"><Properties><string name="Name">Good</string><ProtectedString name="Source">print(1)</ProtectedString></Properties></Item>
+                    <Item class="ModuleScript" referent="C"><Properties><string name="Name">Bad</string><ProtectedString name="Source">x = [[€</ProtectedString></Properties></Item>
+                </Item>
+            </roblox>"#,
+        );
+        let (report, parsed) = check(&dom, true).unwrap();
+        assert_eq!((report.valid, report.invalid), (1, 1));
+        assert_eq!(report.errors[0].path, "ServerScriptService.Bad");
+        assert_eq!((report.errors[0].line, report.errors[0].column), (1, 5));
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].path, "ServerScriptService.Good");
+    }
+
+    #[test]
+    fn ast_file_names_are_unique() {
+        let script = |path: &str| ParsedScript { path: path.to_string(), ast_json: Vec::new() };
+        let names = ast_file_names(&[script("A.B"), script("a.b"), script(&"x".repeat(300))]);
+        assert_eq!(names[0], "A.B.json");
+        assert_eq!(names[1], "a.b~2.json");
+        assert_eq!(names[2].len(), MAX_AST_NAME_LENGTH + ".json".len());
+    }
+}
@@ -0,0 +1,809 @@
+use crate::analysis::{assets, audit, dead, dedup, deps, packages, remotes, sounds, stats, syntax, ReportSettings};
+use crate::dom::{Dom, Instance};
+use crate::export::images::ImageExport;
+use crate::export::mesh_data::MeshDataExport;
+use crate::export::{animation, file_stems, geometry, geometry_files, gui, lighting, map, terrain, ExportSettings, GeometryFormat};
+use crate::extract::{process_file_with_progress, ExtractionSettings, OutputFormat};
+use crate::normalize::{restore_folder, Indentation, LineEndings, NormalizeSettings, DEFAULT_INDENT_WIDTH};
+use crate::path_policy::DEFAULT_MAX_PATH_LENGTH;
+use crate::plan::{build_plan, render_text, summary};
+use crate::search::{self, SearchField, SearchQuery, DEFAULT_CONTEXT_LINES, DEFAULT_MAX_RESULTS};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Parser)]
+#[command(name = "rbx_ripper", version, about = "Extract Roblox place files (.rbxlx) into folders")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Extract a place into a folder hierarchy
+    Extract {
+        input: PathBuf,
+        /// Output folder (defaults to `<input>_extracted`)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        options: ExtractArgs,
+    },
+    /// Show what `extract` would write without touching the disk
+    Plan {
+        input: PathBuf,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        options: ExtractArgs,
+    },
+    /// Undo script normalization in an extracted folder using its normalization.json
+    RestoreScripts {
+        /// Folder written by `extract`
+        folder: PathBuf,
+    },
+    /// Build the require() dependency graph of all scripts
+    Deps {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = GraphFormat::Json)]
+        format: GraphFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Parse every script and list syntax errors; fails when any script is invalid
+    Check {
+        input: PathBuf,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// List every asset id referenced by properties and script string literals
+    Assets {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// List every Sound with its playback settings, SoundGroup and the world position of the part it plays from
+    Sounds {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = TableFormat::Csv)]
+        format: TableFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// List scripts with identical sources, exactly or ignoring formatting
+    Duplicates {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// List remotes and bindables with the scripts that fire, invoke or listen to them
+    Remotes {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// List packages (PackageLink roots) and LinkedSource scripts, and warn about copies of a package that differ
+    Packages {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Find a regex in script sources, property values and instance names without extracting
+    Search {
+        input: PathBuf,
+        pattern: String,
+        /// Treat the pattern as plain text
+        #[arg(short = 'F', long)]
+        fixed_strings: bool,
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// What to search, comma separated
+        #[arg(long = "in", value_enum, value_delimiter = ',', default_values_t = [SearchField::Sources, SearchField::Properties, SearchField::Names])]
+        fields: Vec<SearchField>,
+        /// Source lines shown around each match
+        #[arg(short = 'C', long, default_value_t = DEFAULT_CONTEXT_LINES)]
+        context: usize,
+        #[arg(long, default_value_t = DEFAULT_MAX_RESULTS)]
+        max_results: usize,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Export Workspace parts as a glTF or OBJ scene for Blender and other 3D tools
+    Geometry {
+        input: PathBuf,
+        /// Output file; the extension picks the format (.glb, .gltf or .obj)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Overrides the format given by the output extension
+        #[arg(long, value_enum)]
+        format: Option<GeometryFormat>,
+        /// Top-level instance to export
+        #[arg(long, default_value = "Workspace")]
+        root: String,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Decode the smooth terrain into raw voxel arrays, a heightmap PNG and a material summary
+    Terrain {
+        input: PathBuf,
+        /// Output folder, `<input>_terrain` by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Decode union and mesh part payloads (MeshData, ChildData, PhysicsData) into OBJ files
+    MeshData {
+        input: PathBuf,
+        /// Output folder with one subfolder per instance, `<input>_meshes` by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Extract images embedded in binary properties (PNG, JPEG, TGA, DDS, KTX) with PNG copies and thumbnails
+    Images {
+        input: PathBuf,
+        /// Output folder with one subfolder per instance, `<input>_images` by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Convert every KeyframeSequence into a JSON file with keyframes, poses, easing and markers
+    Animations {
+        input: PathBuf,
+        /// Output folder, `<input>_animations` by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Also write a BVH file per animation, sampled at 30 fps
+        #[arg(long)]
+        bvh: bool,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Render every ScreenGui and SurfaceGui to a static HTML preview
+    Gui {
+        input: PathBuf,
+        /// Output folder, `<input>_gui` by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Screen size ScreenGuis are laid out on
+        #[arg(long, value_parser = gui::parse_resolution, default_value = "1920x1080")]
+        resolution: (u32, u32),
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Render an orthographic top-down PNG of Workspace, with SpawnLocations and named Models marked
+    Map {
+        input: PathBuf,
+        /// Output image, `<input>_map.png` by default; a .json with the scale and markers is written next to it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Longest side of the image in pixels
+        #[arg(long, default_value_t = map::DEFAULT_SIZE, value_parser = clap::value_parser!(u32).range(map::MIN_SIZE as i64..=map::MAX_SIZE as i64))]
+        size: u32,
+        /// Area to render in studs as minX,minZ,maxX,maxZ; all parts by default
+        #[arg(long, value_parser = map::parse_bounds, allow_hyphen_values = true)]
+        bounds: Option<map::MapBounds>,
+        /// Color parts by their own color, their material or their class
+        #[arg(long, value_enum, default_value_t = map::MapColoring::Color)]
+        color_by: map::MapColoring,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Save Lighting and its effects (Atmosphere, Sky, Bloom, ...) as a typed preset JSON
+    Lighting {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Write a lighting preset into a place, replacing its Lighting properties and effects
+    ApplyLighting {
+        /// Place to change
+        place: PathBuf,
+        /// Preset written by `lighting`
+        preset: PathBuf,
+        /// Output place, `<place>_lighting.rbxlx` by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Count instances, classes, script lines and data sizes of a place
+    Stats {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// List scripts that never run: misplaced, disabled or modules nothing requires
+    DeadScripts {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+    /// Scan scripts for patterns common in backdoored free models
+    Audit {
+        input: PathBuf,
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        #[command(flatten)]
+        filters: FilterArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct FilterArgs {
+    #[arg(long)]
+    exclude_workspace: bool,
+    #[arg(long)]
+    exclude_scripts: bool,
+    /// Comma separated list of class names to skip
+    #[arg(long, value_delimiter = ',')]
+    exclude_classes: Vec<String>,
+}
+
+/// Options that only change what an extraction writes, for `extract` and `plan`.
+#[derive(Args)]
+pub struct ExtractArgs {
+    #[command(flatten)]
+    filters: FilterArgs,
+    #[arg(long, default_value_t = DEFAULT_MAX_PATH_LENGTH)]
+    max_path_length: usize,
+    /// Layout of the written output
+    #[arg(long = "output-format", value_enum, default_value_t = OutputFormat::Folders)]
+    output_format: OutputFormat,
+    /// Store sources shared by several scripts once under sources/ and reference them from script.ref
+    #[arg(long)]
+    dedup_scripts: bool,
+    /// Extract every package once under packages/ and reference it from package.ref in each copy
+    #[arg(long)]
+    dedup_packages: bool,
+    /// Line endings of written scripts
+    #[arg(long, value_enum, default_value_t = LineEndings::Keep)]
+    line_endings: LineEndings,
+    /// Remove a UTF-8 byte order mark from written scripts
+    #[arg(long)]
+    strip_bom: bool,
+    /// Rewrite the leading whitespace of script lines
+    #[arg(long, value_enum, default_value_t = Indentation::Keep)]
+    indentation: Indentation,
+    /// Spaces per tab for --indentation
+    #[arg(long, default_value_t = DEFAULT_INDENT_WIDTH)]
+    indent_width: usize,
+    /// Remove spaces and tabs at the end of script lines
+    #[arg(long)]
+    trim_trailing_whitespace: bool,
+    /// Pipe every script through this command, e.g. "stylua -"
+    #[arg(long)]
+    formatter: Option<String>,
+    /// Also write reports/dependencies.json and .dot
+    #[arg(long)]
+    report_dependencies: bool,
+    /// Also write reports/audit.json and .md
+    #[arg(long)]
+    report_audit: bool,
+    /// Also write reports/assets.json and .csv
+    #[arg(long)]
+    report_assets: bool,
+    /// Also write reports/sounds.json and .csv
+    #[arg(long)]
+    report_sounds: bool,
+    /// Also write reports/duplicates.json and .txt
+    #[arg(long)]
+    report_duplicates: bool,
+    /// Also write reports/remotes.json and .txt
+    #[arg(long)]
+    report_remotes: bool,
+    /// Also write reports/packages.json and .txt
+    #[arg(long)]
+    report_packages: bool,
+    /// Also write reports/dead_scripts.json and .txt
+    #[arg(long)]
+    report_dead_scripts: bool,
+    /// Also write reports/syntax.json
+    #[arg(long)]
+    report_syntax: bool,
+    /// Write reports/ast/<script>.json for every script that parses; implies --report-syntax
+    #[arg(long)]
+    syntax_ast: bool,
+    /// Also write Workspace parts to exports/workspace.glb
+    #[arg(long)]
+    export_geometry: bool,
+    /// Also decode the smooth terrain into exports/terrain/
+    #[arg(long)]
+    export_terrain: bool,
+    /// Write decoded union and mesh part payloads next to each properties.json
+    #[arg(long)]
+    export_mesh_data: bool,
+    /// Write images embedded in binary properties next to each properties.json
+    #[arg(long)]
+    export_images: bool,
+    /// Also write every KeyframeSequence to exports/animations/
+    #[arg(long)]
+    export_animations: bool,
+    /// Add a BVH file next to each exported animation
+    #[arg(long)]
+    export_animations_bvh: bool,
+    /// Also render ScreenGuis and SurfaceGuis to exports/gui/*.html
+    #[arg(long)]
+    export_gui: bool,
+    /// Screen size of the GUI previews, e.g. 1280x720
+    #[arg(long, value_parser = gui::parse_resolution)]
+    gui_resolution: Option<(u32, u32)>,
+    /// Also save Lighting and its effects to exports/lighting.json
+    #[arg(long)]
+    export_lighting: bool,
+    /// Also render a top-down image of Workspace to exports/map.png
+    #[arg(long)]
+    export_map: bool,
+    /// Longest side of the map in pixels
+    #[arg(long, value_parser = clap::value_parser!(u32).range(map::MIN_SIZE as i64..=map::MAX_SIZE as i64))]
+    map_size: Option<u32>,
+    /// Area of the map in studs as minX,minZ,maxX,maxZ
+    #[arg(long, value_parser = map::parse_bounds, allow_hyphen_values = true)]
+    map_bounds: Option<map::MapBounds>,
+    /// What the map colors parts by
+    #[arg(long, value_enum, default_value_t = map::MapColoring::Color)]
+    map_color_by: map::MapColoring,
+    /// Also write reports/stats.json and .md
+    #[arg(long)]
+    report_stats: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TableFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    Json,
+    Dot,
+}
+
+impl FilterArgs {
+    /// Settings for loading the place; everything that only affects written output keeps its default.
+    fn settings(&self) -> ExtractionSettings {
+        ExtractionSettings {
+            exclude_workspace: self.exclude_workspace,
+            exclude_scripts: self.exclude_scripts,
+            exclude_classes: self.exclude_classes.iter()
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            max_path_length: DEFAULT_MAX_PATH_LENGTH,
+            format: OutputFormat::Folders,
+            dedup_scripts: false,
+            dedup_packages: false,
+            normalize: NormalizeSettings::default(),
+            reports: ReportSettings::default(),
+            exports: ExportSettings::default(),
+        }
+    }
+}
+
+impl ExtractArgs {
+    fn settings(&self) -> ExtractionSettings {
+        ExtractionSettings {
+            max_path_length: self.max_path_length,
+            format: self.output_format,
+            dedup_scripts: self.dedup_scripts,
+            dedup_packages: self.dedup_packages,
+            normalize: NormalizeSettings {
+                line_endings: self.line_endings,
+                strip_bom: self.strip_bom,
+                indentation: self.indentation,
+                indent_width: self.indent_width,
+                trim_trailing_whitespace: self.trim_trailing_whitespace,
+                formatter: self.formatter.clone(),
+            },
+            reports: ReportSettings {
+                dependencies: self.report_dependencies,
+                audit: self.report_audit,
+                assets: self.report_assets,
+                sounds: self.report_sounds,
+                duplicates: self.report_duplicates,
+                remotes: self.report_remotes,
+                packages: self.report_packages,
+                dead_scripts: self.report_dead_scripts,
+                syntax: self.report_syntax || self.syntax_ast,
+                stats: self.report_stats,
+                syntax_ast: self.syntax_ast,
+            },
+            exports: ExportSettings {
+                geometry: self.export_geometry,
+                terrain: self.export_terrain,
+                mesh_data: self.export_mesh_data,
+                images: self.export_images,
+                animations: self.export_animations || self.export_animations_bvh,
+                animation_bvh: self.export_animations_bvh,
+                gui: self.export_gui,
+                gui_resolution: self.gui_resolution,
+                lighting: self.export_lighting,
+                map: self.export_map,
+                map_size: self.map_size,
+                map_bounds: self.map_bounds,
+                map_color_by: self.map_color_by,
+            },
+            ..self.filters.settings()
+        }
+    }
+}
+
+pub fn default_output(input: &Path) -> PathBuf {
+    let mut out = input.to_path_buf();
+    out.set_extension("");
+    PathBuf::from(format!("{}_extracted", out.display()))
+}
+
+fn write_output(output: Option<&Path>, contents: &str) -> anyhow::Result<()> {
+    match output {
+        Some(path) => fs::write(path, contents)?,
+        None => std::io::stdout().lock().write_all(contents.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// Writes the files of every instance that has some into `output/<path>/`, one folder per instance.
+fn write_instance_files(dom: &Dom, output: &Path, files: impl Fn(&Instance) -> Vec<(String, Vec<u8>)>) -> anyhow::Result<()> {
+    let mut paths = Vec::new();
+    let mut found = Vec::new();
+    for (id, instance) in dom.instances.iter().enumerate() {
+        let files = files(instance);
+        if !files.is_empty() {
+            paths.push(dom.path(id));
+            found.push(files);
+        }
+    }
+    // Named together so folders that differ only by case or get truncated alike are numbered.
+    let folders = file_stems(paths.iter().map(String::as_str));
+    for (files, folder) in found.into_iter().zip(folders) {
+        let dir = output.join(folder);
+        fs::create_dir_all(&dir)?;
+        for (name, bytes) in files {
+            fs::write(dir.join(name), bytes)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Extract { input, output, options } => {
+            let output = output.unwrap_or_else(|| default_output(&input));
+            let summary = process_file_with_progress(&input, &output, &options.settings(), &|_, message| {
+                eprint!("\r{}", message);
+                let _ = std::io::stderr().flush();
+            })?;
+            eprintln!();
+            println!("{}", summary.message());
+        }
+        Command::Plan { input, output, format, options } => {
+            let output = output.unwrap_or_else(|| default_output(&input));
+            let plan = build_plan(&input, &output, &options.settings())?;
+            match format {
+                ReportFormat::Text => print!("{}", render_text(&plan)),
+                ReportFormat::Json => {
+                    serde_json::to_writer_pretty(std::io::stdout().lock(), &plan)?;
+                    println!();
+                    eprintln!("{}", summary(&plan));
+                }
+            }
+        }
+        Command::RestoreScripts { folder } => {
+            let restored = restore_folder(&folder)?;
+            eprintln!("{} scripts restored", restored);
+        }
+        Command::Deps { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let graph = deps::analyze(&dom);
+            let rendered = match format {
+                GraphFormat::Json => serde_json::to_string_pretty(&graph)? + "\n",
+                GraphFormat::Dot => deps::to_dot(&graph),
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", deps::summary(&graph));
+        }
+        Command::Check { input, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let (report, _) = syntax::check(&dom, false)?;
+            match format {
+                ReportFormat::Text => print!("{}", syntax::render_text(&report)),
+                ReportFormat::Json => {
+                    serde_json::to_writer_pretty(std::io::stdout().lock(), &report)?;
+                    println!();
+                }
+            }
+            if report.invalid > 0 {
+                anyhow::bail!("{} scripts have syntax errors", report.invalid);
+            }
+        }
+        Command::Assets { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let inventory = assets::analyze(&dom);
+            let rendered = match format {
+                TableFormat::Csv => assets::to_csv(&inventory),
+                TableFormat::Json => serde_json::to_string_pretty(&inventory)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", assets::summary(&inventory));
+        }
+        Command::Sounds { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let manifest = sounds::analyze(&dom);
+            let rendered = match format {
+                TableFormat::Csv => sounds::to_csv(&manifest),
+                TableFormat::Json => serde_json::to_string_pretty(&manifest)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", sounds::summary(&manifest));
+        }
+        Command::Duplicates { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let report = dedup::analyze(&dom);
+            let rendered = match format {
+                ReportFormat::Text => dedup::render_text(&report),
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", dedup::summary(&report));
+        }
+        Command::Remotes { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let report = remotes::analyze(&dom);
+            let rendered = match format {
+                ReportFormat::Text => remotes::render_text(&report),
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", remotes::summary(&report));
+        }
+        Command::Packages { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let report = packages::analyze(&dom);
+            let rendered = match format {
+                ReportFormat::Text => packages::render_text(&report),
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", packages::summary(&report));
+        }
+        Command::Search { input, pattern, fixed_strings, ignore_case, fields, context, max_results, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let query = SearchQuery { pattern, fixed_string: fixed_strings, ignore_case, fields, context, max_results };
+            let results = search::search(&dom, &query)?;
+            match format {
+                ReportFormat::Text => print!("{}", search::render_text(&results)),
+                ReportFormat::Json => {
+                    serde_json::to_writer_pretty(std::io::stdout().lock(), &results)?;
+                    println!();
+                    eprintln!("{}", search::summary(&results));
+                }
+            }
+        }
+        Command::Geometry { input, output, format, root, filters } => {
+            let format = format
+                .or_else(|| output.as_deref().and_then(GeometryFormat::from_path))
+                .unwrap_or(GeometryFormat::Glb);
+            let output = output.unwrap_or_else(|| input.with_extension(format.extension()));
+            let dom = Dom::load(&input, &filters.settings())?;
+            let root = dom.find_root(&root).ok_or_else(|| anyhow::anyhow!("{} is not a top-level instance of the place", root))?;
+            let scene = geometry::build_scene(&dom, root);
+            let stem = output.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "scene".to_string());
+            let dir = output.parent().unwrap_or(Path::new(""));
+            for (name, bytes) in geometry_files(&scene, format, &stem)? {
+                fs::write(dir.join(name), bytes)?;
+            }
+            eprintln!("{}", geometry::summary(&scene));
+        }
+        Command::Terrain { input, output, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let decoded = terrain::load(&dom).ok_or_else(|| anyhow::anyhow!("the place has no smooth terrain"))??;
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                input.with_file_name(format!("{}_terrain", stem))
+            });
+            fs::create_dir_all(&output)?;
+            for (name, bytes) in terrain::terrain_files(&decoded)? {
+                fs::write(output.join(name), bytes)?;
+            }
+            eprintln!("{}", terrain::summary(&decoded));
+        }
+        Command::MeshData { input, output, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                input.with_file_name(format!("{}_meshes", stem))
+            });
+            let meshes = MeshDataExport::new(&dom.shared_strings);
+            write_instance_files(&dom, &output, |instance| meshes.files(&instance.class, &instance.properties))?;
+            eprintln!("{}", meshes.summary());
+        }
+        Command::Images { input, output, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                input.with_file_name(format!("{}_images", stem))
+            });
+            let images = ImageExport::new(&dom.shared_strings);
+            write_instance_files(&dom, &output, |instance| images.files(&instance.properties))?;
+            eprintln!("{}", images.summary());
+        }
+        Command::Animations { input, output, bvh, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let set = animation::collect(&dom);
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                input.with_file_name(format!("{}_animations", stem))
+            });
+            fs::create_dir_all(&output)?;
+            for (name, bytes) in animation::animation_files(&set, bvh)? {
+                fs::write(output.join(name), bytes)?;
+            }
+            eprintln!("{}", animation::summary(&set));
+        }
+        Command::Gui { input, output, resolution, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let pages = gui::render_all(&dom, resolution);
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                input.with_file_name(format!("{}_gui", stem))
+            });
+            fs::create_dir_all(&output)?;
+            for (name, bytes) in gui::gui_files(&pages) {
+                fs::write(output.join(name), bytes)?;
+            }
+            eprintln!("{}", gui::summary(&pages));
+        }
+        Command::Map { input, output, size, bounds, color_by, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let workspace = dom.find_service("Workspace").ok_or_else(|| anyhow::anyhow!("the place has no Workspace"))?;
+            let map = map::render(&dom, workspace, size, bounds, color_by).ok_or_else(|| anyhow::anyhow!("Workspace has no parts to draw"))?;
+            let output = output.unwrap_or_else(|| {
+                let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                input.with_file_name(format!("{}_map.png", stem))
+            });
+            let stem = output.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let dir = output.parent().unwrap_or(Path::new(""));
+            if !dir.as_os_str().is_empty() {
+                fs::create_dir_all(dir)?;
+            }
+            for (name, bytes) in map::map_files(&map, &stem)? {
+                fs::write(dir.join(name), bytes)?;
+            }
+            eprintln!("{}", map::summary(&map));
+        }
+        Command::Lighting { input, output, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let preset = lighting::collect(&dom).ok_or_else(|| anyhow::anyhow!("the place has no Lighting service"))?;
+            write_output(output.as_deref(), &(serde_json::to_string_pretty(&preset)? + "\n"))?;
+            eprintln!("{}", lighting::summary(&preset));
+        }
+        Command::ApplyLighting { place, preset, output } => {
+            let preset: lighting::LightingPreset = serde_json::from_str(&fs::read_to_string(&preset)?)?;
+            let text = fs::read_to_string(&place)?;
+            let updated = lighting::apply(&text, &preset)?;
+            let output = output.unwrap_or_else(|| {
+                let stem = place.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+                place.with_file_name(format!("{}_lighting.rbxlx", stem))
+            });
+            fs::write(&output, updated)?;
+            eprintln!("{} → {}", lighting::summary(&preset), output.display());
+        }
+        Command::Stats { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let place = stats::analyze(&dom);
+            let rendered = match format {
+                ReportFormat::Text => stats::render_markdown(&place),
+                ReportFormat::Json => serde_json::to_string_pretty(&place)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", stats::summary(&place));
+        }
+        Command::DeadScripts { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let report = dead::analyze(&dom);
+            let rendered = match format {
+                ReportFormat::Text => dead::render_text(&report),
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", dead::summary(&report));
+        }
+        Command::Audit { input, output, format, filters } => {
+            let dom = Dom::load(&input, &filters.settings())?;
+            let report = audit::audit(&dom);
+            let rendered = match format {
+                ReportFormat::Text => audit::render_text(&report),
+                ReportFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+            };
+            write_output(output.as_deref(), &rendered)?;
+            eprintln!("{}", audit::summary(&report));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_folders_stay_apart_on_case_insensitive_disks() {
+        let dom = Dom::parse(r#"<roblox version="4">
+<Item class="Workspace" referent="A"><Properties><string name="Name">Workspace</string></Properties>
+<Item class="Part" referent="B"><Properties><string name="Name">Part</string></Properties></Item>
+<Item class="Part" referent="C"><Properties><string name="Name">part</string></Properties></Item>
+<Item class="Part" referent="D"><Properties><string name="Name">CON</string></Properties></Item>
+</Item>
+</roblox>"#);
+        let output = std::env::temp_dir().join(format!("rbx_ripper_test_{}_instance_files", std::process::id()));
+        let _ = fs::remove_dir_all(&output);
+        write_instance_files(&dom, &output, |instance| {
+            if instance.class == "Part" { vec![("name.txt".to_string(), instance.name.clone().into_bytes())] } else { Vec::new() }
+        }).unwrap();
+        let mut folders: Vec<String> = fs::read_dir(&output).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        folders.sort();
+        assert_eq!(folders, ["Workspace.CON", "Workspace.Part", "Workspace.part~2"]);
+        assert_eq!(fs::read_to_string(output.join("Workspace.part~2").join("name.txt")).unwrap(), "part");
+        let _ = fs::remove_dir_all(&output);
+    }
+}
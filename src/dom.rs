@@ -0,0 +1,143 @@
+use crate::extract::{child_items, read_item, should_exclude_node, top_level_items, ExtractionSettings};
+use crate::properties::{read_typed_properties, Property, PropertyValue};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+pub struct Instance {
+    pub referent: Option<String>,
+    pub class: String,
+    pub name: String,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    pub properties: Vec<Property>,
+    pub source: Option<String>,
+}
+
+impl Instance {
+    pub fn property(&self, name: &str) -> Option<&PropertyValue> {
+        self.properties.iter().find(|p| p.name == name).map(|p| &p.value)
+    }
+}
+
+/// The parsed instance tree with typed properties, owned so analyses can outlive the XML text.
+/// Subtrees removed by the extraction filters are not part of it.
+pub struct Dom {
+    pub instances: Vec<Instance>,
+    pub roots: Vec<usize>,
+    /// Base64 payloads of the `<SharedStrings>` section, keyed by the hash `SharedString` properties refer to.
+    pub shared_strings: HashMap<String, String>,
+}
+
+/// Base64 payloads of the `<SharedStrings>` section, keyed by the hash `SharedString` properties refer to.
+pub fn read_shared_strings(doc: &roxmltree::Document) -> HashMap<String, String> {
+    let mut shared_strings = HashMap::new();
+    let sections = doc.root_element().children().filter(|n| n.has_tag_name("SharedStrings"));
+    for node in sections.flat_map(|s| s.children()).filter(|n| n.has_tag_name("SharedString")) {
+        if let Some(key) = node.attribute("md5") {
+            let payload: String = node.text().unwrap_or("").split_whitespace().collect();
+            shared_strings.insert(key.to_string(), payload);
+        }
+    }
+    shared_strings
+}
+
+impl Dom {
+    pub fn load(input: &Path, settings: &ExtractionSettings) -> anyhow::Result<Self> {
+        let text = fs::read_to_string(input)?;
+        let doc = roxmltree::Document::parse(&text)?;
+        Ok(Self::build(&doc, settings))
+    }
+
+    pub fn build(doc: &roxmltree::Document, settings: &ExtractionSettings) -> Self {
+        let mut dom = Dom { instances: Vec::new(), roots: Vec::new(), shared_strings: HashMap::new() };
+        for node in top_level_items(doc) {
+            if let Some(id) = dom.add_recursive(node, None, settings) {
+                dom.roots.push(id);
+            }
+        }
+        dom.shared_strings = read_shared_strings(doc);
+        dom
+    }
+
+    fn add_recursive(&mut self, node: roxmltree::Node, parent: Option<usize>, settings: &ExtractionSettings) -> Option<usize> {
+        if should_exclude_node(node, settings) {
+            return None;
+        }
+
+        let item = read_item(node);
+        let id = self.instances.len();
+        self.instances.push(Instance {
+            referent: node.attribute("referent").map(|r| r.to_string()),
+            class: item.class_name,
+            name: item.name,
+            parent,
+            children: Vec::new(),
+            properties: read_typed_properties(node).into_iter().filter(|p| p.name != "Source").collect(),
+            source: item.source,
+        });
+
+        for child in child_items(node) {
+            if let Some(child_id) = self.add_recursive(child, Some(id), settings) {
+                self.instances[id].children.push(child_id);
+            }
+        }
+        Some(id)
+    }
+
+    pub fn find_child(&self, id: usize, name: &str) -> Option<usize> {
+        self.instances[id].children.iter().copied().find(|&c| self.instances[c].name == name)
+    }
+
+    /// A top-level service looked up the way `game:GetService` does, by class name.
+    pub fn find_service(&self, class: &str) -> Option<usize> {
+        self.roots.iter().copied().find(|&r| self.instances[r].class == class)
+    }
+
+    pub fn find_root(&self, name: &str) -> Option<usize> {
+        self.roots.iter().copied().find(|&r| self.instances[r].name == name)
+    }
+
+    /// Dotted path in the style of `Instance:GetFullName()`, e.g. `Workspace.Map.Part`.
+    pub fn path(&self, id: usize) -> String {
+        let mut names = Vec::new();
+        let mut current = Some(id);
+        while let Some(i) = current {
+            names.push(self.instances[i].name.as_str());
+            current = self.instances[i].parent;
+        }
+        names.reverse();
+        names.join(".")
+    }
+
+    /// Instance ids in depth-first pre-order, matching the document order.
+    pub fn iter_depth_first(&self) -> Vec<usize> {
+        let mut order = Vec::with_capacity(self.instances.len());
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.instances[id].children.iter().rev());
+        }
+        order
+    }
+}
+
+#[cfg(test)]
+impl Dom {
+    /// The tree of place file XML with nothing filtered out.
+    pub fn parse(xml: &str) -> Self {
+        let settings = ExtractionSettings {
+            exclude_workspace: false,
+            exclude_scripts: false,
+            exclude_classes: Vec::new(),
+            max_path_length: crate::path_policy::DEFAULT_MAX_PATH_LENGTH,
+            format: crate::extract::OutputFormat::Folders,
+            dedup_scripts: false,
+            dedup_packages: false,
+            normalize: Default::default(),
+            reports: Default::default(),
+            exports: Default::default(),
+        };
+        Self::build(&roxmltree::Document::parse(xml).unwrap(), &settings)
+    }
+}
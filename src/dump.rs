@@ -0,0 +1,89 @@
+use crate::dom::Dom;
+use crate::properties::serialize_properties;
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::io::Write;
+
+pub const JSON_DUMP_FILE: &str = "place.json";
+pub const NDJSON_DUMP_FILE: &str = "place.ndjson";
+
+struct NestedInstance<'a> {
+    dom: &'a Dom,
+    id: usize,
+}
+
+struct Properties<'a>(&'a [crate::properties::Property]);
+
+impl Serialize for Properties<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_properties(self.0, serializer)
+    }
+}
+
+struct Children<'a> {
+    dom: &'a Dom,
+    ids: &'a [usize],
+}
+
+impl Serialize for Children<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.ids.len()))?;
+        for &id in self.ids {
+            seq.serialize_element(&NestedInstance { dom: self.dom, id })?;
+        }
+        seq.end()
+    }
+}
+
+impl Serialize for NestedInstance<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let instance = &self.dom.instances[self.id];
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("referent", &instance.referent)?;
+        map.serialize_entry("class", &instance.class)?;
+        map.serialize_entry("name", &instance.name)?;
+        map.serialize_entry("properties", &Properties(&instance.properties))?;
+        if let Some(source) = &instance.source {
+            map.serialize_entry("source", source)?;
+        }
+        map.serialize_entry("children", &Children { dom: self.dom, ids: &instance.children })?;
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+struct FlatInstance<'a> {
+    referent: &'a Option<String>,
+    parent: Option<&'a str>,
+    path: String,
+    class: &'a str,
+    name: &'a str,
+    properties: Properties<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+}
+
+/// One document holding the whole tree, children nested inside their parents.
+pub fn write_json<W: Write>(dom: &Dom, writer: W) -> anyhow::Result<()> {
+    serde_json::to_writer_pretty(writer, &Children { dom, ids: &dom.roots })?;
+    Ok(())
+}
+
+/// One instance per line in depth-first order, linked to its parent by referent.
+pub fn write_ndjson<W: Write>(dom: &Dom, mut writer: W) -> anyhow::Result<()> {
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        let line = FlatInstance {
+            referent: &instance.referent,
+            parent: instance.parent.and_then(|p| dom.instances[p].referent.as_deref()),
+            path: dom.path(id),
+            class: &instance.class,
+            name: &instance.name,
+            properties: Properties(&instance.properties),
+            source: instance.source.as_deref(),
+        };
+        serde_json::to_writer(&mut writer, &line)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,482 @@
+use crate::dom::{Dom, Instance};
+use crate::export::file_stems;
+use crate::export::geometry::{cframe, Transform};
+use crate::properties::PropertyValue;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+
+/// Sampling rate of the BVH export.
+pub const BVH_FRAME_RATE: f64 = 30.0;
+/// Longest stretch of an animation written to BVH; keyframe times beyond it are usually broken data.
+pub const MAX_BVH_LENGTH: f64 = 600.0;
+
+#[derive(Serialize)]
+pub struct Marker {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Serialize)]
+pub struct PoseCFrame {
+    pub position: [f64; 3],
+    /// Rotation matrix rows.
+    pub rotation: [[f64; 3]; 3],
+    /// Degrees as returned by `CFrame:ToOrientation()`, applied in Y, X, Z order.
+    pub orientation: [f64; 3],
+}
+
+#[derive(Serialize)]
+pub struct Pose {
+    /// The joint's Part1 name, e.g. `LowerTorso`.
+    pub joint: String,
+    pub parent: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cframe: Option<PoseCFrame>,
+    /// Value of a `NumberPose`, used for facial animation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    pub weight: f64,
+    pub easing_style: &'static str,
+    pub easing_direction: &'static str,
+    #[serde(skip)]
+    transform: Transform,
+}
+
+#[derive(Serialize)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct Keyframe {
+    pub name: String,
+    pub time: f64,
+    pub poses: Vec<Pose>,
+    pub markers: Vec<Marker>,
+}
+
+#[derive(Serialize)]
+pub struct Animation {
+    pub name: String,
+    pub path: String,
+    pub looped: bool,
+    pub priority: String,
+    /// Time of the last keyframe in seconds.
+    pub length: f64,
+    /// Joints in the order they first appear.
+    pub joints: Vec<Joint>,
+    pub keyframes: Vec<Keyframe>,
+}
+
+#[derive(Serialize)]
+pub struct AnimationSet {
+    pub animations: Vec<Animation>,
+}
+
+fn float(instance: &Instance, name: &str) -> Option<f64> {
+    match instance.property(name) {
+        Some(PropertyValue::Float(value)) => Some(*value),
+        Some(PropertyValue::Int(value)) => Some(*value as f64),
+        _ => None,
+    }
+}
+
+fn token(instance: &Instance, name: &str) -> Option<u32> {
+    match instance.property(name) {
+        Some(PropertyValue::Token(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+/// Enum.PoseEasingStyle
+fn easing_style(value: Option<u32>) -> &'static str {
+    match value.unwrap_or(0) {
+        0 => "Linear",
+        1 => "Constant",
+        2 => "Elastic",
+        3 => "Cubic",
+        4 => "Bounce",
+        5 => "CubicV2",
+        _ => "Unknown",
+    }
+}
+
+/// Enum.PoseEasingDirection
+fn easing_direction(value: Option<u32>) -> &'static str {
+    match value.unwrap_or(0) {
+        0 => "Out",
+        1 => "In",
+        2 => "InOut",
+        _ => "Unknown",
+    }
+}
+
+/// Enum.AnimationPriority
+fn priority(value: Option<u32>) -> String {
+    match value.unwrap_or(1000) {
+        0 => "Idle".to_string(),
+        1 => "Movement".to_string(),
+        2 => "Action".to_string(),
+        3 => "Action2".to_string(),
+        4 => "Action3".to_string(),
+        5 => "Action4".to_string(),
+        1000 => "Core".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Degrees without negative zeros, which would otherwise show up as `-0` in the output.
+fn degrees(radians: f64) -> f64 {
+    radians.to_degrees() + 0.0
+}
+
+/// Angles of `R = Ry * Rx * Rz`, the decomposition of `CFrame:ToOrientation()`.
+fn orientation_yxz(r: &[[f64; 3]; 3]) -> [f64; 3] {
+    let x = (-r[1][2]).clamp(-1.0, 1.0).asin();
+    let y = r[0][2].atan2(r[2][2]);
+    let z = r[1][0].atan2(r[1][1]);
+    [x, y, z].map(degrees)
+}
+
+/// Angles of `R = Rz * Rx * Ry`, matching the BVH channel order `Zrotation Xrotation Yrotation`.
+fn euler_zxy(r: &[[f64; 3]; 3]) -> [f64; 3] {
+    let x = r[2][1].clamp(-1.0, 1.0).asin();
+    let y = (-r[2][0]).atan2(r[2][2]);
+    let z = (-r[0][1]).atan2(r[1][1]);
+    [z, x, y].map(degrees)
+}
+
+fn collect_poses(dom: &Dom, id: usize, parent: Option<&str>, poses: &mut Vec<Pose>, markers: &mut Vec<Marker>) {
+    for &child in &dom.instances[id].children {
+        let instance = &dom.instances[child];
+        match instance.class.as_str() {
+            "Pose" | "NumberPose" => {
+                let transform = cframe(instance).unwrap_or(Transform::IDENTITY);
+                poses.push(Pose {
+                    joint: instance.name.clone(),
+                    parent: parent.map(str::to_string),
+                    cframe: (instance.class == "Pose").then(|| PoseCFrame {
+                        position: transform.position,
+                        rotation: transform.rotation,
+                        orientation: orientation_yxz(&transform.rotation),
+                    }),
+                    value: if instance.class == "NumberPose" { float(instance, "Value") } else { None },
+                    weight: float(instance, "Weight").unwrap_or(1.0),
+                    easing_style: easing_style(token(instance, "EasingStyle")),
+                    easing_direction: easing_direction(token(instance, "EasingDirection")),
+                    transform,
+                });
+                collect_poses(dom, child, Some(&instance.name), poses, markers);
+            }
+            "KeyframeMarker" => markers.push(Marker {
+                name: instance.name.clone(),
+                value: match instance.property("Value") {
+                    Some(PropertyValue::String(value)) => value.clone(),
+                    _ => String::new(),
+                },
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn read_animation(dom: &Dom, id: usize) -> Animation {
+    let sequence = &dom.instances[id];
+    let mut keyframes: Vec<Keyframe> = sequence.children.iter().copied()
+        .filter(|&c| dom.instances[c].class == "Keyframe")
+        .map(|c| {
+            let keyframe = &dom.instances[c];
+            let mut poses = Vec::new();
+            let mut markers = Vec::new();
+            collect_poses(dom, c, None, &mut poses, &mut markers);
+            Keyframe { name: keyframe.name.clone(), time: float(keyframe, "Time").unwrap_or(0.0), poses, markers }
+        })
+        .collect();
+    keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+
+    let mut joints = Vec::new();
+    let mut seen = HashSet::new();
+    for pose in keyframes.iter().flat_map(|k| &k.poses) {
+        if seen.insert(pose.joint.clone()) {
+            joints.push(Joint { name: pose.joint.clone(), parent: pose.parent.clone() });
+        }
+    }
+
+    Animation {
+        name: sequence.name.clone(),
+        path: dom.path(id),
+        looped: matches!(sequence.property("Loop"), Some(PropertyValue::Bool(true))),
+        priority: priority(token(sequence, "Priority")),
+        length: keyframes.last().map(|k| k.time).unwrap_or(0.0),
+        joints,
+        keyframes,
+    }
+}
+
+/// Every KeyframeSequence in the place, in document order.
+pub fn collect(dom: &Dom) -> AnimationSet {
+    let animations = dom.iter_depth_first().into_iter()
+        .filter(|&id| dom.instances[id].class == "KeyframeSequence")
+        .map(|id| read_animation(dom, id))
+        .collect();
+    AnimationSet { animations }
+}
+
+pub fn summary(set: &AnimationSet) -> String {
+    let keyframes: usize = set.animations.iter().map(|a| a.keyframes.len()).sum();
+    let markers: usize = set.animations.iter().flat_map(|a| &a.keyframes).map(|k| k.markers.len()).sum();
+    format!("Animations: {} keyframe sequences, {} keyframes, {} markers", set.animations.len(), keyframes, markers)
+}
+
+/// `<stem>.json` for every animation, plus `<stem>.bvh` if `bvh` is set.
+pub fn animation_files(set: &AnimationSet, bvh: bool) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let stems = file_stems(set.animations.iter().map(|animation| animation.path.as_str()));
+    for (animation, stem) in set.animations.iter().zip(stems) {
+        files.push((format!("{}.json", stem), serde_json::to_vec_pretty(animation)?));
+        if bvh {
+            files.push((format!("{}.bvh", stem), to_bvh(animation).into_bytes()));
+        }
+    }
+    Ok(files)
+}
+
+fn quaternion(r: &[[f64; 3]; 3]) -> [f64; 4] {
+    let trace = r[0][0] + r[1][1] + r[2][2];
+    let q = if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [0.25 * s, (r[2][1] - r[1][2]) / s, (r[0][2] - r[2][0]) / s, (r[1][0] - r[0][1]) / s]
+    } else if r[0][0] > r[1][1] && r[0][0] > r[2][2] {
+        let s = (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt() * 2.0;
+        [(r[2][1] - r[1][2]) / s, 0.25 * s, (r[0][1] + r[1][0]) / s, (r[0][2] + r[2][0]) / s]
+    } else if r[1][1] > r[2][2] {
+        let s = (1.0 + r[1][1] - r[0][0] - r[2][2]).sqrt() * 2.0;
+        [(r[0][2] - r[2][0]) / s, (r[0][1] + r[1][0]) / s, 0.25 * s, (r[1][2] + r[2][1]) / s]
+    } else {
+        let s = (1.0 + r[2][2] - r[0][0] - r[1][1]).sqrt() * 2.0;
+        [(r[1][0] - r[0][1]) / s, (r[0][2] + r[2][0]) / s, (r[1][2] + r[2][1]) / s, 0.25 * s]
+    };
+    let length = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+    q.map(|v| v / length)
+}
+
+fn rotation([w, x, y, z]: [f64; 4]) -> [[f64; 3]; 3] {
+    [
+        [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y)],
+        [2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x)],
+        [2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y)],
+    ]
+}
+
+/// Blends two transforms by `t`, normalizing the interpolated rotation quaternion.
+fn blend(a: &Transform, b: &Transform, t: f64) -> Transform {
+    let qa = quaternion(&a.rotation);
+    let mut qb = quaternion(&b.rotation);
+    if qa.iter().zip(&qb).map(|(x, y)| x * y).sum::<f64>() < 0.0 {
+        qb = qb.map(|v| -v);
+    }
+    let q: [f64; 4] = std::array::from_fn(|i| qa[i] + (qb[i] - qa[i]) * t);
+    let length = q.iter().map(|v| v * v).sum::<f64>().sqrt();
+    Transform {
+        position: std::array::from_fn(|i| a.position[i] + (b.position[i] - a.position[i]) * t),
+        rotation: rotation(q.map(|v| v / length)),
+    }
+}
+
+/// The pose of `joint` at `time`: held by `Constant` keyframes, otherwise interpolated linearly
+/// between the surrounding keyframes that pose it with a non-zero weight.
+fn sample(animation: &Animation, joint: &str, time: f64) -> Transform {
+    let posed = animation.keyframes.iter()
+        .filter_map(|k| k.poses.iter().find(|p| p.joint == joint && p.cframe.is_some() && p.weight > 0.0).map(|p| (k.time, p)));
+    let mut previous: Option<(f64, &Pose)> = None;
+    for (t, pose) in posed {
+        if t > time {
+            return match previous {
+                Some((start, before)) if before.easing_style != "Constant" && t > start => {
+                    blend(&before.transform, &pose.transform, (time - start) / (t - start))
+                }
+                Some((_, before)) => before.transform,
+                None => pose.transform,
+            };
+        }
+        previous = Some((t, pose));
+    }
+    previous.map(|(_, pose)| pose.transform).unwrap_or(Transform::IDENTITY)
+}
+
+/// Writes `joint` and its descendants, appending them to `order` in the sequence their channels appear.
+fn write_joint<'a>(out: &mut String, children: &HashMap<Option<&'a str>, Vec<&'a str>>, joint: &'a str, depth: usize, order: &mut Vec<&'a str>) {
+    if order.contains(&joint) {
+        return;
+    }
+    let indent = "  ".repeat(depth);
+    let _ = writeln!(out, "{}{} {}", indent, if depth == 0 { "ROOT" } else { "JOINT" }, joint.replace(char::is_whitespace, "_"));
+    let _ = writeln!(out, "{}{{\n{}  OFFSET 0 0 0", indent, indent);
+    let _ = writeln!(out, "{}  CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation", indent);
+    order.push(joint);
+    match children.get(&Some(joint)) {
+        Some(list) => {
+            for child in list {
+                write_joint(out, children, child, depth + 1, order);
+            }
+        }
+        None => {
+            let _ = writeln!(out, "{}  End Site\n{}  {{\n{}    OFFSET 0 0 0\n{}  }}", indent, indent, indent, indent);
+        }
+    }
+    let _ = writeln!(out, "{}}}", indent);
+}
+
+/// A BVH motion file sampled at [`BVH_FRAME_RATE`]. KeyframeSequences carry no rig, so every joint
+/// has a zero offset and the channels hold the pose relative to the joint's rest transform.
+/// Only the first [`MAX_BVH_LENGTH`] seconds are sampled.
+pub fn to_bvh(animation: &Animation) -> String {
+    let mut children: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+    for joint in &animation.joints {
+        children.entry(joint.parent.as_deref()).or_default().push(&joint.name);
+    }
+    let roots = children.get(&None).cloned().unwrap_or_default();
+
+    let mut out = String::from("HIERARCHY\n");
+    let mut order = Vec::new();
+    match roots.as_slice() {
+        [root] => write_joint(&mut out, &children, root, 0, &mut order),
+        _ => {
+            // BVH has a single root, so several top-level poses hang from an unanimated one.
+            let _ = writeln!(out, "ROOT Root\n{{\n  OFFSET 0 0 0\n  CHANNELS 6 Xposition Yposition Zposition Zrotation Xrotation Yrotation");
+            for root in &roots {
+                write_joint(&mut out, &children, root, 1, &mut order);
+            }
+            if roots.is_empty() {
+                let _ = writeln!(out, "  End Site\n  {{\n    OFFSET 0 0 0\n  }}");
+            }
+            let _ = writeln!(out, "}}");
+        }
+    }
+
+    // NaN and negative lengths come out as a single frame.
+    let frames = (animation.length.min(MAX_BVH_LENGTH) * BVH_FRAME_RATE).floor() as usize + 1;
+    let _ = writeln!(out, "MOTION\nFrames: {}\nFrame Time: {:.6}", frames, 1.0 / BVH_FRAME_RATE);
+    for frame in 0..frames {
+        let time = frame as f64 / BVH_FRAME_RATE;
+        let mut values: Vec<String> = Vec::new();
+        if roots.len() != 1 {
+            values.extend(std::iter::repeat_n("0".to_string(), 6));
+        }
+        for joint in &order {
+            let pose = sample(animation, joint, time);
+            values.extend(pose.position.iter().chain(&euler_zxy(&pose.rotation)).map(|v| format!("{:.4}", v)));
+        }
+        let _ = writeln!(out, "{}", values.join(" "));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis_rotation(axis: usize, degrees: f64) -> [[f64; 3]; 3] {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut r = Transform::IDENTITY.rotation;
+        r[a][a] = cos;
+        r[a][b] = -sin;
+        r[b][a] = sin;
+        r[b][b] = cos;
+        r
+    }
+
+    fn product(matrices: &[[[f64; 3]; 3]]) -> [[f64; 3]; 3] {
+        let transforms = matrices.iter().map(|&rotation| Transform { position: [0.0; 3], rotation });
+        transforms.reduce(|a, b| a.then(&b)).unwrap().rotation
+    }
+
+    fn assert_close<const N: usize>(a: [f64; N], b: [f64; N]) {
+        assert!(a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-6), "{:?} != {:?}", a, b);
+    }
+
+    fn assert_same_rotation(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) {
+        for (row_a, row_b) in a.iter().zip(b) {
+            assert_close(*row_a, *row_b);
+        }
+    }
+
+    const ANGLES: &[[f64; 3]] = &[[0.0, 0.0, 0.0], [30.0, -45.0, 60.0], [-80.0, 170.0, -120.0], [10.0, 90.0, 0.0], [0.0, 180.0, 0.0]];
+
+    #[test]
+    fn orientation_matches_to_orientation() {
+        for &[x, y, z] in ANGLES {
+            let r = product(&[axis_rotation(1, y), axis_rotation(0, x), axis_rotation(2, z)]);
+            let [ox, oy, oz] = orientation_yxz(&r);
+            assert_same_rotation(&product(&[axis_rotation(1, oy), axis_rotation(0, ox), axis_rotation(2, oz)]), &r);
+        }
+        assert_close(orientation_yxz(&product(&[axis_rotation(1, -45.0), axis_rotation(0, 30.0), axis_rotation(2, 60.0)])), [30.0, -45.0, 60.0]);
+    }
+
+    #[test]
+    fn bvh_angles_rebuild_the_rotation() {
+        for &[x, y, z] in ANGLES {
+            let r = product(&[axis_rotation(1, y), axis_rotation(0, x), axis_rotation(2, z)]);
+            let [ez, ex, ey] = euler_zxy(&r);
+            assert_same_rotation(&product(&[axis_rotation(2, ez), axis_rotation(0, ex), axis_rotation(1, ey)]), &r);
+        }
+    }
+
+    #[test]
+    fn quaternions_round_trip() {
+        // Each of the four branches of the matrix to quaternion conversion.
+        for r in [axis_rotation(1, 30.0), axis_rotation(0, 180.0), axis_rotation(1, 180.0), axis_rotation(2, 180.0)] {
+            assert_same_rotation(&rotation(quaternion(&r)), &r);
+        }
+        let a = Transform { position: [0.0, 0.0, 0.0], rotation: Transform::IDENTITY.rotation };
+        let b = Transform { position: [2.0, 4.0, 0.0], rotation: axis_rotation(1, 90.0) };
+        let half = blend(&a, &b, 0.5);
+        assert_close(half.position, [1.0, 2.0, 0.0]);
+        assert_same_rotation(&half.rotation, &axis_rotation(1, 45.0));
+    }
+
+    fn pose(name: &str, x: f64, style: u32, weight: f64) -> String {
+        format!(
+            r#"<Item class="Pose" referent="{name}{x}"><Properties><string name="Name">{name}</string><token name="EasingStyle">{style}</token><float name="Weight">{weight}</float>
+<CoordinateFrame name="CFrame"><X>{x}</X><Y>0</Y><Z>0</Z><R00>1</R00><R01>0</R01><R02>0</R02><R10>0</R10><R11>1</R11><R12>0</R12><R20>0</R20><R21>0</R21><R22>1</R22></CoordinateFrame></Properties></Item>"#
+        )
+    }
+
+    fn keyframe(time: f64, poses: &str) -> String {
+        format!(r#"<Item class="Keyframe" referent="K{time}"><Properties><string name="Name">Keyframe</string><float name="Time">{time}</float></Properties>{poses}</Item>"#)
+    }
+
+    fn animation(keyframes: &str) -> Animation {
+        let dom = Dom::parse(&format!(r#"<roblox version="4"><Item class="KeyframeSequence" referent="S"><Properties><string name="Name">Walk</string></Properties>{keyframes}</Item></roblox>"#));
+        collect(&dom).animations.remove(0)
+    }
+
+    #[test]
+    fn samples_between_keyframes() {
+        let keyframes = [
+            keyframe(2.0, &format!("{}{}", pose("Linear", 4.0, 0, 1.0), pose("Constant", 4.0, 1, 1.0))),
+            keyframe(0.0, &format!("{}{}", pose("Linear", 0.0, 0, 1.0), pose("Constant", 0.0, 1, 1.0))),
+            keyframe(1.0, &pose("Linear", 100.0, 0, 0.0)),
+        ];
+        let animation = animation(&keyframes.concat());
+        assert_eq!(animation.length, 2.0);
+        let x = |joint: &str, time: f64| sample(&animation, joint, time).position[0];
+        // The zero-weight pose at 1s is skipped.
+        assert_eq!([x("Linear", 0.0), x("Linear", 0.5), x("Linear", 1.5), x("Linear", 3.0)], [0.0, 1.0, 3.0, 4.0]);
+        assert_eq!([x("Constant", 1.9), x("Constant", 2.0)], [0.0, 4.0]);
+        assert_eq!(x("Missing", 1.0), 0.0);
+    }
+
+    #[test]
+    fn limits_bvh_length() {
+        let animation = animation(&[keyframe(0.0, &pose("Root", 0.0, 0, 1.0)), keyframe(1e9, &pose("Root", 1.0, 0, 1.0))].concat());
+        let bvh = to_bvh(&animation);
+        let frames = MAX_BVH_LENGTH as usize * BVH_FRAME_RATE as usize + 1;
+        assert!(bvh.contains(&format!("Frames: {}\n", frames)));
+        assert_eq!(bvh.lines().skip_while(|l| !l.starts_with("Frame Time")).count(), frames + 1);
+        assert!(bvh.starts_with("HIERARCHY\nROOT Root\n"));
+    }
+}
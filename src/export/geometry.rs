@@ -0,0 +1,399 @@
+use crate::analysis::assets::asset_ids;
+use crate::dom::{Dom, Instance};
+use crate::properties::PropertyValue;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+const SPHERE_SEGMENTS: usize = 24;
+const SPHERE_RINGS: usize = 12;
+const CYLINDER_SEGMENTS: usize = 24;
+
+/// Parts built from a primitive shape, by class.
+pub const BASIC_PART_CLASSES: &[&str] = &["Part", "WedgePart", "CornerWedgePart", "TrussPart", "SpawnLocation", "Seat", "VehicleSeat", "SkateboardPlatform"];
+/// Parts whose geometry lives in an uploaded asset and is exported as a box of the same size.
+pub const PLACEHOLDER_CLASSES: &[&str] = &["MeshPart", "UnionOperation", "IntersectOperation", "NegateOperation", "PartOperation"];
+/// Properties of placeholder parts that reference the real geometry.
+const ASSET_PROPERTIES: &[&str] = &["MeshId", "TextureID", "AssetId"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shape {
+    Block,
+    Ball,
+    Cylinder,
+    Wedge,
+    CornerWedge,
+}
+
+/// A rigid transform: `world = rotation * local + position`, with `rotation` stored row by row like a CFrame.
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub position: [f64; 3],
+    pub rotation: [[f64; 3]; 3],
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { position: [0.0; 3], rotation: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] };
+
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let r = &self.rotation;
+        std::array::from_fn(|i| r[i][0] * point[0] + r[i][1] * point[1] + r[i][2] * point[2] + self.position[i])
+    }
+
+    pub fn rotate(&self, vector: [f64; 3]) -> [f64; 3] {
+        let r = &self.rotation;
+        std::array::from_fn(|i| r[i][0] * vector[0] + r[i][1] * vector[1] + r[i][2] * vector[2])
+    }
+
+    pub fn then(&self, child: &Transform) -> Transform {
+        let rotation = std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| self.rotation[i][k] * child.rotation[k][j]).sum()));
+        Transform { position: self.apply(child.position), rotation }
+    }
+
+    /// The inverse, assuming `rotation` is orthonormal as it is for every CFrame.
+    pub fn inverse(&self) -> Transform {
+        let rotation: [[f64; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| self.rotation[j][i]));
+        let moved = Transform { position: [0.0; 3], rotation }.rotate(self.position);
+        Transform { position: moved.map(|v| -v), rotation }
+    }
+
+    /// Column-major 4x4 matrix as used by glTF nodes.
+    pub fn to_matrix(self) -> [f64; 16] {
+        let r = &self.rotation;
+        let p = &self.position;
+        [
+            r[0][0], r[1][0], r[2][0], 0.0,
+            r[0][1], r[1][1], r[2][1], 0.0,
+            r[0][2], r[1][2], r[2][2], 0.0,
+            p[0], p[1], p[2], 1.0,
+        ]
+    }
+}
+
+#[derive(Default)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Adds a triangle with per-vertex normals, flipping its winding if needed so it faces away from `center`.
+    fn triangle(&mut self, mut points: [[f32; 3]; 3], mut normals: [[f32; 3]; 3], center: [f32; 3]) {
+        let face = cross(sub(points[1], points[0]), sub(points[2], points[0]));
+        let middle = points.iter().fold([0.0; 3], |acc, p| add(acc, p.map(|v| v / 3.0)));
+        if dot(face, sub(middle, center)) < 0.0 {
+            points.swap(1, 2);
+            normals.swap(1, 2);
+        }
+        let start = self.positions.len() as u32;
+        self.positions.extend(points);
+        self.normals.extend(normals);
+        self.indices.extend([start, start + 1, start + 2]);
+    }
+
+    /// Adds a flat convex polygon facing away from `center`.
+    fn polygon(&mut self, points: &[[f32; 3]], center: [f32; 3]) {
+        let mut normal = normalize(cross(sub(points[1], points[0]), sub(points[2], points[0])));
+        let middle = points.iter().fold([0.0; 3], |acc, p| add(acc, p.map(|v| v / points.len() as f32)));
+        if dot(normal, sub(middle, center)) < 0.0 {
+            normal = normal.map(|v| -v);
+        }
+        for i in 1..points.len() - 1 {
+            self.triangle([points[0], points[i], points[i + 1]], [normal; 3], center);
+        }
+    }
+
+    pub fn bounds(&self) -> ([f32; 3], [f32; 3]) {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &self.positions {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
+            }
+        }
+        (min, max)
+    }
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+    if length > 0.0 { v.map(|c| c / length) } else { v }
+}
+
+/// The mesh of a primitive part of the given size, centered on the part's origin.
+pub fn primitive_mesh(shape: Shape, size: [f32; 3]) -> Mesh {
+    let [x, y, z] = size.map(|s| s / 2.0);
+    let mut mesh = Mesh::default();
+    match shape {
+        Shape::Block => {
+            let corner = |i: usize| [if i & 1 == 0 { -x } else { x }, if i & 2 == 0 { -y } else { y }, if i & 4 == 0 { -z } else { z }];
+            for face in [[0, 1, 3, 2], [4, 5, 7, 6], [0, 1, 5, 4], [2, 3, 7, 6], [0, 2, 6, 4], [1, 3, 7, 5]] {
+                mesh.polygon(&face.map(corner), [0.0; 3]);
+            }
+        }
+        Shape::Wedge => {
+            // The slope rises from the bottom front edge to the top back edge.
+            let points = [[-x, -y, -z], [x, -y, -z], [x, -y, z], [-x, -y, z], [-x, y, z], [x, y, z]];
+            let center = [0.0, -y / 3.0, z / 3.0];
+            for face in [&[0, 1, 2, 3][..], &[3, 2, 5, 4], &[0, 1, 5, 4], &[0, 3, 4], &[1, 2, 5]] {
+                mesh.polygon(&face.iter().map(|&i| points[i]).collect::<Vec<_>>(), center);
+            }
+        }
+        Shape::CornerWedge => {
+            // A pyramid whose apex stands above the right front corner.
+            let points = [[-x, -y, -z], [x, -y, -z], [x, -y, z], [-x, -y, z], [x, y, -z]];
+            let center = [x / 5.0, -3.0 * y / 5.0, -z / 5.0];
+            for face in [&[0, 1, 2, 3][..], &[0, 1, 4], &[1, 2, 4], &[2, 3, 4], &[3, 0, 4]] {
+                mesh.polygon(&face.iter().map(|&i| points[i]).collect::<Vec<_>>(), center);
+            }
+        }
+        Shape::Ball => {
+            let point = |ring: usize, segment: usize| {
+                let theta = PI * ring as f32 / SPHERE_RINGS as f32;
+                let phi = 2.0 * PI * segment as f32 / SPHERE_SEGMENTS as f32;
+                let unit = [theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin()];
+                ([unit[0] * x, unit[1] * y, unit[2] * z], normalize([unit[0] / x, unit[1] / y, unit[2] / z]))
+            };
+            for ring in 0..SPHERE_RINGS {
+                for segment in 0..SPHERE_SEGMENTS {
+                    let corners = [point(ring, segment), point(ring, segment + 1), point(ring + 1, segment + 1), point(ring + 1, segment)];
+                    // The quads touching a pole collapse into a single triangle.
+                    if ring > 0 {
+                        let triangle = [corners[0], corners[1], corners[2]];
+                        mesh.triangle(triangle.map(|t| t.0), triangle.map(|t| t.1), [0.0; 3]);
+                    }
+                    if ring < SPHERE_RINGS - 1 {
+                        let triangle = [corners[0], corners[2], corners[3]];
+                        mesh.triangle(triangle.map(|t| t.0), triangle.map(|t| t.1), [0.0; 3]);
+                    }
+                }
+            }
+        }
+        Shape::Cylinder => {
+            // The axis runs along X; the radius follows the smaller of the other two sides.
+            let radius = y.min(z);
+            let ring = |segment: usize| {
+                let angle = 2.0 * PI * segment as f32 / CYLINDER_SEGMENTS as f32;
+                (angle.cos(), angle.sin())
+            };
+            for segment in 0..CYLINDER_SEGMENTS {
+                let (c0, s0) = ring(segment);
+                let (c1, s1) = ring(segment + 1);
+                let side = [[-x, c0 * radius, s0 * radius], [x, c0 * radius, s0 * radius], [x, c1 * radius, s1 * radius], [-x, c1 * radius, s1 * radius]];
+                let normals = [[0.0, c0, s0], [0.0, c0, s0], [0.0, c1, s1], [0.0, c1, s1]];
+                mesh.triangle([side[0], side[1], side[2]], [normals[0], normals[1], normals[2]], [0.0; 3]);
+                mesh.triangle([side[0], side[2], side[3]], [normals[0], normals[2], normals[3]], [0.0; 3]);
+            }
+            for end in [-x, x] {
+                let cap: Vec<[f32; 3]> = (0..CYLINDER_SEGMENTS).map(ring).map(|(c, s)| [end, c * radius, s * radius]).collect();
+                mesh.polygon(&cap, [0.0; 3]);
+            }
+        }
+    }
+    mesh
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub color: [f32; 3],
+    pub transparency: f32,
+}
+
+pub struct SceneNode {
+    pub name: String,
+    pub class: String,
+    pub path: String,
+    /// Relative to the parent node.
+    pub transform: Transform,
+    /// Index into [`Scene::meshes`] and [`Scene::materials`].
+    pub mesh: Option<(usize, usize)>,
+    /// Asset references of placeholder parts, e.g. `MeshId`.
+    pub assets: Vec<(String, u64)>,
+    pub placeholder: bool,
+    pub children: Vec<SceneNode>,
+}
+
+#[derive(Default)]
+pub struct Scene {
+    pub roots: Vec<SceneNode>,
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub parts: usize,
+    pub placeholders: usize,
+}
+
+pub fn summary(scene: &Scene) -> String {
+    format!(
+        "Geometry: {} parts, {} placeholders, {} meshes, {} materials",
+        scene.parts,
+        scene.placeholders,
+        scene.meshes.len(),
+        scene.materials.len()
+    )
+}
+
+fn vector3(instance: &Instance, names: &[&str]) -> Option<[f64; 3]> {
+    names.iter().find_map(|name| match instance.property(name) {
+        Some(PropertyValue::Vector3 { x, y, z }) => Some([*x, *y, *z]),
+        _ => None,
+    })
+}
+
+pub fn cframe(instance: &Instance) -> Option<Transform> {
+    match instance.property("CFrame").or_else(|| instance.property("CoordinateFrame")) {
+        Some(PropertyValue::CFrame { position, orientation }) => Some(Transform { position: *position, rotation: *orientation }),
+        _ => None,
+    }
+}
+
+pub fn shape(instance: &Instance) -> Shape {
+    match instance.class.as_str() {
+        "WedgePart" => Shape::Wedge,
+        "CornerWedgePart" => Shape::CornerWedge,
+        "Part" => match instance.property("shape").or_else(|| instance.property("Shape")) {
+            // Enum.PartType
+            Some(PropertyValue::Token(0)) => Shape::Ball,
+            Some(PropertyValue::Token(2)) => Shape::Cylinder,
+            Some(PropertyValue::Token(3)) => Shape::Wedge,
+            Some(PropertyValue::Token(4)) => Shape::CornerWedge,
+            _ => Shape::Block,
+        },
+        _ => Shape::Block,
+    }
+}
+
+pub fn part_color(instance: &Instance) -> [f32; 3] {
+    match instance.property("Color3uint8").or_else(|| instance.property("Color")) {
+        Some(PropertyValue::Color3uint8 { r, g, b }) => [*r, *g, *b].map(|c| c as f32 / 255.0),
+        Some(PropertyValue::Color3 { r, g, b }) => [*r, *g, *b].map(|c| c as f32),
+        // Medium stone grey, the default part color.
+        _ => [163.0 / 255.0, 162.0 / 255.0, 165.0 / 255.0],
+    }
+}
+
+pub fn part_transparency(instance: &Instance) -> f32 {
+    match instance.property("Transparency") {
+        Some(PropertyValue::Float(value)) => value.clamp(0.0, 1.0) as f32,
+        _ => 0.0,
+    }
+}
+
+/// The part's size, or the default 4×1×2 brick if it has none.
+pub fn part_size(instance: &Instance) -> [f32; 3] {
+    vector3(instance, &["size", "Size"]).unwrap_or([4.0, 1.0, 2.0]).map(|v| v as f32)
+}
+
+fn material(instance: &Instance) -> Material {
+    let color = part_color(instance);
+    let transparency = part_transparency(instance);
+    let [r, g, b] = color.map(|c| (c * 255.0).round() as u8);
+    let name = if transparency > 0.0 {
+        format!("{:02x}{:02x}{:02x}_{:.0}", r, g, b, transparency * 100.0)
+    } else {
+        format!("{:02x}{:02x}{:02x}", r, g, b)
+    };
+    Material { name, color, transparency }
+}
+
+struct SceneBuilder<'a> {
+    dom: &'a Dom,
+    scene: Scene,
+    mesh_keys: HashMap<(Shape, [u32; 3]), usize>,
+    material_keys: HashMap<String, usize>,
+}
+
+impl SceneBuilder<'_> {
+    fn mesh(&mut self, shape: Shape, size: [f32; 3]) -> usize {
+        let key = (shape, size.map(f32::to_bits));
+        if let Some(&index) = self.mesh_keys.get(&key) {
+            return index;
+        }
+        self.scene.meshes.push(primitive_mesh(shape, size));
+        self.mesh_keys.insert(key, self.scene.meshes.len() - 1);
+        self.scene.meshes.len() - 1
+    }
+
+    fn material(&mut self, material: Material) -> usize {
+        if let Some(&index) = self.material_keys.get(&material.name) {
+            return index;
+        }
+        self.material_keys.insert(material.name.clone(), self.scene.materials.len());
+        self.scene.materials.push(material);
+        self.scene.materials.len() - 1
+    }
+
+    /// The node for `id` and its descendants, or `None` if the subtree holds no parts.
+    /// `parent_world` is the world transform of the nearest ancestor part, if any.
+    fn node(&mut self, id: usize, parent_world: &Transform) -> Option<SceneNode> {
+        let instance = &self.dom.instances[id];
+        let is_basic = BASIC_PART_CLASSES.contains(&instance.class.as_str());
+        let placeholder = PLACEHOLDER_CLASSES.contains(&instance.class.as_str());
+        let world = (is_basic || placeholder).then(|| cframe(instance)).flatten();
+
+        let mut node = SceneNode {
+            name: instance.name.clone(),
+            class: instance.class.clone(),
+            path: self.dom.path(id),
+            transform: match &world {
+                Some(world) => parent_world.inverse().then(world),
+                None => Transform::IDENTITY,
+            },
+            mesh: None,
+            assets: Vec::new(),
+            placeholder: false,
+            children: Vec::new(),
+        };
+
+        if world.is_some() {
+            let mesh = self.mesh(if placeholder { Shape::Block } else { shape(instance) }, part_size(instance));
+            let material = self.material(material(instance));
+            node.mesh = Some((mesh, material));
+            if placeholder {
+                node.placeholder = true;
+                for property in ASSET_PROPERTIES {
+                    if let Some(PropertyValue::Content(Some(url))) = instance.property(property) {
+                        node.assets.extend(asset_ids(url).into_iter().map(|asset| (property.to_string(), asset)));
+                    }
+                }
+                self.scene.placeholders += 1;
+            } else {
+                self.scene.parts += 1;
+            }
+        }
+
+        let child_world = world.unwrap_or(*parent_world);
+        for &child in &instance.children {
+            if let Some(child) = self.node(child, &child_world) {
+                node.children.push(child);
+            }
+        }
+        (node.mesh.is_some() || !node.children.is_empty()).then_some(node)
+    }
+}
+
+/// Builds a scene from every part under `root`, keeping models and folders as empty nodes.
+pub fn build_scene(dom: &Dom, root: usize) -> Scene {
+    let mut builder = SceneBuilder { dom, scene: Scene::default(), mesh_keys: HashMap::new(), material_keys: HashMap::new() };
+    if let Some(node) = builder.node(root, &Transform::IDENTITY) {
+        builder.scene.roots.push(node);
+    }
+    builder.scene
+}
@@ -0,0 +1,274 @@
+use crate::export::geometry::{Scene, SceneNode, Transform};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Roblox colors are sRGB; glTF base colors are linear.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+struct Builder {
+    bin: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    nodes: Vec<Value>,
+    /// glTF meshes pair a geometry with a material, so one is made per combination in use.
+    mesh_keys: HashMap<(usize, usize), usize>,
+    /// Accessor indices of every geometry: positions, normals, indices.
+    geometry: Vec<(usize, usize, usize)>,
+}
+
+impl Builder {
+    fn view(&mut self, bytes: &[u8], target: u32) -> usize {
+        let offset = self.bin.len();
+        self.bin.extend_from_slice(bytes);
+        while !self.bin.len().is_multiple_of(4) {
+            self.bin.push(0);
+        }
+        self.buffer_views.push(json!({ "buffer": 0, "byteOffset": offset, "byteLength": bytes.len(), "target": target }));
+        self.buffer_views.len() - 1
+    }
+
+    fn accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_geometry(&mut self, scene: &Scene) {
+        for mesh in &scene.meshes {
+            let floats = |values: &[[f32; 3]]| values.iter().flatten().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>();
+            let (min, max) = mesh.bounds();
+            let view = self.view(&floats(&mesh.positions), ARRAY_BUFFER);
+            let positions = self.accessor(json!({
+                "bufferView": view, "componentType": FLOAT, "count": mesh.positions.len(), "type": "VEC3", "min": min, "max": max,
+            }));
+            let view = self.view(&floats(&mesh.normals), ARRAY_BUFFER);
+            let normals = self.accessor(json!({ "bufferView": view, "componentType": FLOAT, "count": mesh.normals.len(), "type": "VEC3" }));
+            let bytes: Vec<u8> = mesh.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+            let view = self.view(&bytes, ELEMENT_ARRAY_BUFFER);
+            let indices = self.accessor(json!({ "bufferView": view, "componentType": UNSIGNED_INT, "count": mesh.indices.len(), "type": "SCALAR" }));
+            self.geometry.push((positions, normals, indices));
+        }
+    }
+
+    fn mesh(&mut self, geometry: usize, material: usize) -> usize {
+        if let Some(&index) = self.mesh_keys.get(&(geometry, material)) {
+            return index;
+        }
+        let (positions, normals, indices) = self.geometry[geometry];
+        self.meshes.push(json!({
+            "primitives": [{ "attributes": { "POSITION": positions, "NORMAL": normals }, "indices": indices, "material": material }],
+        }));
+        self.mesh_keys.insert((geometry, material), self.meshes.len() - 1);
+        self.meshes.len() - 1
+    }
+
+    fn node(&mut self, node: &SceneNode) -> usize {
+        let children: Vec<usize> = node.children.iter().map(|child| self.node(child)).collect();
+        let mut value = Map::new();
+        value.insert("name".to_string(), json!(node.name));
+        let matrix = node.transform.to_matrix();
+        if matrix != Transform::IDENTITY.to_matrix() {
+            value.insert("matrix".to_string(), json!(matrix));
+        }
+        if let Some((geometry, material)) = node.mesh {
+            value.insert("mesh".to_string(), json!(self.mesh(geometry, material)));
+        }
+        if !children.is_empty() {
+            value.insert("children".to_string(), json!(children));
+        }
+        let mut extras = json!({ "class": node.class, "path": node.path });
+        if node.placeholder {
+            extras["placeholder"] = json!(true);
+            for (property, asset) in &node.assets {
+                extras[property.as_str()] = json!(asset);
+            }
+        }
+        value.insert("extras".to_string(), extras);
+        self.nodes.push(Value::Object(value));
+        self.nodes.len() - 1
+    }
+}
+
+/// The glTF document and its binary buffer. `buffer_uri` names the `.bin` file; `None` is used for GLB.
+fn build(scene: &Scene, buffer_uri: Option<&str>) -> (Value, Vec<u8>) {
+    let mut builder = Builder {
+        bin: Vec::new(),
+        buffer_views: Vec::new(),
+        accessors: Vec::new(),
+        meshes: Vec::new(),
+        nodes: Vec::new(),
+        mesh_keys: HashMap::new(),
+        geometry: Vec::new(),
+    };
+    builder.add_geometry(scene);
+    let roots: Vec<usize> = scene.roots.iter().map(|root| builder.node(root)).collect();
+
+    let materials: Vec<Value> = scene.materials.iter()
+        .map(|material| {
+            let [r, g, b] = material.color.map(srgb_to_linear);
+            let mut value = json!({
+                "name": material.name,
+                "pbrMetallicRoughness": { "baseColorFactor": [r, g, b, 1.0 - material.transparency], "metallicFactor": 0.0, "roughnessFactor": 1.0 },
+            });
+            if material.transparency > 0.0 {
+                value["alphaMode"] = json!("BLEND");
+            }
+            value
+        })
+        .collect();
+
+    let mut buffer = json!({ "byteLength": builder.bin.len() });
+    if let Some(uri) = buffer_uri {
+        buffer["uri"] = json!(uri);
+    }
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "RBX Ripper" },
+        "scene": 0,
+        "scenes": [{ "nodes": roots }],
+        "nodes": builder.nodes,
+        "meshes": builder.meshes,
+        "materials": materials,
+        "accessors": builder.accessors,
+        "bufferViews": builder.buffer_views,
+    });
+    if !builder.bin.is_empty() {
+        document["buffers"] = json!([buffer]);
+    }
+    (document, builder.bin)
+}
+
+/// A `.gltf` document and the `.bin` buffer it refers to as `buffer_uri`.
+pub fn to_gltf(scene: &Scene, buffer_uri: &str) -> anyhow::Result<(Vec<u8>, Vec<u8>)> {
+    let (document, bin) = build(scene, Some(buffer_uri));
+    Ok((serde_json::to_vec_pretty(&document)?, bin))
+}
+
+/// A binary `.glb` file with the buffer embedded.
+pub fn to_glb(scene: &Scene) -> anyhow::Result<Vec<u8>> {
+    let (document, bin) = build(scene, None);
+    let mut json = serde_json::to_vec(&document)?;
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+
+    let mut length = 12 + 8 + json.len();
+    if !bin.is_empty() {
+        length += 8 + bin.len();
+    }
+    let mut out = Vec::with_capacity(length);
+    for word in [GLB_MAGIC, GLB_VERSION, length as u32, json.len() as u32, CHUNK_JSON] {
+        out.extend_from_slice(&word.to_le_bytes());
+    }
+    out.extend_from_slice(&json);
+    if !bin.is_empty() {
+        out.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        out.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        out.extend_from_slice(&bin);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom::Dom;
+    use crate::export::geometry::{build_scene, primitive_mesh, Mesh, Shape};
+
+    fn word(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|i| a[i] - b[i])
+    }
+
+    fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+        (0..3).map(|i| a[i] * b[i]).sum()
+    }
+
+    fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+        [a[1] * b[2] - a[2] * b[1], a[2] * b[0] - a[0] * b[2], a[0] * b[1] - a[1] * b[0]]
+    }
+
+    /// Checks that every triangle winds counter-clockwise seen from outside and that its vertex normals agree.
+    fn assert_outward(mesh: &Mesh) {
+        let count = mesh.positions.len() as f32;
+        let inside = mesh.positions.iter().fold([0.0; 3], |acc, p| std::array::from_fn(|i| acc[i] + p[i] / count));
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.positions[triangle[i] as usize]);
+            let face = cross(sub(b, a), sub(c, a));
+            if dot(face, face) < 1e-10 {
+                continue;
+            }
+            let middle: [f32; 3] = std::array::from_fn(|i| (a[i] + b[i] + c[i]) / 3.0);
+            assert!(dot(face, sub(middle, inside)) > 0.0, "triangle {:?} faces inwards", [a, b, c]);
+            for &index in triangle {
+                assert!(dot(face, mesh.normals[index as usize]) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn writes_a_valid_glb() {
+        let dom = Dom::parse(r#"<roblox version="4"><Item class="Workspace" referent="W"><Properties><string name="Name">Workspace</string></Properties>
+<Item class="Part" referent="P"><Properties><string name="Name">Block</string>
+<CoordinateFrame name="CFrame"><X>10</X><Y>0</Y><Z>0</Z><R00>1</R00><R01>0</R01><R02>0</R02><R10>0</R10><R11>1</R11><R12>0</R12><R20>0</R20><R21>0</R21><R22>1</R22></CoordinateFrame>
+<Vector3 name="size"><X>4</X><Y>2</Y><Z>6</Z></Vector3></Properties></Item>
+</Item></roblox>"#);
+        let glb = to_glb(&build_scene(&dom, dom.find_service("Workspace").unwrap())).unwrap();
+
+        assert_eq!([word(&glb, 0), word(&glb, 4), word(&glb, 8)], [GLB_MAGIC, GLB_VERSION, glb.len() as u32]);
+        let json_length = word(&glb, 12) as usize;
+        assert_eq!(word(&glb, 16), CHUNK_JSON);
+        assert_eq!(json_length % 4, 0);
+        let document: Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        let bin_start = 20 + json_length;
+        let bin_length = word(&glb, bin_start) as usize;
+        assert_eq!(word(&glb, bin_start + 4), CHUNK_BIN);
+        assert_eq!(bin_start + 8 + bin_length, glb.len());
+        assert_eq!(document["buffers"][0]["byteLength"], bin_length);
+        let bin = &glb[bin_start + 8..];
+
+        let block = document["nodes"].as_array().unwrap().iter().find(|n| n["name"] == "Block").unwrap();
+        assert_eq!(block["matrix"][12], 10.0);
+        let primitive = &document["meshes"][block["mesh"].as_u64().unwrap() as usize]["primitives"][0];
+        let accessor = |name: &str| &document["accessors"][primitive["attributes"].get(name).unwrap_or(&primitive[name]).as_u64().unwrap() as usize];
+        let read = |accessor: &Value| {
+            let view = &document["bufferViews"][accessor["bufferView"].as_u64().unwrap() as usize];
+            let offset = view["byteOffset"].as_u64().unwrap() as usize;
+            let length = view["byteLength"].as_u64().unwrap() as usize;
+            assert_eq!(offset % 4, 0);
+            bin[offset..offset + length].chunks_exact(4).map(|b| b.try_into().unwrap()).collect::<Vec<[u8; 4]>>()
+        };
+        assert_eq!((accessor("POSITION")["min"].clone(), accessor("POSITION")["max"].clone()), (json!([-2.0, -1.0, -3.0]), json!([2.0, 1.0, 3.0])));
+        assert_eq!(accessor("indices")["count"], 36);
+
+        let floats = |name: &str| read(accessor(name)).into_iter().map(f32::from_le_bytes).collect::<Vec<f32>>();
+        let mesh = Mesh {
+            positions: floats("POSITION").chunks_exact(3).map(|p| [p[0], p[1], p[2]]).collect(),
+            normals: floats("NORMAL").chunks_exact(3).map(|n| [n[0], n[1], n[2]]).collect(),
+            indices: read(accessor("indices")).into_iter().map(u32::from_le_bytes).collect(),
+        };
+        assert_eq!(mesh.indices.len() / 3, 12);
+        assert_outward(&mesh);
+    }
+
+    #[test]
+    fn primitives_face_outwards() {
+        for shape in [Shape::Block, Shape::Wedge, Shape::CornerWedge, Shape::Ball, Shape::Cylinder] {
+            assert_outward(&primitive_mesh(shape, [4.0, 2.0, 6.0]));
+        }
+    }
+}
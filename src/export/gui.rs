@@ -0,0 +1,457 @@
+use crate::analysis::assets::asset_ids;
+use crate::dom::{Dom, Instance};
+use crate::export::file_stems;
+use crate::properties::{PropertyValue, UDim};
+use std::fmt::Write as _;
+
+pub const DEFAULT_RESOLUTION: (u32, u32) = (1920, 1080);
+/// `SurfaceGui.CanvasSize` when the property is missing.
+const DEFAULT_CANVAS_SIZE: (f64, f64) = (800.0, 600.0);
+
+const ROOT_CLASSES: &[&str] = &["ScreenGui", "SurfaceGui"];
+const TEXT_CLASSES: &[&str] = &["TextLabel", "TextButton", "TextBox"];
+const IMAGE_CLASSES: &[&str] = &["ImageLabel", "ImageButton"];
+/// Everything drawn as a box; the rest of a GUI tree is scripts, constraints and layout objects.
+const GUI_OBJECT_CLASSES: &[&str] = &[
+    "Frame", "ScrollingFrame", "CanvasGroup", "ViewportFrame", "VideoFrame",
+    "TextLabel", "TextButton", "TextBox", "ImageLabel", "ImageButton",
+];
+
+/// Parses `1920x1080`.
+pub fn parse_resolution(text: &str) -> Result<(u32, u32), String> {
+    let (width, height) = text.split_once(['x', 'X']).ok_or_else(|| format!("expected WIDTHxHEIGHT, got {}", text))?;
+    let parse = |v: &str| v.trim().parse::<u32>().ok().filter(|&v| v > 0).ok_or_else(|| format!("invalid size {}", v));
+    Ok((parse(width)?, parse(height)?))
+}
+
+#[derive(Clone, Copy)]
+struct Rect {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+}
+
+fn udim2(instance: &Instance, name: &str) -> (UDim, UDim) {
+    match instance.property(name) {
+        Some(PropertyValue::UDim2 { x, y }) => (*x, *y),
+        _ => (UDim { scale: 0.0, offset: 0.0 }, UDim { scale: 0.0, offset: 0.0 }),
+    }
+}
+
+fn udim(instance: &Instance, name: &str) -> UDim {
+    match instance.property(name) {
+        Some(PropertyValue::UDim(value)) => *value,
+        _ => UDim { scale: 0.0, offset: 0.0 },
+    }
+}
+
+fn vector2(instance: &Instance, name: &str) -> Option<(f64, f64)> {
+    match instance.property(name) {
+        Some(PropertyValue::Vector2 { x, y }) => Some((*x, *y)),
+        _ => None,
+    }
+}
+
+fn float(instance: &Instance, name: &str, default: f64) -> f64 {
+    match instance.property(name) {
+        Some(PropertyValue::Float(value)) => *value,
+        Some(PropertyValue::Int(value)) => *value as f64,
+        _ => default,
+    }
+}
+
+fn int(instance: &Instance, name: &str, default: i64) -> i64 {
+    match instance.property(name) {
+        Some(PropertyValue::Int(value)) => *value,
+        _ => default,
+    }
+}
+
+fn token(instance: &Instance, name: &str, default: u32) -> u32 {
+    match instance.property(name) {
+        Some(PropertyValue::Token(value)) => *value,
+        _ => default,
+    }
+}
+
+fn flag(instance: &Instance, name: &str, default: bool) -> bool {
+    match instance.property(name) {
+        Some(PropertyValue::Bool(value)) => *value,
+        _ => default,
+    }
+}
+
+fn string<'a>(instance: &'a Instance, name: &str) -> &'a str {
+    match instance.property(name) {
+        Some(PropertyValue::String(value)) => value,
+        _ => "",
+    }
+}
+
+/// A CSS `rgba()` from a Color3 property and a transparency.
+fn color(instance: &Instance, name: &str, default: [f64; 3], transparency: f64) -> String {
+    let [r, g, b] = match instance.property(name) {
+        Some(PropertyValue::Color3 { r, g, b }) => [*r, *g, *b],
+        Some(PropertyValue::Color3uint8 { r, g, b }) => [*r, *g, *b].map(|c| c as f64 / 255.0),
+        _ => default,
+    }
+    .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("rgba({},{},{},{:.3})", r, g, b, (1.0 - transparency).clamp(0.0, 1.0))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The font family named by a `FontFace` or legacy `Font` property, e.g. `Gotham`.
+fn font_family(instance: &Instance) -> String {
+    if let Some(PropertyValue::Font { family: Some(family), .. }) = instance.property("FontFace") {
+        let stem = family.rsplit('/').next().unwrap_or(family);
+        return stem.trim_end_matches(".json").to_string();
+    }
+    // Enum.Font, for places saved before FontFace.
+    let legacy = [
+        "Legacy", "Arial", "Arial", "SourceSans", "SourceSans", "SourceSans", "SourceSans", "Bodoni", "Garamond", "Cartoon", "Code",
+        "Highway", "SciFi", "Arcade", "Fantasy", "Antique", "SourceSans", "Gotham", "Gotham", "Gotham", "Gotham",
+    ];
+    legacy.get(token(instance, "Font", 3) as usize).copied().unwrap_or("SourceSans").to_string()
+}
+
+struct Renderer<'a> {
+    dom: &'a Dom,
+    out: String,
+    elements: usize,
+    images: usize,
+}
+
+impl Renderer<'_> {
+    /// GuiObjects that draw inside `id`, looking through folders the way Roblox does.
+    fn gui_children(&self, id: usize, out: &mut Vec<usize>) {
+        for &child in &self.dom.instances[id].children {
+            let class = self.dom.instances[child].class.as_str();
+            if GUI_OBJECT_CLASSES.contains(&class) {
+                out.push(child);
+            } else if class == "Folder" {
+                self.gui_children(child, out);
+            }
+        }
+    }
+
+    fn modifier(&self, id: usize, class: &str) -> Option<&Instance> {
+        self.dom.instances[id].children.iter().map(|&c| &self.dom.instances[c]).find(|c| c.class == class)
+    }
+
+    /// The area children are laid out in, after `UIPadding`, relative to the element's own corner.
+    fn content_rect(&self, id: usize, width: f64, height: f64) -> Rect {
+        match self.modifier(id, "UIPadding") {
+            Some(padding) => {
+                let side = |name: &str, length: f64| {
+                    let value = udim(padding, name);
+                    value.scale * length + value.offset
+                };
+                let left = side("PaddingLeft", width);
+                let top = side("PaddingTop", height);
+                Rect {
+                    x: left,
+                    y: top,
+                    width: (width - left - side("PaddingRight", width)).max(0.0),
+                    height: (height - top - side("PaddingBottom", height)).max(0.0),
+                }
+            }
+            None => Rect { x: 0.0, y: 0.0, width, height },
+        }
+    }
+
+    fn size(&self, id: usize, parent: &Rect) -> (f64, f64) {
+        let (x, y) = udim2(&self.dom.instances[id], "Size");
+        ((x.scale * parent.width + x.offset).max(0.0), (y.scale * parent.height + y.offset).max(0.0))
+    }
+
+    /// Rects of `children` inside `content`: stacked by a `UIListLayout` if `id` has one, otherwise from
+    /// each child's Position and AnchorPoint.
+    fn layout(&self, id: usize, children: &mut [usize], content: &Rect) -> Vec<Rect> {
+        let Some(list) = self.modifier(id, "UIListLayout") else {
+            return children.iter()
+                .map(|&child| {
+                    let instance = &self.dom.instances[child];
+                    let (width, height) = self.size(child, content);
+                    let (px, py) = udim2(instance, "Position");
+                    let (ax, ay) = vector2(instance, "AnchorPoint").unwrap_or((0.0, 0.0));
+                    Rect {
+                        x: content.x + px.scale * content.width + px.offset - ax * width,
+                        y: content.y + py.scale * content.height + py.offset - ay * height,
+                        width,
+                        height,
+                    }
+                })
+                .collect();
+        };
+
+        // Enum.SortOrder: Name = 0, Custom = 1, LayoutOrder = 2.
+        match token(list, "SortOrder", 2) {
+            0 => children.sort_by(|&a, &b| self.dom.instances[a].name.cmp(&self.dom.instances[b].name)),
+            2 => children.sort_by_key(|&c| int(&self.dom.instances[c], "LayoutOrder", 0)),
+            _ => {}
+        }
+        // Enum.FillDirection: Horizontal = 0, Vertical = 1.
+        let vertical = token(list, "FillDirection", 1) == 1;
+        let padding = udim(list, "Padding");
+        let gap = padding.scale * if vertical { content.height } else { content.width } + padding.offset;
+        let sizes: Vec<(f64, f64)> = children.iter().map(|&c| self.size(c, content)).collect();
+        let total = sizes.iter().map(|&(w, h)| if vertical { h } else { w }).sum::<f64>() + gap * sizes.len().saturating_sub(1) as f64;
+
+        // Enum.HorizontalAlignment: Center = 0, Left = 1, Right = 2; Enum.VerticalAlignment: Center = 0, Top = 1, Bottom = 2.
+        let align = |value: u32, free: f64| match value {
+            0 => free / 2.0,
+            2 => free,
+            _ => 0.0,
+        };
+        let horizontal = token(list, "HorizontalAlignment", 1);
+        let vertical_alignment = token(list, "VerticalAlignment", 1);
+        let mut cursor = if vertical {
+            align(vertical_alignment, content.height - total)
+        } else {
+            align(horizontal, content.width - total)
+        };
+        sizes.iter()
+            .map(|&(width, height)| {
+                let rect = if vertical {
+                    Rect { x: content.x + align(horizontal, content.width - width), y: content.y + cursor, width, height }
+                } else {
+                    Rect { x: content.x + cursor, y: content.y + align(vertical_alignment, content.height - height), width, height }
+                };
+                cursor += if vertical { height } else { width } + gap;
+                rect
+            })
+            .collect()
+    }
+
+    fn children(&mut self, id: usize, width: f64, height: f64, depth: usize) {
+        let mut children = Vec::new();
+        self.gui_children(id, &mut children);
+        children.retain(|&c| flag(&self.dom.instances[c], "Visible", true));
+        let content = self.content_rect(id, width, height);
+        let rects = self.layout(id, &mut children, &content);
+        for (child, rect) in children.into_iter().zip(rects) {
+            self.element(child, rect, depth);
+        }
+    }
+
+    fn element(&mut self, id: usize, rect: Rect, depth: usize) {
+        let dom = self.dom;
+        let instance = &dom.instances[id];
+        let class = instance.class.as_str();
+        self.elements += 1;
+
+        let mut style = format!(
+            "left:{:.1}px;top:{:.1}px;width:{:.1}px;height:{:.1}px;z-index:{};",
+            rect.x, rect.y, rect.width, rect.height, int(instance, "ZIndex", 1)
+        );
+        let background = float(instance, "BackgroundTransparency", 0.0);
+        if background < 1.0 {
+            let _ = write!(style, "background:{};", color(instance, "BackgroundColor3", [0.64, 0.64, 0.64], background));
+        }
+        let border = float(instance, "BorderSizePixel", 1.0);
+        if border > 0.0 && background < 1.0 {
+            let _ = write!(style, "outline:{}px solid {};", border, color(instance, "BorderColor3", [0.11, 0.16, 0.2], background));
+        }
+        if let Some(corner) = self.modifier(id, "UICorner") {
+            let radius = udim(corner, "CornerRadius");
+            let radius = radius.scale * rect.width.min(rect.height) + radius.offset;
+            let _ = write!(style, "border-radius:{:.1}px;", radius);
+        }
+        let rotation = float(instance, "Rotation", 0.0);
+        if rotation != 0.0 {
+            let _ = write!(style, "transform:rotate({}deg);", rotation);
+        }
+        if flag(instance, "ClipsDescendants", false) || class == "ScrollingFrame" {
+            style.push_str("overflow:hidden;");
+        }
+
+        let indent = "  ".repeat(depth + 1);
+        let _ = writeln!(
+            self.out,
+            "{}<div class=\"gui\" title=\"{} ({})\" style=\"{}\">",
+            indent,
+            escape(&dom.path(id)),
+            class,
+            style
+        );
+
+        if IMAGE_CLASSES.contains(&class) {
+            let image = match instance.property("Image") {
+                Some(PropertyValue::Content(Some(url))) => url.as_str(),
+                _ => "",
+            };
+            if !image.is_empty() {
+                self.images += 1;
+                let label = match asset_ids(image).first() {
+                    Some(asset) => format!("rbxassetid://{}", asset),
+                    None => image.to_string(),
+                };
+                let _ = writeln!(self.out, "{}  <div class=\"image\" title=\"{}\">{}</div>", indent, escape(image), escape(&label));
+            }
+        }
+
+        if TEXT_CLASSES.contains(&class) {
+            let text = string(instance, "Text");
+            // Enum.TextXAlignment: Left = 0, Right = 1, Center = 2; Enum.TextYAlignment: Top = 0, Center = 1, Bottom = 2.
+            let justify = ["flex-start", "flex-end", "center"].get(token(instance, "TextXAlignment", 2) as usize).copied().unwrap_or("center");
+            let align = ["flex-start", "center", "flex-end"].get(token(instance, "TextYAlignment", 1) as usize).copied().unwrap_or("center");
+            let text_align = ["left", "right", "center"].get(token(instance, "TextXAlignment", 2) as usize).copied().unwrap_or("center");
+            let size = if flag(instance, "TextScaled", false) {
+                // Roughly the largest size that fits the text on one line.
+                let characters = text.chars().count().max(1) as f64;
+                (rect.height * 0.8).min(rect.width / characters * 1.8).max(1.0)
+            } else {
+                float(instance, "TextSize", 14.0)
+            };
+            let wrap = if flag(instance, "TextWrapped", false) { "pre-wrap" } else { "pre" };
+            let _ = writeln!(
+                self.out,
+                "{}  <div class=\"text\" style=\"justify-content:{};align-items:{};text-align:{};font-size:{:.1}px;font-family:'{}',sans-serif;color:{};white-space:{};\">{}</div>",
+                indent,
+                justify,
+                align,
+                text_align,
+                size,
+                escape(&font_family(instance)),
+                color(instance, "TextColor3", [0.0, 0.0, 0.0], float(instance, "TextTransparency", 0.0)),
+                wrap,
+                escape(text)
+            );
+        }
+
+        self.children(id, rect.width, rect.height, depth + 1);
+        let _ = writeln!(self.out, "{}</div>", indent);
+    }
+}
+
+const STYLE: &str = "body{margin:0;padding:16px;background:#202124;color:#ddd;font-family:sans-serif}\
+h1{font-size:14px;font-weight:normal}\
+.screen{position:relative;overflow:hidden;background:repeating-conic-gradient(#3a3a3a 0 25%,#333 0 50%) 0 0/32px 32px}\
+.gui{position:absolute;box-sizing:border-box}\
+.text{position:absolute;inset:0;display:flex;overflow:hidden;line-height:1}\
+.image{position:absolute;inset:0;display:flex;align-items:center;justify-content:center;border:1px dashed #e0a030;\
+background:rgba(224,160,48,.15);color:#e0a030;font:11px monospace;overflow:hidden;word-break:break-all}";
+
+/// A ScreenGui or SurfaceGui rendered as a standalone page.
+pub struct GuiPage {
+    pub path: String,
+    pub html: String,
+    pub elements: usize,
+    pub images: usize,
+}
+
+/// Renders the GUI `id` as absolutely positioned boxes on a `resolution` screen; a SurfaceGui uses its CanvasSize.
+pub fn render(dom: &Dom, id: usize, resolution: (u32, u32)) -> GuiPage {
+    let instance = &dom.instances[id];
+    let (width, height) = if instance.class == "SurfaceGui" {
+        vector2(instance, "CanvasSize").unwrap_or(DEFAULT_CANVAS_SIZE)
+    } else {
+        (resolution.0 as f64, resolution.1 as f64)
+    };
+    let path = dom.path(id);
+    let mut renderer = Renderer { dom, out: String::new(), elements: 0, images: 0 };
+    renderer.children(id, width, height, 0);
+
+    let mut html = String::new();
+    let _ = writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>", escape(&path), STYLE);
+    let _ = writeln!(html, "<h1>{} ({}, {}×{})</h1>", escape(&path), instance.class, width, height);
+    let _ = writeln!(html, "<div class=\"screen\" style=\"width:{}px;height:{}px\">", width, height);
+    html.push_str(&renderer.out);
+    html.push_str("</div>\n</body>\n</html>\n");
+    GuiPage { path, html, elements: renderer.elements, images: renderer.images }
+}
+
+/// Every ScreenGui and SurfaceGui in the place, in document order.
+pub fn render_all(dom: &Dom, resolution: (u32, u32)) -> Vec<GuiPage> {
+    dom.iter_depth_first().into_iter()
+        .filter(|&id| ROOT_CLASSES.contains(&dom.instances[id].class.as_str()))
+        .map(|id| render(dom, id, resolution))
+        .collect()
+}
+
+pub fn summary(pages: &[GuiPage]) -> String {
+    let elements: usize = pages.iter().map(|p| p.elements).sum();
+    let images: usize = pages.iter().map(|p| p.images).sum();
+    format!("GUI: {} pages, {} elements, {} image placeholders", pages.len(), elements, images)
+}
+
+/// `<path>.html` for every page, with names made unique.
+pub fn gui_files(pages: &[GuiPage]) -> Vec<(String, Vec<u8>)> {
+    let stems = file_stems(pages.iter().map(|page| page.path.as_str()));
+    pages.iter().zip(stems).map(|(page, stem)| (format!("{}.html", stem), page.html.clone().into_bytes())).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(name: &str, size: [f64; 4], extra: &str, children: &str) -> String {
+        format!(
+            r#"<Item class="Frame"><Properties><string name="Name">{}</string><UDim2 name="Size"><XS>{}</XS><XO>{}</XO><YS>{}</YS><YO>{}</YO></UDim2>{}</Properties>{}</Item>"#,
+            name, size[0], size[1], size[2], size[3], extra, children
+        )
+    }
+
+    /// `(path, [left, top, width, height])` of every element, read back from the page's inline styles.
+    fn boxes(html: &str) -> Vec<(String, [f64; 4])> {
+        html.lines()
+            .filter(|line| line.contains("class=\"gui\""))
+            .map(|line| {
+                let title = line.split("title=\"").nth(1).unwrap();
+                let path = title[..title.find(" (").unwrap()].to_string();
+                let value = |key: &str| {
+                    let rest = line.split(&format!("{}:", key)).nth(1).unwrap();
+                    rest[..rest.find("px").unwrap()].parse().unwrap()
+                };
+                (path, ["left", "top", "width", "height"].map(value))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lays_out_lists_and_padding() {
+        let panel_children = [
+            r#"<Item class="UIPadding"><Properties><UDim name="PaddingLeft"><S>0</S><O>10</O></UDim><UDim name="PaddingRight"><S>0</S><O>10</O></UDim><UDim name="PaddingTop"><S>0.1</S><O>0</O></UDim></Properties></Item>"#.to_string(),
+            r#"<Item class="UIListLayout"><Properties><token name="HorizontalAlignment">0</token><UDim name="Padding"><S>0</S><O>5</O></UDim></Properties></Item>"#.to_string(),
+            frame("B", [1.0, 0.0, 0.0, 40.0], r#"<int name="LayoutOrder">2</int>"#, ""),
+            frame("A", [0.0, 100.0, 0.0, 30.0], r#"<int name="LayoutOrder">1</int>"#, ""),
+            frame("Hidden", [0.0, 10.0, 0.0, 10.0], r#"<bool name="Visible">false</bool>"#, ""),
+            format!(r#"<Item class="Folder"><Properties><string name="Name">Group</string></Properties>{}</Item>"#, frame("C", [0.0, 60.0, 0.0, 20.0], r#"<int name="LayoutOrder">3</int>"#, "")),
+        ];
+        let bar_children = [
+            r#"<Item class="UIListLayout"><Properties><token name="FillDirection">0</token><token name="SortOrder">0</token><token name="HorizontalAlignment">2</token><token name="VerticalAlignment">0</token><UDim name="Padding"><S>0.01</S><O>0</O></UDim></Properties></Item>"#.to_string(),
+            frame("Z", [0.0, 100.0, 0.0, 20.0], "", ""),
+            frame("Y", [0.0, 50.0, 0.0, 50.0], "", ""),
+        ];
+        let panel = frame(
+            "Panel",
+            [0.5, 0.0, 0.0, 200.0],
+            r#"<UDim2 name="Position"><XS>0.5</XS><XO>0</XO><YS>0.5</YS><YO>0</YO></UDim2><Vector2 name="AnchorPoint"><X>0.5</X><Y>0.5</Y></Vector2>"#,
+            &panel_children.concat(),
+        );
+        let bar = frame("Bar", [1.0, 0.0, 0.0, 50.0], "", &bar_children.concat());
+        let dom = Dom::parse(&format!(
+            r#"<roblox version="4"><Item class="StarterGui"><Properties><string name="Name">StarterGui</string></Properties><Item class="ScreenGui"><Properties><string name="Name">Hud</string></Properties>{}{}</Item></Item></roblox>"#,
+            panel, bar
+        ));
+
+        let pages = render_all(&dom, (400, 300));
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].elements, 7);
+        let expected = [
+            ("Panel", [100.0, 50.0, 200.0, 200.0]),
+            ("Panel.A", [50.0, 20.0, 100.0, 30.0]),
+            ("Panel.B", [10.0, 55.0, 180.0, 40.0]),
+            ("Panel.Group.C", [70.0, 100.0, 60.0, 20.0]),
+            ("Bar", [0.0, 0.0, 400.0, 50.0]),
+            ("Bar.Y", [246.0, 0.0, 50.0, 50.0]),
+            ("Bar.Z", [300.0, 15.0, 100.0, 20.0]),
+        ]
+        .map(|(path, rect)| (format!("StarterGui.Hud.{}", path), rect));
+        assert_eq!(boxes(&pages[0].html), expected);
+    }
+}
@@ -0,0 +1,282 @@
+use crate::export::property_file_name;
+use crate::properties::{decode_base64, Property, PropertyValue};
+use image::{DynamicImage, ImageFormat};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use std::sync::Mutex;
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+const JPEG_MAGIC: &[u8] = b"\xff\xd8\xff";
+const DDS_MAGIC: &[u8] = b"DDS ";
+/// Shared by KTX 1.1 (`«KTX 11»`) and KTX 2 (`«KTX 20»`).
+const KTX_MAGIC: &[u8] = b"\xabKTX ";
+/// TGA has no magic at the start; version 2 files end with this footer.
+const TGA_FOOTER: &[u8] = b"TRUEVISION-XFILE.\0";
+const TGA_HEADER_LENGTH: usize = 18;
+/// Longest side of the `.thumb.png` written next to larger images.
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageKind {
+    Png,
+    Jpeg,
+    Tga,
+    Dds,
+    Ktx,
+}
+
+impl ImageKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImageKind::Png => "PNG",
+            ImageKind::Jpeg => "JPEG",
+            ImageKind::Tga => "TGA",
+            ImageKind::Dds => "DDS",
+            ImageKind::Ktx => "KTX",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageKind::Png => "png",
+            ImageKind::Jpeg => "jpg",
+            ImageKind::Tga => "tga",
+            ImageKind::Dds => "dds",
+            ImageKind::Ktx => "ktx",
+        }
+    }
+
+    /// The `image` decoder for the kind; KTX textures have none.
+    fn format(self) -> Option<ImageFormat> {
+        match self {
+            ImageKind::Png => Some(ImageFormat::Png),
+            ImageKind::Jpeg => Some(ImageFormat::Jpeg),
+            ImageKind::Tga => Some(ImageFormat::Tga),
+            ImageKind::Dds => Some(ImageFormat::Dds),
+            ImageKind::Ktx => None,
+        }
+    }
+
+    /// Whether browsers and image viewers open the original file, so no PNG copy is needed.
+    fn viewable(self) -> bool {
+        matches!(self, ImageKind::Png | ImageKind::Jpeg)
+    }
+}
+
+/// A TGA header with a known image type, size and pixel depth. Only a hint: the caller still has to decode it.
+fn looks_like_tga(data: &[u8]) -> bool {
+    if data.len() <= TGA_HEADER_LENGTH {
+        return false;
+    }
+    let width = u16::from_le_bytes([data[12], data[13]]);
+    let height = u16::from_le_bytes([data[14], data[15]]);
+    data[1] <= 1
+        && matches!(data[2], 1 | 2 | 3 | 9 | 10 | 11)
+        && width > 0
+        && height > 0
+        && matches!(data[16], 8 | 15 | 16 | 24 | 32)
+}
+
+/// The kind of image in `data`, judged by its magic bytes.
+pub fn detect(data: &[u8]) -> Option<ImageKind> {
+    if data.starts_with(PNG_MAGIC) {
+        Some(ImageKind::Png)
+    } else if data.starts_with(JPEG_MAGIC) {
+        Some(ImageKind::Jpeg)
+    } else if data.starts_with(DDS_MAGIC) {
+        Some(ImageKind::Dds)
+    } else if data.starts_with(KTX_MAGIC) {
+        Some(ImageKind::Ktx)
+    } else if data.ends_with(TGA_FOOTER) || looks_like_tga(data) {
+        Some(ImageKind::Tga)
+    } else {
+        None
+    }
+}
+
+fn decode(kind: ImageKind, data: &[u8]) -> Option<DynamicImage> {
+    image::load_from_memory_with_format(data, kind.format()?).ok()
+}
+
+fn png(image: &DynamicImage) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png).ok()?;
+    Some(png)
+}
+
+#[derive(Default)]
+struct Counts {
+    found: BTreeMap<&'static str, usize>,
+    converted: usize,
+    undecodable: usize,
+}
+
+/// Finds images embedded in binary properties and writes them next to their `properties.json`.
+/// Shared between the extraction threads, so it also keeps count of what it found.
+pub struct ImageExport<'a> {
+    shared_strings: &'a HashMap<String, String>,
+    counts: Mutex<Counts>,
+}
+
+impl<'a> ImageExport<'a> {
+    pub fn new(shared_strings: &'a HashMap<String, String>) -> Self {
+        Self { shared_strings, counts: Mutex::new(Counts::default()) }
+    }
+
+    fn payload(&self, value: &PropertyValue) -> Option<Vec<u8>> {
+        let base64 = match value {
+            PropertyValue::BinaryString(data) => data.as_str(),
+            PropertyValue::SharedString(key) => self.shared_strings.get(key)?,
+            // `data:image/png;base64,...` URLs
+            PropertyValue::Content(Some(url)) => url.strip_prefix("data:")?.split_once(";base64,")?.1,
+            _ => return None,
+        };
+        decode_base64(base64).filter(|data| !data.is_empty())
+    }
+
+    /// `<Property>.<ext>` with the original bytes of every embedded image, plus `<Property>.png` for formats
+    /// viewers don't open and `<Property>.thumb.png` for images larger than [`THUMBNAIL_SIZE`].
+    pub fn files(&self, properties: &[Property]) -> Vec<(String, Vec<u8>)> {
+        let mut files = Vec::new();
+        for property in properties {
+            let Some(data) = self.payload(&property.value) else { continue };
+            let Some(kind) = detect(&data) else { continue };
+            let decoded = decode(kind, &data);
+            // A TGA guessed from its header alone is only kept when it decodes.
+            if kind == ImageKind::Tga && decoded.is_none() && !data.ends_with(TGA_FOOTER) {
+                continue;
+            }
+
+            let mut counts = self.counts.lock().unwrap();
+            *counts.found.entry(kind.name()).or_default() += 1;
+            files.push((property_file_name(&property.name, kind.extension()), data));
+            let Some(image) = decoded else {
+                counts.undecodable += 1;
+                continue;
+            };
+            if !kind.viewable() {
+                if let Some(png) = png(&image) {
+                    files.push((property_file_name(&property.name, "png"), png));
+                    counts.converted += 1;
+                }
+            }
+            if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+                if let Some(png) = png(&image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)) {
+                    files.push((property_file_name(&property.name, "thumb.png"), png));
+                }
+            }
+        }
+        files
+    }
+
+    pub fn summary(&self) -> String {
+        let counts = self.counts.lock().unwrap();
+        let total: usize = counts.found.values().sum();
+        let parts: Vec<String> = counts.found.iter().map(|(kind, count)| format!("{} × {}", kind, count)).collect();
+        let found = if parts.is_empty() { format!("{} found", total) } else { format!("{} found ({})", total, parts.join(", ")) };
+        format!("Embedded images: {}, {} converted to PNG, {} not decodable", found, counts.converted, counts.undecodable)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn base64(data: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for group in data.chunks(3) {
+            let bytes = [group[0], *group.get(1).unwrap_or(&0), *group.get(2).unwrap_or(&0)];
+            let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+            for i in 0..4 {
+                out.push(if i <= group.len() { ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char } else { '=' });
+            }
+        }
+        out
+    }
+
+    fn encoded(image: &DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        image.write_to(&mut Cursor::new(&mut data), format).unwrap();
+        data
+    }
+
+    fn binary(name: &str, data: &[u8]) -> Property {
+        Property { name: name.to_string(), value: PropertyValue::BinaryString(base64(data)) }
+    }
+
+    fn names(files: &[(String, Vec<u8>)]) -> Vec<&str> {
+        files.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    #[test]
+    fn detects_by_magic() {
+        let kind = |data: &[u8]| detect(data).map(ImageKind::name);
+        assert_eq!(kind(b"\x89PNG\r\n\x1a\n...."), Some("PNG"));
+        assert_eq!(kind(b"\xff\xd8\xff\xe0"), Some("JPEG"));
+        assert_eq!(kind(b"DDS \x7c\0\0\0"), Some("DDS"));
+        assert_eq!(kind(b"\xabKTX 20\xbb"), Some("KTX"));
+        assert_eq!(kind(b"anything TRUEVISION-XFILE.\0"), Some("TGA"));
+        assert_eq!(kind(b"version 2.00\n"), None);
+        assert_eq!(kind(b""), None);
+    }
+
+    #[test]
+    fn guesses_tga_from_its_header() {
+        let tga = encoded(&DynamicImage::new_rgba8(2, 2), ImageFormat::Tga);
+        assert!(looks_like_tga(&tga));
+        let mut no_depth = tga.clone();
+        no_depth[16] = 7;
+        assert!(!looks_like_tga(&no_depth));
+        let mut no_width = tga.clone();
+        no_width[12..14].copy_from_slice(&[0, 0]);
+        assert!(!looks_like_tga(&no_width));
+        assert!(!looks_like_tga(&tga[..TGA_HEADER_LENGTH]));
+    }
+
+    #[test]
+    fn writes_originals_conversions_and_thumbnails() {
+        let shared_strings = HashMap::from([("key".to_string(), base64(&encoded(&DynamicImage::new_rgb8(2, 2), ImageFormat::Png)))]);
+        let export = ImageExport::new(&shared_strings);
+        let tga = encoded(&DynamicImage::new_rgba8(4, 4), ImageFormat::Tga);
+        let large = encoded(&DynamicImage::new_rgb8(600, 300), ImageFormat::Png);
+        let properties = [
+            Property { name: "Shared".to_string(), value: PropertyValue::SharedString("key".to_string()) },
+            binary("Texture", &tga),
+            binary("Large", &large),
+            Property { name: "Url".to_string(), value: PropertyValue::Content(Some(format!("data:image/png;base64,{}", base64(&large)))) },
+            binary("Text", b"plain text"),
+        ];
+        let files = export.files(&properties);
+        assert_eq!(names(&files), [
+            "Shared.png", "Texture.tga", "Texture.png", "Large.png", "Large.thumb.png", "Url.png", "Url.thumb.png",
+        ]);
+        assert_eq!(files[1].1, tga);
+        let thumbnail = image::load_from_memory(&files[4].1).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (THUMBNAIL_SIZE, THUMBNAIL_SIZE / 2));
+        assert_eq!(export.summary(), "Embedded images: 4 found (PNG × 3, TGA × 1), 1 converted to PNG, 0 not decodable");
+    }
+
+    #[test]
+    fn strips_paths_from_property_names() {
+        let shared_strings = HashMap::new();
+        let export = ImageExport::new(&shared_strings);
+        let png = encoded(&DynamicImage::new_rgb8(2, 2), ImageFormat::Png);
+        let files = export.files(&[binary("../../../../outside", &png), binary("..\\nested\\CON", &png), binary("..", &png)]);
+        assert_eq!(names(&files), ["........outside.png", "..nestedCON.png", "property.png"]);
+        assert!(files.iter().all(|(name, _)| Path::new(name).components().count() == 1));
+    }
+
+    #[test]
+    fn keeps_undecodable_images_it_is_sure_of() {
+        let shared_strings = HashMap::new();
+        let export = ImageExport::new(&shared_strings);
+        // A header that only looks like TGA is dropped; a broken DDS is still a DDS.
+        let mut guessed = vec![0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4, 0, 4, 0, 32, 0];
+        guessed.extend(b"short");
+        let files = export.files(&[binary("Guess", &guessed), binary("Broken", b"DDS \0\0\0\0")]);
+        assert_eq!(names(&files), ["Broken.dds"]);
+        assert_eq!(export.summary(), "Embedded images: 1 found (DDS × 1), 0 converted to PNG, 1 not decodable");
+    }
+}
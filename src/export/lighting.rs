@@ -0,0 +1,252 @@
+use crate::dom::{Dom, Instance};
+use crate::extract::top_level_items;
+use crate::properties::{property_xml, PropertyValue};
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+pub const PRESET_FORMAT: u32 = 1;
+
+/// Children of Lighting that belong to the look of a place.
+pub const EFFECT_CLASSES: &[&str] = &[
+    "Atmosphere", "Sky", "BloomEffect", "BlurEffect", "ColorCorrectionEffect", "SunRaysEffect", "DepthOfFieldEffect",
+];
+/// Properties tied to one particular place rather than to the look.
+const SKIPPED_PROPERTIES: &[&str] = &["Name", "UniqueId", "HistoryId", "SourceAssetId"];
+
+#[derive(Serialize, Deserialize)]
+pub struct PresetInstance {
+    pub class: String,
+    pub name: String,
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+/// Lighting and its post-processing children as typed properties, in the `{"type", "value"}` form of the JSON dumps.
+#[derive(Serialize, Deserialize)]
+pub struct LightingPreset {
+    pub format: u32,
+    pub lighting: PresetInstance,
+    pub effects: Vec<PresetInstance>,
+}
+
+fn preset_instance(instance: &Instance) -> PresetInstance {
+    let properties = instance.properties.iter()
+        .filter(|p| !SKIPPED_PROPERTIES.contains(&p.name.as_str()))
+        // References and shared strings point into the source place and mean nothing elsewhere.
+        .filter(|p| !matches!(p.value, PropertyValue::Ref(_) | PropertyValue::SharedString(_)))
+        .map(|p| (p.name.clone(), p.value.clone()))
+        .collect();
+    PresetInstance { class: instance.class.clone(), name: instance.name.clone(), properties }
+}
+
+/// The preset of the place's Lighting service, if it has one.
+pub fn collect(dom: &Dom) -> Option<LightingPreset> {
+    let lighting = dom.find_service("Lighting")?;
+    let effects = dom.instances[lighting].children.iter()
+        .map(|&c| &dom.instances[c])
+        .filter(|c| EFFECT_CLASSES.contains(&c.class.as_str()))
+        .map(preset_instance)
+        .collect();
+    Some(LightingPreset { format: PRESET_FORMAT, lighting: preset_instance(&dom.instances[lighting]), effects })
+}
+
+pub fn summary(preset: &LightingPreset) -> String {
+    let effects: Vec<&str> = preset.effects.iter().map(|e| e.class.as_str()).collect();
+    format!(
+        "Lighting: {} properties, {} effects{}",
+        preset.lighting.properties.len(),
+        effects.len(),
+        if effects.is_empty() { String::new() } else { format!(" ({})", effects.join(", ")) }
+    )
+}
+
+fn validate(preset: &LightingPreset) -> anyhow::Result<()> {
+    if preset.format != PRESET_FORMAT {
+        bail!("unsupported lighting preset format {}", preset.format);
+    }
+    if preset.lighting.class != "Lighting" {
+        bail!("preset describes a {}, not Lighting", preset.lighting.class);
+    }
+    if let Some(effect) = preset.effects.iter().find(|e| !EFFECT_CLASSES.contains(&e.class.as_str())) {
+        bail!("{} is not a lighting effect", effect.class);
+    }
+    Ok(())
+}
+
+/// A referent that isn't used anywhere in `place`.
+fn new_referent(place: &str, taken: &mut usize) -> String {
+    loop {
+        *taken += 1;
+        let referent = format!("RBXLIGHTINGPRESET{:06}", taken);
+        if !place.contains(&referent) {
+            return referent;
+        }
+    }
+}
+
+/// The `Name` property of an `<Item>`.
+fn item_name<'a>(item: roxmltree::Node<'a, '_>) -> &'a str {
+    item.children()
+        .filter(|n| n.has_tag_name("Properties"))
+        .flat_map(|n| n.children())
+        .find(|n| n.attribute("name") == Some("Name"))
+        .and_then(|n| n.text())
+        .unwrap_or("")
+}
+
+fn effect_xml(effect: &PresetInstance, referent: &str) -> String {
+    let mut out = format!("<Item class=\"{}\" referent=\"{}\">\n<Properties>\n", effect.class, referent);
+    let _ = writeln!(out, "{}", property_xml("Name", &PropertyValue::String(effect.name.clone())));
+    for (name, value) in &effect.properties {
+        let _ = writeln!(out, "{}", property_xml(name, value));
+    }
+    out.push_str("</Properties>\n</Item>\n");
+    out
+}
+
+/// The rbxlx text of `place` with its Lighting set to `preset`.
+///
+/// Lighting properties named by the preset are replaced and the others kept, so a preset from an older place
+/// doesn't drop newer properties. Existing effects are dropped together with their descendants and replaced
+/// by the preset's; one with the same class and name as a preset effect hands it its referent, so references
+/// to it still resolve. Other children such as scripts stay. A place without Lighting gets one.
+pub fn apply(place: &str, preset: &LightingPreset) -> anyhow::Result<String> {
+    validate(preset)?;
+    let doc = roxmltree::Document::parse(place)?;
+    let mut counter = 0;
+    let lighting = top_level_items(&doc).into_iter().find(|n| n.attribute("class") == Some("Lighting"));
+
+    let Some(lighting) = lighting else {
+        let end = place.rfind("</roblox>").context("the place has no <roblox> root")?;
+        let mut item = effect_xml(&preset.lighting, &new_referent(place, &mut counter));
+        let closing = item.len() - "</Item>\n".len();
+        let effects: String = preset.effects.iter().map(|e| effect_xml(e, &new_referent(place, &mut counter))).collect();
+        item.insert_str(closing, &effects);
+        return Ok(format!("{}{}{}", &place[..end], item, &place[end..]));
+    };
+
+    let referent = lighting.attribute("referent").map(str::to_string).unwrap_or_else(|| new_referent(place, &mut counter));
+    let mut item = format!("<Item class=\"Lighting\" referent=\"{}\">\n<Properties>\n", referent);
+    let mut written = Vec::new();
+    if let Some(properties) = lighting.children().find(|n| n.has_tag_name("Properties")) {
+        for property in properties.children().filter(|n| n.is_element()) {
+            let name = property.attribute("name").unwrap_or("");
+            match preset.lighting.properties.get(name) {
+                Some(value) => {
+                    let _ = writeln!(item, "{}", property_xml(name, value));
+                    written.push(name);
+                }
+                None => {
+                    let _ = writeln!(item, "{}", &place[property.range()]);
+                }
+            }
+        }
+    }
+    for (name, value) in &preset.lighting.properties {
+        if !written.contains(&name.as_str()) {
+            let _ = writeln!(item, "{}", property_xml(name, value));
+        }
+    }
+    item.push_str("</Properties>\n");
+
+    let mut replaced = Vec::new();
+    for child in lighting.children().filter(|n| n.has_tag_name("Item")) {
+        let class = child.attribute("class").unwrap_or("");
+        if !EFFECT_CLASSES.contains(&class) {
+            let _ = writeln!(item, "{}", &place[child.range()]);
+        } else if let Some(referent) = child.attribute("referent") {
+            replaced.push((class, item_name(child), referent));
+        }
+    }
+    for effect in &preset.effects {
+        let reused = replaced.iter().position(|&(class, name, _)| class == effect.class && name == effect.name);
+        let referent = match reused {
+            Some(index) => replaced.remove(index).2.to_string(),
+            None => new_referent(place, &mut counter),
+        };
+        item.push_str(&effect_xml(effect, &referent));
+    }
+    item.push_str("</Item>");
+
+    let range = lighting.range();
+    Ok(format!("{}{}{}", &place[..range.start], item, &place[range.end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLACE: &str = r#"<roblox version="4">
+<Item class="Lighting" referent="RBX1">
+<Properties>
+<string name="Name">Lighting</string>
+<float name="Brightness">2</float>
+<float name="ClockTime">14</float>
+</Properties>
+<Item class="Sky" referent="RBX2">
+<Properties><string name="Name">Sky</string></Properties>
+</Item>
+<Item class="BloomEffect" referent="RBX3">
+<Properties><string name="Name">Bloom</string></Properties>
+<Item class="Folder" referent="RBX4"><Properties><string name="Name">Nested</string></Properties></Item>
+</Item>
+<Item class="Script" referent="RBX5">
+<Properties><string name="Name">DayCycle</string></Properties>
+</Item>
+</Item>
+<Item class="ObjectValue" referent="RBX6">
+<Properties><Ref name="Value">RBX2</Ref></Properties>
+</Item>
+</roblox>"#;
+
+    fn effect(class: &str, name: &str) -> PresetInstance {
+        PresetInstance { class: class.to_string(), name: name.to_string(), properties: BTreeMap::new() }
+    }
+
+    fn preset(effects: Vec<PresetInstance>) -> LightingPreset {
+        let properties = BTreeMap::from([("Brightness".to_string(), PropertyValue::Float(3.0))]);
+        LightingPreset { format: PRESET_FORMAT, lighting: PresetInstance { class: "Lighting".to_string(), name: "Lighting".to_string(), properties }, effects }
+    }
+
+    #[test]
+    fn replaces_effects_and_keeps_the_rest() {
+        let applied = apply(PLACE, &preset(vec![effect("Sky", "Sky"), effect("BloomEffect", "Glow")])).unwrap();
+        let dom = Dom::parse(&applied);
+        let lighting = dom.find_service("Lighting").unwrap();
+        let children: Vec<(&str, &str, Option<&str>)> = dom.instances[lighting].children.iter()
+            .map(|&c| &dom.instances[c])
+            .map(|c| (c.class.as_str(), c.name.as_str(), c.referent.as_deref()))
+            .collect();
+        assert_eq!(children[0], ("Script", "DayCycle", Some("RBX5")));
+        // The Sky keeps its referent, so the ObjectValue still points at it; the renamed bloom gets a new one.
+        assert_eq!(children[1], ("Sky", "Sky", Some("RBX2")));
+        assert_eq!((children[2].0, children[2].1), ("BloomEffect", "Glow"));
+        assert_ne!(children[2].2, Some("RBX3"));
+        assert_eq!(children.len(), 3);
+        assert!(!applied.contains("Nested"));
+        assert!(matches!(dom.instances[lighting].property("Brightness"), Some(PropertyValue::Float(v)) if *v == 3.0));
+        assert!(matches!(dom.instances[lighting].property("ClockTime"), Some(PropertyValue::Float(v)) if *v == 14.0));
+    }
+
+    #[test]
+    fn reuses_each_referent_once() {
+        let applied = apply(PLACE, &preset(vec![effect("Sky", "Sky"), effect("Sky", "Sky")])).unwrap();
+        assert_eq!(applied.matches("referent=\"RBX2\"").count(), 1);
+        assert_eq!(applied.matches("class=\"Sky\"").count(), 2);
+    }
+
+    #[test]
+    fn adds_lighting_to_a_place_without_one() {
+        let applied = apply("<roblox version=\"4\">\n</roblox>", &preset(vec![effect("Atmosphere", "Air")])).unwrap();
+        let dom = Dom::parse(&applied);
+        let lighting = dom.find_service("Lighting").unwrap();
+        assert_eq!(dom.instances[lighting].children.len(), 1);
+        assert_eq!(collect(&dom).unwrap().effects[0].name, "Air");
+    }
+
+    #[test]
+    fn rejects_foreign_effects() {
+        assert!(apply(PLACE, &preset(vec![effect("Part", "Part")])).is_err());
+    }
+}
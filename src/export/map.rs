@@ -0,0 +1,516 @@
+use crate::dom::Dom;
+use crate::export::geometry::{cframe, part_color, part_size, part_transparency, primitive_mesh, shape, Shape, BASIC_PART_CLASSES, PLACEHOLDER_CLASSES};
+use crate::properties::PropertyValue;
+use image::{ImageFormat, Rgba, RgbaImage};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+
+/// Longest side of the image in pixels.
+pub const DEFAULT_SIZE: u32 = 2048;
+/// Smallest and largest accepted sizes; a square map at the largest takes 1 GB in memory.
+pub const MIN_SIZE: u32 = 16;
+pub const MAX_SIZE: u32 = 16384;
+const BACKGROUND: [u8; 3] = [24, 26, 30];
+const SPAWN_COLOR: [u8; 3] = [60, 220, 90];
+const MODEL_COLOR: [u8; 3] = [255, 210, 60];
+const OUTLINE_COLOR: [u8; 3] = [0, 0, 0];
+/// Models still called this were never named by anyone and aren't worth a marker.
+const DEFAULT_MODEL_NAME: &str = "Model";
+/// Share of the part extent added around automatic bounds.
+const MARGIN: f64 = 0.02;
+/// The lowest parts are drawn this much darker than the highest, so height differences stay visible.
+const HEIGHT_SHADING: f32 = 0.35;
+
+/// What the parts are colored by.
+#[derive(Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum MapColoring {
+    /// The part's own color.
+    #[default]
+    Color,
+    /// One color per Enum.Material.
+    Material,
+    /// One color per class name.
+    Class,
+}
+
+impl MapColoring {
+    fn name(self) -> &'static str {
+        match self {
+            MapColoring::Color => "color",
+            MapColoring::Material => "material",
+            MapColoring::Class => "class",
+        }
+    }
+}
+
+/// The rendered area on the X/Z plane in studs.
+#[derive(Clone, Copy, Serialize)]
+pub struct MapBounds {
+    pub min: [f64; 2],
+    pub max: [f64; 2],
+}
+
+/// Parses `minX,minZ,maxX,maxZ`.
+pub fn parse_bounds(text: &str) -> Result<MapBounds, String> {
+    let values: Vec<f64> = text.split(',').map(|v| v.trim().parse::<f64>().map_err(|_| format!("invalid number {}", v.trim()))).collect::<Result<_, _>>()?;
+    let [x1, z1, x2, z2] = values[..] else {
+        return Err(format!("expected minX,minZ,maxX,maxZ, got {}", text));
+    };
+    if [x1, z1, x2, z2].iter().any(|v| !v.is_finite()) {
+        return Err(format!("bounds {} are not finite", text));
+    }
+    if x2 <= x1 || z2 <= z1 {
+        return Err(format!("bounds {} are empty", text));
+    }
+    Ok(MapBounds { min: [x1, z1], max: [x2, z2] })
+}
+
+/// Enum.Material, with colors close to how the materials look from above.
+const MATERIALS: &[(u32, &str, [u8; 3])] = &[
+    (256, "Plastic", [163, 162, 165]),
+    (272, "SmoothPlastic", [190, 190, 190]),
+    (288, "Neon", [255, 90, 220]),
+    (512, "Wood", [150, 103, 60]),
+    (528, "WoodPlanks", [170, 125, 80]),
+    (784, "Marble", [220, 215, 205]),
+    (788, "Basalt", [60, 60, 65]),
+    (800, "Slate", [90, 95, 100]),
+    (804, "CrackedLava", [200, 80, 30]),
+    (816, "Concrete", [150, 150, 145]),
+    (820, "Limestone", [205, 195, 160]),
+    (832, "Granite", [140, 125, 120]),
+    (836, "Pavement", [120, 120, 125]),
+    (848, "Brick", [160, 80, 60]),
+    (864, "Pebble", [170, 165, 150]),
+    (880, "Cobblestone", [125, 120, 110]),
+    (896, "Rock", [105, 100, 95]),
+    (912, "Sandstone", [195, 150, 105]),
+    (1040, "CorrodedMetal", [110, 85, 70]),
+    (1056, "DiamondPlate", [175, 180, 185]),
+    (1072, "Foil", [200, 200, 210]),
+    (1088, "Metal", [130, 135, 145]),
+    (1280, "Grass", [95, 150, 60]),
+    (1284, "LeafyGrass", [80, 135, 55]),
+    (1296, "Sand", [215, 195, 140]),
+    (1312, "Fabric", [150, 110, 130]),
+    (1328, "Snow", [240, 245, 250]),
+    (1344, "Mud", [90, 70, 50]),
+    (1360, "Ground", [110, 95, 70]),
+    (1376, "Asphalt", [70, 70, 75]),
+    (1392, "Salt", [225, 220, 215]),
+    (1536, "Ice", [175, 215, 235]),
+    (1552, "Glacier", [150, 200, 225]),
+    (1568, "Glass", [160, 200, 220]),
+    (1584, "ForceField", [120, 170, 255]),
+    (2048, "Water", [40, 110, 190]),
+];
+
+/// A stable, fairly saturated color for any name.
+fn hashed_color(name: &str) -> [f32; 3] {
+    // FNV-1a
+    let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+    let hue = (hash % 360) as f32 / 60.0;
+    let (saturation, value) = (0.55, 0.85);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let [r, g, b] = match hue as u32 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    [r, g, b].map(|c| c + value - chroma)
+}
+
+fn hex(color: [f32; 3]) -> String {
+    let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+struct MapPart {
+    /// Footprint triangles on the X/Z plane.
+    triangles: Vec<[[f64; 2]; 3]>,
+    area: MapBounds,
+    top: f64,
+    color: [f32; 3],
+    opacity: f32,
+    /// The material or class the part is colored by.
+    group: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MapLegendEntry {
+    pub name: String,
+    pub color: String,
+    pub parts: usize,
+}
+
+#[derive(Serialize)]
+pub struct MapMarker {
+    pub kind: &'static str,
+    pub path: String,
+    /// The spawn's position, or the center of the model's parts with the height of their top.
+    pub position: [f64; 3],
+    pub pixel: [i64; 2],
+    /// The model's outline in pixels: left, top, right, bottom.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pixel_bounds: Option<[i64; 4]>,
+}
+
+/// What `map.json` says about the image, so positions on it can be traced back to studs.
+#[derive(Serialize)]
+pub struct MapInfo {
+    pub bounds: MapBounds,
+    pub width: u32,
+    pub height: u32,
+    pub studs_per_pixel: f64,
+    pub color_by: &'static str,
+    pub parts: usize,
+    /// Colors used for each material or class; empty when parts keep their own color.
+    pub legend: Vec<MapLegendEntry>,
+    pub markers: Vec<MapMarker>,
+}
+
+pub struct Map {
+    pub image: RgbaImage,
+    pub info: MapInfo,
+}
+
+/// The X/Z bounding box of a set of footprint triangles.
+fn footprint_bounds<'a>(triangles: impl Iterator<Item = &'a [[f64; 2]; 3]>) -> Option<MapBounds> {
+    let mut bounds: Option<MapBounds> = None;
+    for point in triangles.flatten() {
+        let b = bounds.get_or_insert(MapBounds { min: *point, max: *point });
+        for (i, &v) in point.iter().enumerate() {
+            b.min[i] = b.min[i].min(v);
+            b.max[i] = b.max[i].max(v);
+        }
+    }
+    bounds
+}
+
+struct Canvas {
+    image: RgbaImage,
+    bounds: MapBounds,
+    scale: f64,
+}
+
+impl Canvas {
+    fn pixel(&self, point: [f64; 2]) -> [f64; 2] {
+        [(point[0] - self.bounds.min[0]) * self.scale, (point[1] - self.bounds.min[1]) * self.scale]
+    }
+
+    fn blend(&mut self, x: i64, y: i64, color: [u8; 3], opacity: f32) {
+        if x < 0 || y < 0 || x >= self.image.width() as i64 || y >= self.image.height() as i64 {
+            return;
+        }
+        let pixel = self.image.get_pixel_mut(x as u32, y as u32);
+        for (channel, &c) in pixel.0.iter_mut().zip(&color) {
+            *channel = (*channel as f32 * (1.0 - opacity) + c as f32 * opacity).round() as u8;
+        }
+    }
+
+    /// Fills the triangles as one shape, so overlapping faces of the same part aren't blended twice.
+    fn fill(&mut self, triangles: &[[[f64; 2]; 3]], color: [u8; 3], opacity: f32) {
+        let pixels: Vec<[[f64; 2]; 3]> = triangles.iter().map(|t| t.map(|p| self.pixel(p))).collect();
+        let Some(area) = footprint_bounds(pixels.iter()) else { return };
+        let (width, height) = (self.image.width() as f64, self.image.height() as f64);
+        let left = area.min[0].floor().max(0.0) as i64;
+        let top = area.min[1].floor().max(0.0) as i64;
+        let right = area.max[0].ceil().min(width) as i64;
+        let bottom = area.max[1].ceil().min(height) as i64;
+        if left >= right || top >= bottom {
+            return;
+        }
+
+        let columns = (right - left) as usize;
+        let mut covered = vec![false; columns * (bottom - top) as usize];
+        for [a, b, c] in &pixels {
+            let edge = |p: &[f64; 2], q: &[f64; 2], x: f64, y: f64| (q[0] - p[0]) * (y - p[1]) - (q[1] - p[1]) * (x - p[0]);
+            let doubled_area = edge(a, b, c[0], c[1]);
+            if doubled_area == 0.0 {
+                continue;
+            }
+            let x0 = (a[0].min(b[0]).min(c[0]).floor() as i64).max(left);
+            let x1 = (a[0].max(b[0]).max(c[0]).ceil() as i64).min(right);
+            let y0 = (a[1].min(b[1]).min(c[1]).floor() as i64).max(top);
+            let y1 = (a[1].max(b[1]).max(c[1]).ceil() as i64).min(bottom);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                    let sides = [edge(a, b, px, py), edge(b, c, px, py), edge(c, a, px, py)];
+                    if sides.iter().all(|s| s * doubled_area >= 0.0) {
+                        covered[(y - top) as usize * columns + (x - left) as usize] = true;
+                    }
+                }
+            }
+        }
+
+        // Parts thinner than a pixel still leave a dot.
+        if !covered.contains(&true) {
+            let center = [(area.min[0] + area.max[0]) / 2.0, (area.min[1] + area.max[1]) / 2.0];
+            self.blend(center[0] as i64, center[1] as i64, color, opacity);
+            return;
+        }
+        for (i, _) in covered.iter().enumerate().filter(|(_, c)| **c) {
+            self.blend(left + (i % columns) as i64, top + (i / columns) as i64, color, opacity);
+        }
+    }
+
+    fn disc(&mut self, center: [f64; 2], radius: f64, color: [u8; 3]) {
+        let r = radius.ceil() as i64;
+        let (cx, cy) = (center[0].floor() as i64, center[1].floor() as i64);
+        for y in -r..=r {
+            for x in -r..=r {
+                if ((x * x + y * y) as f64) <= radius * radius {
+                    self.blend(cx + x, cy + y, color, 1.0);
+                }
+            }
+        }
+    }
+
+    fn rectangle(&mut self, [left, top, right, bottom]: [i64; 4], thickness: i64, color: [u8; 3]) {
+        for t in 0..thickness {
+            for x in left..=right {
+                self.blend(x, top + t, color, 1.0);
+                self.blend(x, bottom - t, color, 1.0);
+            }
+            for y in top..=bottom {
+                self.blend(left + t, y, color, 1.0);
+                self.blend(right - t, y, color, 1.0);
+            }
+        }
+    }
+}
+
+/// Every visible part under `root` with its footprint.
+fn collect_parts(dom: &Dom, root: usize, coloring: MapColoring) -> Vec<(usize, MapPart)> {
+    let mut parts = Vec::new();
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        let instance = &dom.instances[id];
+        stack.extend(instance.children.iter().rev());
+        let placeholder = PLACEHOLDER_CLASSES.contains(&instance.class.as_str());
+        if !placeholder && !BASIC_PART_CLASSES.contains(&instance.class.as_str()) {
+            continue;
+        }
+        let Some(world) = cframe(instance) else { continue };
+        let opacity = 1.0 - part_transparency(instance);
+        if opacity <= 0.0 {
+            continue;
+        }
+
+        let mesh = primitive_mesh(if placeholder { Shape::Block } else { shape(instance) }, part_size(instance));
+        let points: Vec<[f64; 3]> = mesh.positions.iter().map(|p| world.apply(p.map(|v| v as f64))).collect();
+        let triangles: Vec<[[f64; 2]; 3]> = mesh.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]].map(|i| [points[i as usize][0], points[i as usize][2]])).collect();
+        let Some(area) = footprint_bounds(triangles.iter()) else { continue };
+        let top = points.iter().map(|p| p[1]).fold(f64::MIN, f64::max);
+
+        let (color, group) = match coloring {
+            MapColoring::Color => (part_color(instance), None),
+            MapColoring::Material => {
+                let material = match instance.property("Material") {
+                    Some(PropertyValue::Token(value)) => *value,
+                    _ => 256,
+                };
+                match MATERIALS.iter().find(|(id, ..)| *id == material) {
+                    Some((_, name, color)) => (color.map(|c| c as f32 / 255.0), Some(name.to_string())),
+                    None => {
+                        let name = format!("Material {}", material);
+                        (hashed_color(&name), Some(name))
+                    }
+                }
+            }
+            MapColoring::Class => (hashed_color(&instance.class), Some(instance.class.clone())),
+        };
+        parts.push((id, MapPart { triangles, area, top, color, opacity, group }));
+    }
+    parts
+}
+
+/// An orthographic top-down image of every part under `root`, looking down with +X to the right and +Z down.
+/// Higher parts are drawn over lower ones. Without `bounds` the image covers all parts. `size` is kept
+/// between [`MIN_SIZE`] and [`MAX_SIZE`].
+pub fn render(dom: &Dom, root: usize, size: u32, bounds: Option<MapBounds>, coloring: MapColoring) -> Option<Map> {
+    let size = size.clamp(MIN_SIZE, MAX_SIZE);
+    let mut parts = collect_parts(dom, root, coloring);
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => {
+            let b = parts.iter().map(|(_, p)| p.area).reduce(|a, b| MapBounds {
+                min: [a.min[0].min(b.min[0]), a.min[1].min(b.min[1])],
+                max: [a.max[0].max(b.max[0]), a.max[1].max(b.max[1])],
+            })?;
+            let margin = ((b.max[0] - b.min[0]).max(b.max[1] - b.min[1]) * MARGIN).max(1.0);
+            MapBounds { min: b.min.map(|v| v - margin), max: b.max.map(|v| v + margin) }
+        }
+    };
+    parts.retain(|(_, p)| (0..2).all(|i| p.area.min[i] < bounds.max[i] && p.area.max[i] > bounds.min[i]));
+    let extent = [bounds.max[0] - bounds.min[0], bounds.max[1] - bounds.min[1]];
+    let scale = size as f64 / extent[0].max(extent[1]);
+    let [width, height] = extent.map(|e| ((e * scale).round() as u32).max(1));
+    let [r, g, b] = BACKGROUND;
+    let mut canvas = Canvas { image: RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255])), bounds, scale };
+
+    parts.sort_by(|a, b| a.1.top.total_cmp(&b.1.top));
+    let lowest = parts.first().map_or(0.0, |(_, p)| p.top);
+    let highest = parts.last().map_or(0.0, |(_, p)| p.top);
+    for (_, part) in &parts {
+        let height = if highest > lowest { ((part.top - lowest) / (highest - lowest)) as f32 } else { 1.0 };
+        let shade = 1.0 - HEIGHT_SHADING * (1.0 - height);
+        let color = part.color.map(|c| (c * shade).clamp(0.0, 1.0) * 255.0).map(|c| c.round() as u8);
+        canvas.fill(&part.triangles, color, part.opacity);
+    }
+
+    let marker_size = (size as f64 / 256.0).max(4.0);
+    let mut markers = Vec::new();
+    // Parts of each named model, found by walking up from every part.
+    let mut models: HashMap<usize, Vec<&MapPart>> = HashMap::new();
+    for (id, part) in &parts {
+        let mut current = dom.instances[*id].parent;
+        while let Some(ancestor) = current {
+            let instance = &dom.instances[ancestor];
+            if instance.class == "Model" && instance.name != DEFAULT_MODEL_NAME {
+                models.entry(ancestor).or_default().push(part);
+            }
+            if ancestor == root {
+                break;
+            }
+            current = instance.parent;
+        }
+    }
+    let mut stack = vec![root];
+    while let Some(id) = stack.pop() {
+        stack.extend(dom.instances[id].children.iter().rev());
+        let Some(own) = models.get(&id) else { continue };
+        let Some(area) = footprint_bounds(own.iter().flat_map(|p| p.triangles.iter())) else { continue };
+        let top = own.iter().map(|p| p.top).fold(f64::MIN, f64::max);
+        let center = [(area.min[0] + area.max[0]) / 2.0, (area.min[1] + area.max[1]) / 2.0];
+        let [left, upper] = canvas.pixel(area.min).map(|v| v.floor() as i64);
+        let [right, lower] = canvas.pixel(area.max).map(|v| v.ceil() as i64);
+        let pixel = canvas.pixel(center);
+        canvas.rectangle([left, upper, right, lower], (marker_size / 4.0).ceil() as i64, MODEL_COLOR);
+        canvas.disc(pixel, marker_size / 2.0, MODEL_COLOR);
+        markers.push(MapMarker {
+            kind: "Model",
+            path: dom.path(id),
+            position: [center[0], top, center[1]],
+            pixel: pixel.map(|v| v.floor() as i64),
+            pixel_bounds: Some([left, upper, right, lower]),
+        });
+    }
+    for (id, _) in &parts {
+        let instance = &dom.instances[*id];
+        if instance.class != "SpawnLocation" {
+            continue;
+        }
+        let Some(world) = cframe(instance) else { continue };
+        let pixel = canvas.pixel([world.position[0], world.position[2]]);
+        canvas.disc(pixel, marker_size + 1.0, OUTLINE_COLOR);
+        canvas.disc(pixel, marker_size, SPAWN_COLOR);
+        markers.push(MapMarker { kind: "SpawnLocation", path: dom.path(*id), position: world.position, pixel: pixel.map(|v| v.floor() as i64), pixel_bounds: None });
+    }
+
+    let mut legend: BTreeMap<&str, ([f32; 3], usize)> = BTreeMap::new();
+    for (_, part) in &parts {
+        if let Some(group) = &part.group {
+            legend.entry(group).or_insert((part.color, 0)).1 += 1;
+        }
+    }
+    let legend = legend.into_iter().map(|(name, (color, parts))| MapLegendEntry { name: name.to_string(), color: hex(color), parts }).collect();
+    let info = MapInfo { bounds, width, height, studs_per_pixel: 1.0 / scale, color_by: coloring.name(), parts: parts.len(), legend, markers };
+    Some(Map { image: canvas.image, info })
+}
+
+pub fn summary(map: &Map) -> String {
+    let count = |kind: &str| map.info.markers.iter().filter(|m| m.kind == kind).count();
+    format!(
+        "Map: {} parts, {}x{} px at {:.2} studs/px, {} spawns, {} models",
+        map.info.parts,
+        map.info.width,
+        map.info.height,
+        map.info.studs_per_pixel,
+        count("SpawnLocation"),
+        count("Model")
+    )
+}
+
+/// `<stem>.png` and `<stem>.json` with the bounds, scale, legend and marker positions.
+pub fn map_files(map: &Map, stem: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let mut png = Vec::new();
+    map.image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(vec![(format!("{}.png", stem), png), (format!("{}.json", stem), serde_json::to_vec_pretty(&map.info)?)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part(class: &str, name: &str, position: [f64; 3], size: [f64; 3], material: u32) -> String {
+        format!(
+            r#"<Item class="{class}" referent="{name}"><Properties><string name="Name">{name}</string>
+<CoordinateFrame name="CFrame"><X>{}</X><Y>{}</Y><Z>{}</Z><R00>1</R00><R01>0</R01><R02>0</R02><R10>0</R10><R11>1</R11><R12>0</R12><R20>0</R20><R21>0</R21><R22>1</R22></CoordinateFrame>
+<Vector3 name="size"><X>{}</X><Y>{}</Y><Z>{}</Z></Vector3><token name="Material">{material}</token></Properties></Item>"#,
+            position[0], position[1], position[2], size[0], size[1], size[2]
+        )
+    }
+
+    fn place() -> Dom {
+        let ground = part("Part", "Ground", [0.0, 0.0, 0.0], [100.0, 1.0, 50.0], 1280);
+        let spawn = part("SpawnLocation", "Spawn", [-25.0, 1.0, 0.0], [6.0, 1.0, 6.0], 256);
+        let house = part("Part", "Wall", [25.0, 5.0, 10.0], [10.0, 10.0, 10.0], 848);
+        Dom::parse(&format!(
+            r#"<roblox version="4"><Item class="Workspace" referent="W"><Properties><string name="Name">Workspace</string></Properties>
+{ground}{spawn}<Item class="Model" referent="H"><Properties><string name="Name">House</string></Properties>{house}</Item>
+</Item></roblox>"#
+        ))
+    }
+
+    #[test]
+    fn parses_bounds() {
+        let bounds = parse_bounds("-10, -20,30,40").unwrap();
+        assert_eq!((bounds.min, bounds.max), ([-10.0, -20.0], [30.0, 40.0]));
+        assert!(parse_bounds("0,0,10").is_err());
+        assert!(parse_bounds("0,0,10,x").is_err());
+        assert!(parse_bounds("10,0,0,10").is_err());
+        assert!(parse_bounds("0,0,inf,10").is_err());
+        assert!(parse_bounds("0,0,NaN,10").is_err());
+    }
+
+    #[test]
+    fn renders_parts_and_markers() {
+        let dom = place();
+        let workspace = dom.find_service("Workspace").unwrap();
+        let bounds = MapBounds { min: [-50.0, -25.0], max: [50.0, 25.0] };
+        let map = render(&dom, workspace, 100, Some(bounds), MapColoring::Material).unwrap();
+        assert_eq!((map.image.width(), map.image.height(), map.info.studs_per_pixel), (100, 50, 1.0));
+        assert_eq!(map.info.parts, 3);
+
+        let spawn = map.info.markers.iter().find(|m| m.kind == "SpawnLocation").unwrap();
+        assert_eq!(spawn.pixel, [25, 25]);
+        assert_eq!(&map.image.get_pixel(25, 25).0[..3], &SPAWN_COLOR);
+        let house = map.info.markers.iter().find(|m| m.kind == "Model").unwrap();
+        assert_eq!((house.path.as_str(), house.pixel, house.pixel_bounds), ("Workspace.House", [75, 35], Some([70, 30, 80, 40])));
+        assert_eq!(&map.image.get_pixel(70, 35).0[..3], &MODEL_COLOR);
+        // The ground is the lowest part, so it is drawn darker than its grass color.
+        let ground = map.image.get_pixel(5, 5).0;
+        assert!(ground[1] < 150 && ground[1] > 80 && ground[1] > ground[0]);
+
+        let legend: Vec<(&str, usize)> = map.info.legend.iter().map(|e| (e.name.as_str(), e.parts)).collect();
+        assert_eq!(legend, [("Brick", 1), ("Grass", 1), ("Plastic", 1)]);
+        assert_eq!(map.info.legend[1].color, "#5f963c");
+    }
+
+    #[test]
+    fn fits_all_parts_and_limits_the_size() {
+        let dom = place();
+        let workspace = dom.find_service("Workspace").unwrap();
+        let map = render(&dom, workspace, 1, None, MapColoring::Color).unwrap();
+        assert_eq!(map.image.width(), MIN_SIZE);
+        assert!(map.info.bounds.min[0] < -50.0 && map.info.bounds.max[0] > 50.0);
+        assert!(map.info.legend.is_empty());
+    }
+}
@@ -0,0 +1,433 @@
+use crate::export::geometry::{Mesh, PLACEHOLDER_CLASSES};
+use crate::export::obj::meshes_to_obj;
+use crate::export::property_file_name;
+use crate::properties::{decode_base64, Property, PropertyValue};
+use anyhow::{bail, Context};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+const MESH_MAGIC: &[u8] = b"version ";
+const CSG_MESH_MAGIC: &[u8] = b"CSGMDL";
+const CSG_PHYSICS_MAGIC: &[u8] = b"CSGPHS";
+
+/// A decoded `PhysicsData`, `MeshData`, `ChildData` or `PhysicalConfigData` payload.
+pub enum Payload {
+    /// A Roblox mesh file, `version 1.00` to `5.00`; only the most detailed level of detail is kept.
+    Mesh { version: String, mesh: Mesh },
+    /// The convex hulls a union or mesh part collides with. No hulls means the part collides as its bounding box.
+    Physics { version: u32, hulls: Vec<Mesh> },
+    /// A known container whose contents can't be decoded, such as the obfuscated `CSGMDL` meshes.
+    Opaque { format: String },
+}
+
+impl Payload {
+    pub fn format(&self) -> String {
+        match self {
+            Payload::Mesh { version, .. } => format!("mesh {}", version),
+            Payload::Physics { version, .. } => format!("CSGPHS {}", version),
+            Payload::Opaque { format } => format.clone(),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> anyhow::Result<&'a [u8]> {
+        let end = self.offset.checked_add(count).filter(|&end| end <= self.data.len())
+            .with_context(|| format!("payload ends early at byte {}", self.offset))?;
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> anyhow::Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> anyhow::Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into()?))
+    }
+
+    fn vector(&mut self) -> anyhow::Result<[f32; 3]> {
+        let bytes = self.bytes(12)?;
+        Ok(std::array::from_fn(|i| f32::from_le_bytes(bytes[i * 4..i * 4 + 4].try_into().unwrap())))
+    }
+
+    fn at_end(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+}
+
+/// Positions and normals of `count` vertices `stride` bytes apart, each starting with a position and a normal.
+fn read_vertices(reader: &mut Reader, count: usize, stride: usize) -> anyhow::Result<Mesh> {
+    if stride < 24 {
+        bail!("vertices of {} bytes are too small", stride);
+    }
+    let mut mesh = Mesh::default();
+    for _ in 0..count {
+        let vertex = reader.bytes(stride)?;
+        let mut vertex = Reader { data: vertex, offset: 0 };
+        mesh.positions.push(vertex.vector()?);
+        mesh.normals.push(vertex.vector()?);
+    }
+    Ok(mesh)
+}
+
+/// `count` triangles `stride` bytes apart, keeping those in `range`.
+fn read_faces(reader: &mut Reader, mesh: &mut Mesh, count: usize, stride: usize, range: std::ops::Range<usize>) -> anyhow::Result<()> {
+    if stride < 12 {
+        bail!("faces of {} bytes are too small", stride);
+    }
+    for face in 0..count {
+        let bytes = reader.bytes(stride)?;
+        if !range.contains(&face) {
+            continue;
+        }
+        let mut face = Reader { data: bytes, offset: 0 };
+        for _ in 0..3 {
+            let index = face.u32()?;
+            if index as usize >= mesh.positions.len() {
+                bail!("face refers to vertex {} of {}", index, mesh.positions.len());
+            }
+            mesh.indices.push(index);
+        }
+    }
+    Ok(())
+}
+
+/// The faces of the first level of detail, given the face offsets where each level starts.
+fn first_lod(lods: &[u32], faces: usize) -> std::ops::Range<usize> {
+    match lods {
+        [start, end, ..] if start <= end && *end as usize <= faces => *start as usize..*end as usize,
+        _ => 0..faces,
+    }
+}
+
+/// Version 1 meshes are text: the face count on the second line, then `[x,y,z]` position, normal and
+/// texture coordinate triples for three vertices per face. `1.00` stores positions at twice the size.
+fn read_text_mesh(body: &[u8], scale: f32) -> anyhow::Result<Mesh> {
+    let text = String::from_utf8_lossy(body);
+    let mut lines = text.lines();
+    let faces: usize = lines.next().unwrap_or("").trim().parse().context("missing face count")?;
+    let numbers: Vec<f32> = lines
+        .flat_map(|line| line.split(['[', ']', ',']))
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| n.trim().parse::<f32>())
+        .collect::<Result<_, _>>()?;
+    let needed = faces.checked_mul(27).with_context(|| format!("face count {} is too large", faces))?;
+    if numbers.len() < needed {
+        bail!("{} faces need {} numbers, found {}", faces, needed, numbers.len());
+    }
+    let mut mesh = Mesh::default();
+    for vertex in numbers.chunks_exact(9).take(faces * 3) {
+        mesh.indices.push(mesh.positions.len() as u32);
+        mesh.positions.push([vertex[0], vertex[1], vertex[2]].map(|v| v * scale));
+        mesh.normals.push([vertex[3], vertex[4], vertex[5]]);
+    }
+    Ok(mesh)
+}
+
+fn read_binary_mesh(version: &str, body: &[u8]) -> anyhow::Result<Mesh> {
+    let mut reader = Reader { data: body, offset: 0 };
+    let header_size = reader.u16()? as usize;
+    match version {
+        "2.00" => {
+            let vertex_size = reader.bytes(1)?[0] as usize;
+            let face_size = reader.bytes(1)?[0] as usize;
+            let vertices = reader.u32()? as usize;
+            let faces = reader.u32()? as usize;
+            reader.offset = header_size;
+            let mut mesh = read_vertices(&mut reader, vertices, vertex_size)?;
+            read_faces(&mut reader, &mut mesh, faces, face_size, 0..faces)?;
+            Ok(mesh)
+        }
+        "3.00" | "3.01" => {
+            let vertex_size = reader.bytes(1)?[0] as usize;
+            let face_size = reader.bytes(1)?[0] as usize;
+            let lod_size = reader.u16()? as usize;
+            let lod_count = reader.u16()? as usize;
+            let vertices = reader.u32()? as usize;
+            let faces = reader.u32()? as usize;
+            reader.offset = header_size;
+            let mut mesh = read_vertices(&mut reader, vertices, vertex_size)?;
+            let face_start = reader.offset;
+            reader.bytes(faces * face_size)?;
+            let lods = (0..lod_count)
+                .map(|_| Ok(u32::from_le_bytes(reader.bytes(lod_size.max(4))?[..4].try_into()?)))
+                .collect::<anyhow::Result<Vec<u32>>>()?;
+            reader.offset = face_start;
+            read_faces(&mut reader, &mut mesh, faces, face_size, first_lod(&lods, faces))?;
+            Ok(mesh)
+        }
+        "4.00" | "4.01" | "5.00" => {
+            let _lod_type = reader.u16()?;
+            let vertices = reader.u32()? as usize;
+            let faces = reader.u32()? as usize;
+            let lod_count = reader.u16()? as usize;
+            let bones = reader.u16()?;
+            reader.offset = header_size;
+            let mut mesh = read_vertices(&mut reader, vertices, 40)?;
+            if bones > 0 {
+                // Skinning envelopes: four bone indices and four weights per vertex.
+                reader.bytes(vertices * 8)?;
+            }
+            let face_start = reader.offset;
+            reader.bytes(faces * 12)?;
+            let lods = (0..lod_count).map(|_| reader.u32()).collect::<anyhow::Result<Vec<u32>>>()?;
+            reader.offset = face_start;
+            read_faces(&mut reader, &mut mesh, faces, 12, first_lod(&lods, faces))?;
+            Ok(mesh)
+        }
+        _ => bail!("unsupported mesh version {}", version),
+    }
+}
+
+/// `CSGPHS` followed by a version and, unless the part collides as its bounding box, a list of convex hulls,
+/// each a vertex count, the vertices, an index count and the triangle indices.
+fn read_physics(data: &[u8]) -> anyhow::Result<(u32, Vec<Mesh>)> {
+    let mut reader = Reader { data, offset: CSG_PHYSICS_MAGIC.len() };
+    let version = reader.u32()?;
+    let mut hulls = Vec::new();
+    while !reader.at_end() {
+        let vertices = reader.u32()? as usize;
+        if vertices * 12 > data.len() - reader.offset {
+            bail!("hull with {} vertices is larger than the payload", vertices);
+        }
+        let mut hull = Mesh::default();
+        for _ in 0..vertices {
+            hull.positions.push(reader.vector()?);
+        }
+        let indices = reader.u32()? as usize;
+        if !indices.is_multiple_of(3) {
+            bail!("hull index count {} is not a multiple of 3", indices);
+        }
+        for _ in 0..indices {
+            let index = reader.u32()?;
+            if index as usize >= vertices {
+                bail!("hull refers to vertex {} of {}", index, vertices);
+            }
+            hull.indices.push(index);
+        }
+        hulls.push(hull);
+    }
+    Ok((version, hulls))
+}
+
+/// Recognizes a payload by its magic bytes. `None` for anything that isn't mesh or physics data.
+pub fn decode(data: &[u8]) -> Option<Payload> {
+    if data.starts_with(MESH_MAGIC) {
+        let newline = data.iter().take(32).position(|&b| b == b'\n')?;
+        let version = String::from_utf8_lossy(&data[MESH_MAGIC.len()..newline]).trim().to_string();
+        let body = &data[newline + 1..];
+        let mesh = match version.as_str() {
+            "1.00" => read_text_mesh(body, 0.5),
+            "1.01" => read_text_mesh(body, 1.0),
+            _ => read_binary_mesh(&version, body),
+        };
+        return Some(match mesh {
+            Ok(mesh) => Payload::Mesh { version, mesh },
+            Err(_) => Payload::Opaque { format: format!("mesh {}", version) },
+        });
+    }
+    let version = || data.get(6..10).map(|b| u32::from_le_bytes(b.try_into().unwrap())).unwrap_or(0);
+    if data.starts_with(CSG_PHYSICS_MAGIC) {
+        return Some(match read_physics(data) {
+            Ok((version, hulls)) => Payload::Physics { version, hulls },
+            Err(_) => Payload::Opaque { format: format!("CSGPHS {}", version()) },
+        });
+    }
+    if data.starts_with(CSG_MESH_MAGIC) {
+        return Some(Payload::Opaque { format: format!("CSGMDL {}", version()) });
+    }
+    None
+}
+
+#[derive(Default)]
+struct Counts {
+    decoded: BTreeMap<String, usize>,
+    raw: BTreeMap<String, usize>,
+}
+
+/// Decodes the mesh payloads of unions and mesh parts into files written next to their `properties.json`.
+/// Shared between the extraction threads, so it also keeps count of what it found.
+pub struct MeshDataExport<'a> {
+    shared_strings: &'a HashMap<String, String>,
+    counts: Mutex<Counts>,
+}
+
+impl<'a> MeshDataExport<'a> {
+    pub fn new(shared_strings: &'a HashMap<String, String>) -> Self {
+        Self { shared_strings, counts: Mutex::new(Counts::default()) }
+    }
+
+    fn payload(&self, value: &PropertyValue) -> Option<Vec<u8>> {
+        let base64 = match value {
+            PropertyValue::BinaryString(data) => data,
+            PropertyValue::SharedString(key) => self.shared_strings.get(key)?,
+            _ => return None,
+        };
+        decode_base64(base64).filter(|data| !data.is_empty())
+    }
+
+    /// `<Property>.obj` for every decoded payload of the instance and `<Property>.bin` with the raw bytes of those
+    /// that are recognized but can't be decoded.
+    pub fn files(&self, class: &str, properties: &[Property]) -> Vec<(String, Vec<u8>)> {
+        if !PLACEHOLDER_CLASSES.contains(&class) {
+            return Vec::new();
+        }
+        let mut files = Vec::new();
+        for property in properties {
+            let Some(data) = self.payload(&property.value) else { continue };
+            let Some(payload) = decode(&data) else { continue };
+            let format = payload.format();
+            let header = vec![format!("{}.{} ({})", class, property.name, format)];
+            let mut counts = self.counts.lock().unwrap();
+            match payload {
+                Payload::Mesh { mesh, .. } => {
+                    let obj = meshes_to_obj(&[(property.name.clone(), &mesh)], &header);
+                    files.push((property_file_name(&property.name, "obj"), obj.into_bytes()));
+                    *counts.decoded.entry(format).or_default() += 1;
+                }
+                Payload::Physics { hulls, .. } => {
+                    if !hulls.is_empty() {
+                        let named: Vec<(String, &Mesh)> = hulls.iter().enumerate().map(|(i, hull)| (format!("hull{}", i + 1), hull)).collect();
+                        files.push((property_file_name(&property.name, "obj"), meshes_to_obj(&named, &header).into_bytes()));
+                    }
+                    *counts.decoded.entry(format).or_default() += 1;
+                }
+                Payload::Opaque { .. } => {
+                    files.push((property_file_name(&property.name, "bin"), data));
+                    *counts.raw.entry(format).or_default() += 1;
+                }
+            }
+        }
+        files
+    }
+
+    pub fn summary(&self) -> String {
+        let counts = self.counts.lock().unwrap();
+        let list = |counts: &BTreeMap<String, usize>, what: &str| {
+            let total: usize = counts.values().sum();
+            let parts: Vec<String> = counts.iter().map(|(format, count)| format!("{} × {}", format, count)).collect();
+            if parts.is_empty() { format!("{} {}", total, what) } else { format!("{} {} ({})", total, what, parts.join(", ")) }
+        };
+        format!("Mesh data: {}, {}", list(&counts.decoded, "payloads decoded"), list(&counts.raw, "kept raw"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn base64(data: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        data.chunks(3)
+            .flat_map(|group| {
+                let bits = group.iter().enumerate().fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+                (0..4).map(move |i| if i <= group.len() { ALPHABET[(bits >> (18 - 6 * i) & 63) as usize] as char } else { '=' })
+            })
+            .collect()
+    }
+
+    fn decoded(payload: Option<Payload>) -> (String, Mesh) {
+        match payload {
+            Some(Payload::Mesh { version, mesh }) => (version, mesh),
+            other => panic!("expected a mesh, got {:?}", other.map(|p| p.format())),
+        }
+    }
+
+    /// A `version 2.00` mesh with one triangle.
+    fn binary_v2() -> Vec<u8> {
+        let mut data = b"version 2.00\n".to_vec();
+        data.extend(12u16.to_le_bytes());
+        data.extend([36, 12]);
+        data.extend(3u32.to_le_bytes());
+        data.extend(1u32.to_le_bytes());
+        for position in [[0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            for v in position.into_iter().chain([0.0, 0.0, 1.0]).chain([0.0; 3]) {
+                data.extend(v.to_le_bytes());
+            }
+        }
+        for index in [0u32, 1, 2] {
+            data.extend(index.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn reads_text_meshes() {
+        let face = "[2,0,0][0,0,1][0,0,0][0,2,0][0,0,1][0,0,0][0,0,2][0,0,1][0,0,0]";
+        let (version, mesh) = decoded(decode(format!("version 1.00\n1\n{}", face).as_bytes()));
+        assert_eq!(version, "1.00");
+        assert_eq!(mesh.positions, vec![[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        let (_, mesh) = decoded(decode(format!("version 1.01\n1\n{}", face).as_bytes()));
+        assert_eq!(mesh.positions[0], [2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn rejects_face_counts_that_overflow() {
+        let text = format!("{}\n[0,0,0]", usize::MAX / 2);
+        let error = read_text_mesh(text.as_bytes(), 1.0).err().unwrap();
+        assert!(error.to_string().contains("too large"));
+        assert!(matches!(decode(format!("version 1.01\n{}", text).as_bytes()), Some(Payload::Opaque { .. })));
+    }
+
+    #[test]
+    fn reads_binary_meshes() {
+        let (version, mesh) = decoded(decode(&binary_v2()));
+        assert_eq!(version, "2.00");
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(mesh.normals[2], [0.0, 0.0, 1.0]);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn keeps_truncated_and_out_of_range_meshes_opaque() {
+        let data = binary_v2();
+        assert!(matches!(decode(&data[..data.len() - 1]), Some(Payload::Opaque { format }) if format == "mesh 2.00"));
+        let mut data = binary_v2();
+        let last = data.len() - 4;
+        data[last..].copy_from_slice(&3u32.to_le_bytes());
+        assert!(matches!(decode(&data), Some(Payload::Opaque { .. })));
+    }
+
+    #[test]
+    fn reads_physics_hulls() {
+        let mut data = CSG_PHYSICS_MAGIC.to_vec();
+        data.extend(6u32.to_le_bytes());
+        assert!(matches!(decode(&data), Some(Payload::Physics { version: 6, hulls }) if hulls.is_empty()));
+        data.extend(3u32.to_le_bytes());
+        for v in [0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            data.extend(v.to_le_bytes());
+        }
+        data.extend(3u32.to_le_bytes());
+        for index in [0u32, 2, 1] {
+            data.extend(index.to_le_bytes());
+        }
+        let Some(Payload::Physics { hulls, .. }) = decode(&data) else { panic!("expected physics data") };
+        assert_eq!(hulls[0].indices, vec![0, 2, 1]);
+        data.extend(u32::MAX.to_le_bytes());
+        assert!(matches!(decode(&data), Some(Payload::Opaque { format }) if format == "CSGPHS 6"));
+    }
+
+    #[test]
+    fn strips_paths_from_property_names() {
+        let shared_strings = HashMap::new();
+        let export = MeshDataExport::new(&shared_strings);
+        let property = |name: &str, data: &[u8]| Property { name: name.to_string(), value: PropertyValue::BinaryString(base64(data)) };
+        let files = export.files("MeshPart", &[property("../../../../mesh", &binary_v2()), property("..\\csg", b"CSGMDL\x02\0\0\0")]);
+        let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["........mesh.obj", "..csg.bin"]);
+    }
+
+    #[test]
+    fn ignores_other_payloads() {
+        assert!(decode(b"not a mesh").is_none());
+        assert!(matches!(decode(b"CSGMDL\x02\0\0\0rest"), Some(Payload::Opaque { format }) if format == "CSGMDL 2"));
+    }
+}
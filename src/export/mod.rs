@@ -0,0 +1,216 @@
+pub mod animation;
+pub mod geometry;
+pub mod gltf;
+pub mod gui;
+pub mod images;
+pub mod lighting;
+pub mod map;
+pub mod mesh_data;
+pub mod obj;
+pub mod terrain;
+
+use crate::dom::Dom;
+use crate::path_policy::sanitize_segment;
+use crate::sink::OutputSink;
+use std::collections::HashSet;
+use std::path::Path;
+
+pub const EXPORTS_DIR: &str = "exports";
+const MAX_FILE_STEM_LENGTH: usize = 120;
+
+/// Conversions of the place into formats other tools open, written into `exports/` of the output.
+#[derive(Clone, Default)]
+pub struct ExportSettings {
+    /// Workspace parts as `exports/workspace.glb`.
+    pub geometry: bool,
+    /// Decoded smooth terrain in `exports/terrain/`: raw voxel arrays, a heightmap and a material summary.
+    pub terrain: bool,
+    /// Union and mesh part payloads decoded next to each `properties.json`; only used for the folder layout.
+    pub mesh_data: bool,
+    /// Images embedded in binary properties written next to each `properties.json`; only used for the folder layout.
+    pub images: bool,
+    /// Every KeyframeSequence as `exports/animations/<path>.json`.
+    pub animations: bool,
+    /// Also sample the animations into `.bvh` files.
+    pub animation_bvh: bool,
+    /// Every ScreenGui and SurfaceGui as an HTML preview in `exports/gui/`.
+    pub gui: bool,
+    /// Screen size of the GUI previews, [`gui::DEFAULT_RESOLUTION`] if unset.
+    pub gui_resolution: Option<(u32, u32)>,
+    /// Lighting and its effects as a preset in `exports/lighting.json`.
+    pub lighting: bool,
+    /// A top-down image of Workspace as `exports/map.png` with `exports/map.json`.
+    pub map: bool,
+    /// Longest side of the map in pixels, [`map::DEFAULT_SIZE`] if unset.
+    pub map_size: Option<u32>,
+    /// Area of the map in studs; all of Workspace if unset.
+    pub map_bounds: Option<map::MapBounds>,
+    pub map_color_by: map::MapColoring,
+}
+
+impl ExportSettings {
+    pub fn any(&self) -> bool {
+        self.geometry || self.terrain || self.animations || self.gui || self.lighting || self.map
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum GeometryFormat {
+    Glb,
+    Gltf,
+    Obj,
+}
+
+impl GeometryFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_string_lossy().to_lowercase().as_str() {
+            "glb" => Some(GeometryFormat::Glb),
+            "gltf" => Some(GeometryFormat::Gltf),
+            "obj" => Some(GeometryFormat::Obj),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            GeometryFormat::Glb => "glb",
+            GeometryFormat::Gltf => "gltf",
+            GeometryFormat::Obj => "obj",
+        }
+    }
+}
+
+/// The files of a geometry export named `<stem>.<ext>`: the scene itself, plus the `.bin` buffer
+/// of a `.gltf` or the `.mtl` materials of an `.obj`.
+pub fn geometry_files(scene: &geometry::Scene, format: GeometryFormat, stem: &str) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let main = format!("{}.{}", stem, format.extension());
+    Ok(match format {
+        GeometryFormat::Glb => vec![(main, gltf::to_glb(scene)?)],
+        GeometryFormat::Gltf => {
+            let bin_name = format!("{}.bin", stem);
+            let (document, bin) = gltf::to_gltf(scene, &bin_name)?;
+            vec![(main, document), (bin_name, bin)]
+        }
+        GeometryFormat::Obj => {
+            let mtl_name = format!("{}.mtl", stem);
+            let (obj, mtl) = obj::to_obj(scene, &mtl_name);
+            vec![(main, obj.into_bytes()), (mtl_name, mtl.into_bytes())]
+        }
+    })
+}
+
+/// The file `<property>.<extension>` written next to an instance's `properties.json`. Property names come
+/// from the place, so separators, `..` and anything else Windows rejects are stripped first.
+pub fn property_file_name(property: &str, extension: &str) -> String {
+    let mut stem = sanitize_segment(property);
+    if stem.len() > MAX_FILE_STEM_LENGTH {
+        let mut end = MAX_FILE_STEM_LENGTH;
+        while !stem.is_char_boundary(end) {
+            end -= 1;
+        }
+        stem.truncate(end);
+    }
+    if stem.is_empty() {
+        stem.push_str("property");
+    }
+    format!("{}.{}", stem, extension)
+}
+
+/// File name stems for exported instances, derived from their dotted paths and made unique.
+pub fn file_stems<'a>(paths: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut taken = HashSet::new();
+    paths
+        .map(|path| {
+            let mut base = sanitize_segment(path);
+            if base.len() > MAX_FILE_STEM_LENGTH {
+                let mut end = MAX_FILE_STEM_LENGTH;
+                while !base.is_char_boundary(end) {
+                    end -= 1;
+                }
+                base.truncate(end);
+            }
+            let mut stem = base.clone();
+            let mut n = 2;
+            while !taken.insert(stem.to_lowercase()) {
+                stem = format!("{}~{}", base, n);
+                n += 1;
+            }
+            stem
+        })
+        .collect()
+}
+
+/// Writes every enabled export and returns one summary line per export for the finished screen.
+pub fn write_exports(dom: &Dom, exports: &ExportSettings, sink: &dyn OutputSink) -> anyhow::Result<Vec<String>> {
+    let dir = Path::new(EXPORTS_DIR);
+    sink.create_dir(dir)?;
+    let mut summaries = Vec::new();
+
+    if exports.geometry {
+        if let Some(workspace) = dom.find_service("Workspace") {
+            let scene = geometry::build_scene(dom, workspace);
+            for (name, bytes) in geometry_files(&scene, GeometryFormat::Glb, "workspace")? {
+                sink.write_file(&dir.join(name), &bytes)?;
+            }
+            summaries.push(geometry::summary(&scene));
+        }
+    }
+
+    if exports.terrain {
+        match terrain::load(dom) {
+            Some(Ok(decoded)) => {
+                let terrain_dir = dir.join("terrain");
+                sink.create_dir(&terrain_dir)?;
+                for (name, bytes) in terrain::terrain_files(&decoded)? {
+                    sink.write_file(&terrain_dir.join(name), &bytes)?;
+                }
+                summaries.push(terrain::summary(&decoded));
+            }
+            Some(Err(e)) => summaries.push(format!("Terrain: could not decode SmoothGrid: {}", e)),
+            None => {}
+        }
+    }
+
+    if exports.animations {
+        let set = animation::collect(dom);
+        if !set.animations.is_empty() {
+            let animations_dir = dir.join("animations");
+            sink.create_dir(&animations_dir)?;
+            for (name, bytes) in animation::animation_files(&set, exports.animation_bvh)? {
+                sink.write_file(&animations_dir.join(name), &bytes)?;
+            }
+        }
+        summaries.push(animation::summary(&set));
+    }
+
+    if exports.gui {
+        let pages = gui::render_all(dom, exports.gui_resolution.unwrap_or(gui::DEFAULT_RESOLUTION));
+        if !pages.is_empty() {
+            let gui_dir = dir.join("gui");
+            sink.create_dir(&gui_dir)?;
+            for (name, bytes) in gui::gui_files(&pages) {
+                sink.write_file(&gui_dir.join(name), &bytes)?;
+            }
+        }
+        summaries.push(gui::summary(&pages));
+    }
+
+    if exports.lighting {
+        if let Some(preset) = lighting::collect(dom) {
+            sink.write_file(&dir.join("lighting.json"), &serde_json::to_vec_pretty(&preset)?)?;
+            summaries.push(lighting::summary(&preset));
+        }
+    }
+
+    if exports.map {
+        let size = exports.map_size.unwrap_or(map::DEFAULT_SIZE);
+        if let Some(map) = dom.find_service("Workspace").and_then(|workspace| map::render(dom, workspace, size, exports.map_bounds, exports.map_color_by)) {
+            for (name, bytes) in map::map_files(&map, "map")? {
+                sink.write_file(&dir.join(name), &bytes)?;
+            }
+            summaries.push(map::summary(&map));
+        }
+    }
+
+    Ok(summaries)
+}
@@ -0,0 +1,83 @@
+use crate::export::geometry::{Mesh, Scene, SceneNode, Transform};
+use std::fmt::Write as _;
+
+/// Wavefront OBJ has no hierarchy, so every part becomes an object named after its full path,
+/// with its vertices moved into world space.
+pub fn to_obj(scene: &Scene, mtl_name: &str) -> (String, String) {
+    let mut obj = String::new();
+    let _ = writeln!(obj, "# RBX Ripper geometry export\nmtllib {}", mtl_name);
+    let mut next_vertex = 1;
+    for root in &scene.roots {
+        write_node(&mut obj, scene, root, &Transform::IDENTITY, &mut next_vertex);
+    }
+
+    let mut mtl = String::new();
+    for material in &scene.materials {
+        let [r, g, b] = material.color;
+        let _ = writeln!(mtl, "newmtl {}\nKd {:.4} {:.4} {:.4}\nd {:.4}\nillum 1\n", material.name, r, g, b, 1.0 - material.transparency);
+    }
+    (obj, mtl)
+}
+
+fn write_node(out: &mut String, scene: &Scene, node: &SceneNode, parent: &Transform, next_vertex: &mut usize) {
+    let world = parent.then(&node.transform);
+    if let Some((mesh, material)) = node.mesh {
+        let mesh = &scene.meshes[mesh];
+        let _ = writeln!(out, "\no {}", node.path.replace(char::is_whitespace, "_"));
+        if node.placeholder {
+            let _ = writeln!(out, "# placeholder for {}", node.class);
+            for (property, asset) in &node.assets {
+                let _ = writeln!(out, "# {} rbxassetid://{}", property, asset);
+            }
+        }
+        let _ = writeln!(out, "usemtl {}", scene.materials[material].name);
+        for position in &mesh.positions {
+            let [x, y, z] = world.apply(position.map(f64::from));
+            let _ = writeln!(out, "v {:.4} {:.4} {:.4}", x, y, z);
+        }
+        for normal in &mesh.normals {
+            let [x, y, z] = world.rotate(normal.map(f64::from));
+            let _ = writeln!(out, "vn {:.4} {:.4} {:.4}", x, y, z);
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize + *next_vertex);
+            let _ = writeln!(out, "f {a}//{a} {b}//{b} {c}//{c}");
+        }
+        *next_vertex += mesh.positions.len();
+    }
+    for child in &node.children {
+        write_node(out, scene, child, &world, next_vertex);
+    }
+}
+
+/// Standalone meshes as OBJ objects in their own coordinates, e.g. decoded mesh payloads.
+/// `header` lines are written as comments at the top.
+pub fn meshes_to_obj(meshes: &[(String, &Mesh)], header: &[String]) -> String {
+    let mut out = String::new();
+    for line in header {
+        let _ = writeln!(out, "# {}", line);
+    }
+    let mut next_vertex = 1;
+    for (name, mesh) in meshes {
+        let _ = writeln!(out, "\no {}", name.replace(char::is_whitespace, "_"));
+        for [x, y, z] in &mesh.positions {
+            let _ = writeln!(out, "v {:.4} {:.4} {:.4}", x, y, z);
+        }
+        let has_normals = mesh.normals.len() == mesh.positions.len();
+        if has_normals {
+            for [x, y, z] in &mesh.normals {
+                let _ = writeln!(out, "vn {:.4} {:.4} {:.4}", x, y, z);
+            }
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(|i| i as usize + next_vertex);
+            if has_normals {
+                let _ = writeln!(out, "f {a}//{a} {b}//{b} {c}//{c}");
+            } else {
+                let _ = writeln!(out, "f {a} {b} {c}");
+            }
+        }
+        next_vertex += mesh.positions.len();
+    }
+    out
+}
@@ -0,0 +1,436 @@
+use crate::dom::Dom;
+use crate::properties::{decode_base64, PropertyValue};
+use anyhow::{bail, Context};
+use serde::Serialize;
+use std::io::Cursor;
+
+/// Edge length of a voxel in studs.
+pub const VOXEL_SIZE: f64 = 4.0;
+/// Voxels along each edge of a chunk.
+pub const CHUNK_SIZE: usize = 32;
+const CHUNK_VOLUME: usize = CHUNK_SIZE * CHUNK_SIZE * CHUNK_SIZE;
+
+/// Longest side of `heightmap.png`; wider terrain gets several voxel columns per pixel.
+const HEIGHTMAP_MAX_SIDE: usize = 4096;
+
+const GRID_VERSION: u8 = 1;
+const AIR: u8 = 0;
+const WATER: u8 = 1;
+
+/// Terrain materials by the id stored in the grid.
+pub const MATERIALS: &[&str] = &[
+    "Air", "Water", "Grass", "Slate", "Concrete", "Brick", "Sand", "WoodPlanks", "Rock", "Glacier", "Snow", "Sandstone",
+    "Mud", "Basalt", "Ground", "CrackedLava", "Asphalt", "Cobblestone", "Ice", "LeafyGrass", "Salt", "Limestone", "Pavement",
+];
+
+const FLAG_MATERIAL: u8 = 0x3F;
+const FLAG_OCCUPANCY: u8 = 0x40;
+const FLAG_RUN: u8 = 0x80;
+
+fn material_name(id: u8) -> String {
+    MATERIALS.get(id as usize).map(|name| name.to_string()).unwrap_or_else(|| format!("Material{}", id))
+}
+
+/// One 32³ block of voxels. Both arrays are indexed by [`voxel_index`]; occupancy runs from 0 (empty) to 255 (full).
+pub struct Chunk {
+    /// Position in chunks, so the first voxel sits at `position * CHUNK_SIZE` in voxel coordinates.
+    pub position: [i32; 3],
+    pub materials: Vec<u8>,
+    pub occupancy: Vec<u8>,
+}
+
+/// Voxels are stored column by column: Y varies fastest, then Z, then X.
+pub fn voxel_index(x: usize, y: usize, z: usize) -> usize {
+    (x * CHUNK_SIZE + z) * CHUNK_SIZE + y
+}
+
+pub struct Terrain {
+    /// Full name of the Terrain instance the grid was read from.
+    pub path: String,
+    pub chunks: Vec<Chunk>,
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> anyhow::Result<u8> {
+        let byte = *self.data.get(self.offset).with_context(|| format!("SmoothGrid ends early at byte {}", self.offset))?;
+        self.offset += 1;
+        Ok(byte)
+    }
+
+    /// A little-endian signed integer of 1, 2 or 4 bytes.
+    fn signed(&mut self, width: usize) -> anyhow::Result<i32> {
+        let mut value = 0u32;
+        for i in 0..width {
+            value |= (self.byte()? as u32) << (8 * i);
+        }
+        let unused = 32 - 8 * width as u32;
+        Ok(((value << unused) as i32) >> unused)
+    }
+
+    fn at_end(&self) -> bool {
+        self.offset >= self.data.len()
+    }
+}
+
+/// Decodes a `SmoothGrid` payload.
+///
+/// The grid starts with a version byte and the chunk size as a power of two, followed by chunks until the end of the
+/// data. A chunk starts with a byte holding the width of its X, Y and Z position deltas in two bits each (0 = 1 byte,
+/// 1 = 2 bytes, 2 = 4 bytes), then the deltas from the previous chunk's position. Its voxels follow as runs: a flag byte
+/// with the material id in the low six bits, an occupancy byte if bit 6 is set (otherwise the voxel is full, or empty for
+/// air) and a repeat count byte if bit 7 is set, the run covering `count + 1` voxels.
+pub fn decode(path: String, data: &[u8]) -> anyhow::Result<Terrain> {
+    let mut reader = Reader { data, offset: 0 };
+    let version = reader.byte()?;
+    let exponent = reader.byte()?;
+    if version != GRID_VERSION {
+        bail!("unsupported SmoothGrid version {}", version);
+    }
+    if 1usize.checked_shl(exponent as u32) != Some(CHUNK_SIZE) {
+        bail!("unsupported SmoothGrid chunk size 2^{}", exponent);
+    }
+
+    let mut chunks = Vec::new();
+    let mut position = [0i32; 3];
+    while !reader.at_end() {
+        let widths = reader.byte()?;
+        for (axis, coordinate) in position.iter_mut().enumerate() {
+            let width = match (widths >> (axis * 2)) & 0b11 {
+                0 => 1,
+                1 => 2,
+                2 => 4,
+                _ => bail!("invalid chunk position width at byte {}", reader.offset - 1),
+            };
+            *coordinate = coordinate.wrapping_add(reader.signed(width)?);
+        }
+
+        let mut materials = Vec::with_capacity(CHUNK_VOLUME);
+        let mut occupancy = Vec::with_capacity(CHUNK_VOLUME);
+        while materials.len() < CHUNK_VOLUME {
+            let flag = reader.byte()?;
+            let material = flag & FLAG_MATERIAL;
+            let amount = if flag & FLAG_OCCUPANCY != 0 {
+                reader.byte()?
+            } else if material == AIR {
+                0
+            } else {
+                u8::MAX
+            };
+            let count = if flag & FLAG_RUN != 0 { reader.byte()? as usize + 1 } else { 1 };
+            if materials.len() + count > CHUNK_VOLUME {
+                bail!("voxel run overflows chunk {:?} at byte {}", position, reader.offset);
+            }
+            materials.resize(materials.len() + count, material);
+            occupancy.resize(occupancy.len() + count, amount);
+        }
+        chunks.push(Chunk { position, materials, occupancy });
+    }
+    Ok(Terrain { path, chunks })
+}
+
+/// The Terrain object under Workspace and its decoded `SmoothGrid`, if the place has any terrain.
+pub fn load(dom: &Dom) -> Option<anyhow::Result<Terrain>> {
+    let workspace = dom.find_service("Workspace")?;
+    let id = dom.instances[workspace].children.iter().copied().find(|&c| dom.instances[c].class == "Terrain")?;
+    let Some(PropertyValue::BinaryString(grid)) = dom.instances[id].property("SmoothGrid") else {
+        return None;
+    };
+    if grid.is_empty() {
+        return None;
+    }
+    let path = dom.path(id);
+    Some(decode_base64(grid).context("SmoothGrid is not valid base64").and_then(|data| decode(path, &data)))
+}
+
+#[derive(Serialize)]
+pub struct MaterialSummary {
+    pub material: String,
+    pub id: u8,
+    /// Voxels containing any of the material.
+    pub voxels: usize,
+    /// Volume in cubic studs, counting partly filled voxels by their occupancy.
+    pub volume: f64,
+}
+
+#[derive(Serialize)]
+pub struct ChunkEntry {
+    pub position: [i32; 3],
+    /// Byte offset of the chunk in both `materials.bin` and `occupancy.bin`.
+    pub offset: usize,
+}
+
+#[derive(Serialize)]
+pub struct Heightmap {
+    pub file: String,
+    pub width: u32,
+    pub height: u32,
+    /// World X and Z in studs of the top-left pixel's corner; X grows to the right and Z downwards.
+    pub origin: [f64; 2],
+    pub studs_per_pixel: f64,
+    /// Surface heights in studs mapped to pixel values 1 and 65535; 0 marks columns without solid terrain.
+    pub min_height: f64,
+    pub max_height: f64,
+}
+
+/// `terrain.json`: how to read the raw voxel files, plus the material breakdown.
+#[derive(Serialize)]
+pub struct TerrainHeader {
+    pub source: String,
+    pub voxel_size: f64,
+    pub chunk_size: usize,
+    pub voxel_order: &'static str,
+    pub materials_file: String,
+    pub occupancy_file: String,
+    pub material_ids: Vec<String>,
+    /// Smallest and largest voxel coordinates holding anything but air.
+    pub bounds: Option<[[i64; 3]; 2]>,
+    pub materials: Vec<MaterialSummary>,
+    pub heightmap: Option<Heightmap>,
+    pub chunks: Vec<ChunkEntry>,
+}
+
+fn voxels(chunk: &Chunk) -> impl Iterator<Item = ([i64; 3], u8, u8)> + '_ {
+    let base = chunk.position.map(|c| c as i64 * CHUNK_SIZE as i64);
+    (0..CHUNK_SIZE).flat_map(move |x| {
+        (0..CHUNK_SIZE).flat_map(move |z| {
+            (0..CHUNK_SIZE).map(move |y| {
+                let i = voxel_index(x, y, z);
+                ([base[0] + x as i64, base[1] + y as i64, base[2] + z as i64], chunk.materials[i], chunk.occupancy[i])
+            })
+        })
+    })
+}
+
+fn material_summary(terrain: &Terrain) -> Vec<MaterialSummary> {
+    let mut voxels = [0usize; 64];
+    let mut fill = [0u64; 64];
+    for chunk in &terrain.chunks {
+        for (&material, &amount) in chunk.materials.iter().zip(&chunk.occupancy) {
+            if material != AIR && amount > 0 {
+                voxels[material as usize] += 1;
+                fill[material as usize] += amount as u64;
+            }
+        }
+    }
+    let voxel_volume = VOXEL_SIZE.powi(3);
+    let mut summary: Vec<MaterialSummary> = (0..64u8)
+        .filter(|&id| voxels[id as usize] > 0)
+        .map(|id| MaterialSummary {
+            material: material_name(id),
+            id,
+            voxels: voxels[id as usize],
+            volume: fill[id as usize] as f64 / u8::MAX as f64 * voxel_volume,
+        })
+        .collect();
+    summary.sort_by(|a, b| b.voxels.cmp(&a.voxels).then(a.id.cmp(&b.id)));
+    summary
+}
+
+fn bounds(terrain: &Terrain) -> Option<[[i64; 3]; 2]> {
+    let mut bounds: Option<[[i64; 3]; 2]> = None;
+    for chunk in &terrain.chunks {
+        for (position, material, amount) in voxels(chunk) {
+            if material == AIR || amount == 0 {
+                continue;
+            }
+            let [min, max] = bounds.get_or_insert([position, position]);
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+    }
+    bounds
+}
+
+/// A 16-bit grayscale top-down image of the highest solid surface of every voxel column; water is left out.
+/// Terrain more than [`HEIGHTMAP_MAX_SIDE`] voxels across is scaled down, each pixel taking the highest of its columns.
+fn heightmap(terrain: &Terrain, bounds: [[i64; 3]; 2], file: &str) -> anyhow::Result<Option<(Heightmap, Vec<u8>)>> {
+    let [min, max] = bounds;
+    let columns = [max[0] - min[0] + 1, max[2] - min[2] + 1].map(|c| c as u64);
+    let step = columns[0].max(columns[1]).div_ceil(HEIGHTMAP_MAX_SIDE as u64);
+    let [width, depth] = columns.map(|c| c.div_ceil(step) as usize);
+    let step = step as i64;
+    let mut surface = vec![f64::NEG_INFINITY; width * depth];
+    for chunk in &terrain.chunks {
+        for (position, material, amount) in voxels(chunk) {
+            if material == AIR || material == WATER || amount == 0 {
+                continue;
+            }
+            let top = (position[1] as f64 + amount as f64 / u8::MAX as f64) * VOXEL_SIZE;
+            let cell = &mut surface[((position[2] - min[2]) / step) as usize * width + ((position[0] - min[0]) / step) as usize];
+            *cell = cell.max(top);
+        }
+    }
+
+    let solid = surface.iter().copied().filter(|h| h.is_finite());
+    let (low, high) = solid.fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), h| (low.min(h), high.max(h)));
+    if !low.is_finite() {
+        return Ok(None);
+    }
+    let range = (high - low).max(f64::EPSILON);
+    let pixels: Vec<u16> = surface.iter()
+        .map(|&h| if h.is_finite() { 1 + ((h - low) / range * (u16::MAX - 1) as f64).round() as u16 } else { 0 })
+        .collect();
+    let image = image::ImageBuffer::<image::Luma<u16>, _>::from_raw(width as u32, depth as u32, pixels)
+        .context("heightmap size does not match its pixels")?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)?;
+    let header = Heightmap {
+        file: file.to_string(),
+        width: width as u32,
+        height: depth as u32,
+        origin: [min[0] as f64 * VOXEL_SIZE, min[2] as f64 * VOXEL_SIZE],
+        studs_per_pixel: VOXEL_SIZE * step as f64,
+        min_height: low,
+        max_height: high,
+    };
+    Ok(Some((header, png)))
+}
+
+/// `terrain.json`, `materials.bin`, `occupancy.bin` and `heightmap.png`, named for a folder of their own.
+pub fn terrain_files(terrain: &Terrain) -> anyhow::Result<Vec<(String, Vec<u8>)>> {
+    let mut materials = Vec::with_capacity(terrain.chunks.len() * CHUNK_VOLUME);
+    let mut occupancy = Vec::with_capacity(terrain.chunks.len() * CHUNK_VOLUME);
+    let mut chunks = Vec::with_capacity(terrain.chunks.len());
+    for chunk in &terrain.chunks {
+        chunks.push(ChunkEntry { position: chunk.position, offset: materials.len() });
+        materials.extend_from_slice(&chunk.materials);
+        occupancy.extend_from_slice(&chunk.occupancy);
+    }
+
+    let bounds = bounds(terrain);
+    let mut files = Vec::new();
+    let mut heightmap_header = None;
+    if let Some(bounds) = bounds {
+        if let Some((header, png)) = heightmap(terrain, bounds, "heightmap.png")? {
+            files.push((header.file.clone(), png));
+            heightmap_header = Some(header);
+        }
+    }
+
+    let header = TerrainHeader {
+        source: terrain.path.clone(),
+        voxel_size: VOXEL_SIZE,
+        chunk_size: CHUNK_SIZE,
+        voxel_order: "y fastest, then z, then x; one chunk after another",
+        materials_file: "materials.bin".to_string(),
+        occupancy_file: "occupancy.bin".to_string(),
+        material_ids: MATERIALS.iter().map(|m| m.to_string()).collect(),
+        bounds,
+        materials: material_summary(terrain),
+        heightmap: heightmap_header,
+        chunks,
+    };
+    files.push(("terrain.json".to_string(), serde_json::to_vec_pretty(&header)?));
+    files.push((header.materials_file, materials));
+    files.push((header.occupancy_file, occupancy));
+    Ok(files)
+}
+
+pub fn summary(terrain: &Terrain) -> String {
+    let materials = material_summary(terrain);
+    let solid: usize = materials.iter().filter(|m| m.id != WATER).map(|m| m.voxels).sum();
+    let water: usize = materials.iter().filter(|m| m.id == WATER).map(|m| m.voxels).sum();
+    let top: Vec<String> = materials.iter().take(3).map(|m| format!("{} {}", m.material, m.voxels)).collect();
+    format!(
+        "Terrain: {} chunks, {} solid and {} water voxels in {} materials{}",
+        terrain.chunks.len(),
+        solid,
+        water,
+        materials.len(),
+        if top.is_empty() { String::new() } else { format!(" ({})", top.join(", ")) },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A chunk of grass filled up to `height` voxels with the top one half full.
+    fn grass(position: [i32; 3], height: usize) -> Chunk {
+        let mut chunk = Chunk { position, materials: vec![AIR; CHUNK_VOLUME], occupancy: vec![0; CHUNK_VOLUME] };
+        for x in 0..CHUNK_SIZE {
+            for z in 0..CHUNK_SIZE {
+                for y in 0..height {
+                    chunk.materials[voxel_index(x, y, z)] = 2;
+                    chunk.occupancy[voxel_index(x, y, z)] = if y + 1 == height { 128 } else { u8::MAX };
+                }
+            }
+        }
+        chunk
+    }
+
+    /// The chunks as a `SmoothGrid` payload, one run per voxel and four-byte position deltas.
+    fn encode(chunks: &[Chunk]) -> Vec<u8> {
+        let mut data = vec![GRID_VERSION, 5];
+        let mut previous = [0i32; 3];
+        for chunk in chunks {
+            data.push(0b10_10_10);
+            for (coordinate, previous) in chunk.position.iter().zip(previous) {
+                data.extend((coordinate - previous).to_le_bytes());
+            }
+            previous = chunk.position;
+            for (&material, &amount) in chunk.materials.iter().zip(&chunk.occupancy) {
+                data.extend([material | FLAG_OCCUPANCY, amount]);
+            }
+        }
+        data
+    }
+
+    #[test]
+    fn decodes_runs_and_positions() {
+        let mut data = vec![GRID_VERSION, 5, 0b00_01_00, 0xFF, 0x02, 0x01, 0x03];
+        // 128 runs of 256 voxels: full rock, then half-filled sand, then air.
+        data.extend([8 | FLAG_RUN, 0xFF]);
+        data.extend([6 | FLAG_OCCUPANCY | FLAG_RUN, 100, 0xFF]);
+        for _ in 2..128 {
+            data.extend([AIR | FLAG_RUN, 0xFF]);
+        }
+        let terrain = decode("Workspace.Terrain".to_string(), &data).unwrap();
+        assert_eq!(terrain.chunks.len(), 1);
+        let chunk = &terrain.chunks[0];
+        assert_eq!(chunk.position, [-1, 258, 3]);
+        assert_eq!((chunk.materials[255], chunk.occupancy[255]), (8, u8::MAX));
+        assert_eq!((chunk.materials[256], chunk.occupancy[256]), (6, 100));
+        assert_eq!((chunk.materials[512], chunk.occupancy[512]), (AIR, 0));
+    }
+
+    #[test]
+    fn rejects_broken_grids() {
+        assert!(decode(String::new(), &[2, 5]).is_err());
+        assert!(decode(String::new(), &[GRID_VERSION, 4]).is_err());
+        assert!(decode(String::new(), &[GRID_VERSION, 5, 0b11, 0]).is_err());
+        assert!(decode(String::new(), &[GRID_VERSION, 5, 0, 0, 0, 0, 8]).is_err());
+        let mut data = vec![GRID_VERSION, 5, 0, 0, 0, 0];
+        data.extend(std::iter::repeat_n([8 | FLAG_RUN, 0xFE], 129).flatten());
+        assert!(decode(String::new(), &data).err().unwrap().to_string().contains("overflows"));
+    }
+
+    #[test]
+    fn round_trips_and_maps_heights() {
+        let terrain = decode(String::new(), &encode(&[grass([0, 0, 0], 2), grass([1, 0, 0], 4)])).unwrap();
+        assert_eq!(bounds(&terrain), Some([[0, 0, 0], [63, 3, 31]]));
+        let (header, png) = heightmap(&terrain, bounds(&terrain).unwrap(), "heightmap.png").unwrap().unwrap();
+        assert_eq!((header.width, header.height, header.studs_per_pixel), (64, 32, VOXEL_SIZE));
+        assert!((header.min_height - (1.0 + 128.0 / 255.0) * VOXEL_SIZE).abs() < 1e-9);
+        assert!((header.max_height - (3.0 + 128.0 / 255.0) * VOXEL_SIZE).abs() < 1e-9);
+        let image = image::load_from_memory(&png).unwrap().into_luma16();
+        assert_eq!((image.get_pixel(0, 0).0[0], image.get_pixel(63, 31).0[0]), (1, u16::MAX));
+    }
+
+    #[test]
+    fn scales_down_wide_heightmaps() {
+        let terrain = Terrain { path: String::new(), chunks: vec![grass([-5000, 0, 0], 1), grass([5000, 0, 0], 1)] };
+        let (header, _) = heightmap(&terrain, bounds(&terrain).unwrap(), "heightmap.png").unwrap().unwrap();
+        assert!(header.width as usize <= HEIGHTMAP_MAX_SIDE);
+        assert_eq!(header.height, 1);
+        assert_eq!(header.studs_per_pixel, VOXEL_SIZE * 79.0);
+        assert_eq!(header.origin, [-5000.0 * 32.0 * VOXEL_SIZE, 0.0]);
+    }
+}
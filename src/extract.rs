@@ -0,0 +1,494 @@
+use crate::analysis::dedup::{SharedSources, SCRIPT_REF_FILE, SOURCES_DIR};
+use crate::analysis::packages::{SharedPackages, PACKAGES_DIR, PACKAGE_REF_FILE};
+use crate::analysis::stats::PlaceStats;
+use crate::analysis::{write_reports, ReportResults, ReportSettings};
+use crate::dom::{read_shared_strings, Dom};
+use crate::dump::{write_json, write_ndjson, JSON_DUMP_FILE, NDJSON_DUMP_FILE};
+use crate::export::images::ImageExport;
+use crate::export::mesh_data::MeshDataExport;
+use crate::export::{write_exports, ExportSettings};
+use crate::normalize::{NormalizeSettings, Normalizer, NORMALIZATION_FILE};
+use crate::path_policy::{PathPolicy, PATH_MAP_FILE};
+use crate::properties::read_typed_properties;
+use crate::sqlite::{write_database, SQLITE_DUMP_FILE};
+use crate::sink::{open_sink, path_policy_for, OutputSink};
+use rayon::prelude::*;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+pub type ProgressFn<'a> = dyn Fn(f32, String) + Sync + 'a;
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// One folder per instance with `properties.json` and `script.lua`
+    Folders,
+    /// The whole tree as a single nested `place.json`
+    Json,
+    /// One instance per line in `place.ndjson`
+    Ndjson,
+    /// `instances`, `properties` and `scripts` tables in `place.sqlite`
+    Sqlite,
+}
+
+impl OutputFormat {
+    pub fn dump_file(self) -> Option<&'static str> {
+        match self {
+            OutputFormat::Folders => None,
+            OutputFormat::Json => Some(JSON_DUMP_FILE),
+            OutputFormat::Ndjson => Some(NDJSON_DUMP_FILE),
+            OutputFormat::Sqlite => Some(SQLITE_DUMP_FILE),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ExtractionSettings {
+    pub exclude_workspace: bool,
+    pub exclude_scripts: bool,
+    pub exclude_classes: Vec<String>,
+    pub max_path_length: usize,
+    pub format: OutputFormat,
+    /// Write sources used by several scripts once under `sources/` and point to them from `script.ref`.
+    pub dedup_scripts: bool,
+    /// Write every package once under `packages/` and point to it from `package.ref` in each copy.
+    pub dedup_packages: bool,
+    /// Rewrites of `script.lua` files; only used for the folder layout.
+    pub normalize: NormalizeSettings,
+    pub reports: ReportSettings,
+    pub exports: ExportSettings,
+}
+
+#[derive(Default)]
+pub struct ExtractionSummary {
+    pub objects: usize,
+    pub reports: Vec<String>,
+    pub stats: Option<PlaceStats>,
+}
+
+impl ExtractionSummary {
+    pub fn message(&self) -> String {
+        let mut lines = vec![format!("{} objects", self.objects)];
+        lines.extend(self.reports.iter().cloned());
+        lines.join("\n")
+    }
+}
+
+#[derive(Clone, PartialEq, serde::Serialize)]
+#[serde(tag = "kind", content = "class", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    Class(String),
+    Script(String),
+    Workspace,
+}
+
+impl fmt::Display for ExclusionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExclusionReason::Class(class) => write!(f, "class {} is excluded", class),
+            ExclusionReason::Script(class) => write!(f, "scripts are excluded ({})", class),
+            ExclusionReason::Workspace => write!(f, "Workspace is excluded"),
+        }
+    }
+}
+
+pub struct ItemData {
+    pub class_name: String,
+    pub name: String,
+    pub properties: serde_json::Map<String, serde_json::Value>,
+    pub source: Option<String>,
+}
+
+pub fn top_level_items<'a, 'input>(doc: &'a roxmltree::Document<'input>) -> Vec<roxmltree::Node<'a, 'input>> {
+    let mut all_top_items: Vec<_> = doc.root().children().filter(|n| n.has_tag_name("Item")).collect();
+    if let Some(r) = doc.root().children().find(|n| n.has_tag_name("roblox")) {
+        all_top_items.extend(r.children().filter(|n| n.has_tag_name("Item")));
+    }
+    all_top_items
+}
+
+pub fn child_items<'a, 'input>(node: roxmltree::Node<'a, 'input>) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(|n| n.has_tag_name("Item"))
+}
+
+pub fn process_file_with_progress(input: &Path, output: &Path, settings: &ExtractionSettings, on_progress: &ProgressFn) -> anyhow::Result<ExtractionSummary> {
+    let text = fs::read_to_string(input)?;
+    let doc = roxmltree::Document::parse(&text)?;
+    let all_top_items = top_level_items(&doc);
+
+    let mut total_items = 0;
+    for node in &all_top_items {
+        total_items += count_items_recursive(*node, settings);
+    }
+
+    if total_items == 0 {
+        return Ok(ExtractionSummary::default());
+    }
+
+    let sink = open_sink(output)?;
+    let dom;
+    let mut mesh_data_summary = None;
+    let mut images_summary = None;
+    let mut package_warnings = Vec::new();
+
+    if let Some(file_name) = settings.format.dump_file() {
+        let built = Dom::build(&doc, settings);
+        sink.write_file(Path::new(file_name), &render_dump(&built, settings.format)?)?;
+        on_progress(1.0, format!("{} / {}", total_items, total_items));
+        dom = Some(built);
+    } else {
+        let current_count = Arc::new(AtomicUsize::new(0));
+        let policy = path_policy_for(output, settings.max_path_length);
+        let built = (settings.dedup_scripts || settings.dedup_packages).then(|| Dom::build(&doc, settings));
+        let shared = match &built {
+            Some(built) if settings.dedup_scripts => {
+                let shared = SharedSources::collect(built);
+                if !shared.is_empty() {
+                    sink.create_dir(Path::new(SOURCES_DIR))?;
+                }
+                Some(shared)
+            }
+            _ => None,
+        };
+        let packages = match &built {
+            Some(built) if settings.dedup_packages => {
+                let packages = SharedPackages::collect(built, &policy);
+                if !packages.is_empty() {
+                    sink.create_dir(Path::new(PACKAGES_DIR))?;
+                }
+                // The packages report lists the same warnings.
+                if !settings.reports.packages {
+                    package_warnings.extend(packages.warnings().iter().map(|w| format!("Warning: package {}", w)));
+                }
+                Some(packages)
+            }
+            _ => None,
+        };
+        dom = built;
+
+        let normalizer = settings.normalize.is_enabled().then(|| Normalizer::new(settings.normalize.clone()));
+        let scripts = ScriptOutput { shared: shared.as_ref(), normalizer: normalizer.as_ref() };
+        let shared_strings = if settings.exports.mesh_data || settings.exports.images { read_shared_strings(&doc) } else { Default::default() };
+        let meshes = settings.exports.mesh_data.then(|| MeshDataExport::new(&shared_strings));
+        let images = settings.exports.images.then(|| ImageExport::new(&shared_strings));
+        let instances = InstanceOutput { meshes: meshes.as_ref(), images: images.as_ref(), packages: packages.as_ref() };
+
+        allocate_items(all_top_items, Path::new(""), settings, &policy).into_par_iter().try_for_each(|(node, item, dir)| {
+            process_item_recursive_parallel(node, item, dir, &current_count, total_items, on_progress, settings, &policy, &scripts, &instances, sink.as_ref())
+        })?;
+        mesh_data_summary = meshes.map(|meshes| meshes.summary());
+        images_summary = images.map(|images| images.summary());
+
+        if let Some(normalizer) = normalizer {
+            sink.write_file(Path::new(NORMALIZATION_FILE), &serde_json::to_vec_pretty(&normalizer.into_manifest())?)?;
+        }
+
+        let mappings = policy.into_mappings();
+        if !mappings.is_empty() {
+            sink.write_file(Path::new(PATH_MAP_FILE), &serde_json::to_vec_pretty(&mappings)?)?;
+        }
+    }
+
+    let mut results = ReportResults::default();
+    if settings.reports.any() || settings.exports.any() {
+        let dom = dom.unwrap_or_else(|| Dom::build(&doc, settings));
+        if settings.reports.any() {
+            results = write_reports(&dom, &settings.reports, sink.as_ref())?;
+        }
+        if settings.exports.any() {
+            results.summaries.extend(write_exports(&dom, &settings.exports, sink.as_ref())?);
+        }
+    }
+
+    results.summaries.extend(mesh_data_summary);
+    results.summaries.extend(images_summary);
+    results.summaries.extend(package_warnings);
+
+    sink.finish()?;
+    Ok(ExtractionSummary { objects: total_items, reports: results.summaries, stats: results.stats })
+}
+
+pub fn render_dump(dom: &Dom, format: OutputFormat) -> anyhow::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        OutputFormat::Json => write_json(dom, &mut bytes)?,
+        OutputFormat::Ndjson => write_ndjson(dom, &mut bytes)?,
+        OutputFormat::Sqlite => return write_database(dom),
+        OutputFormat::Folders => {}
+    }
+    Ok(bytes)
+}
+
+pub fn count_items_recursive(node: roxmltree::Node, settings: &ExtractionSettings) -> usize {
+    if should_exclude_node(node, settings) {
+        return 0;
+    }
+
+    let mut count = 1;
+    for child in child_items(node) {
+        count += count_items_recursive(child, settings);
+    }
+    count
+}
+
+pub fn should_exclude_node(node: roxmltree::Node, settings: &ExtractionSettings) -> bool {
+    exclusion_reason(node, settings).is_some()
+}
+
+pub fn exclusion_reason(node: roxmltree::Node, settings: &ExtractionSettings) -> Option<ExclusionReason> {
+    let class_name = node.attribute("class").unwrap_or("Unknown");
+
+    if settings.exclude_classes.contains(&class_name.to_lowercase()) {
+        return Some(ExclusionReason::Class(class_name.to_string()));
+    }
+
+    if settings.exclude_scripts && (class_name == "Script" || class_name == "LocalScript" || class_name == "ModuleScript") {
+        return Some(ExclusionReason::Script(class_name.to_string()));
+    }
+
+    if settings.exclude_workspace {
+        if let Some(props_node) = node.children().find(|n| n.has_tag_name("Properties")) {
+            for prop in props_node.children() {
+                if prop.attribute("name") == Some("Name") && prop.text() == Some("Workspace") {
+                    return Some(ExclusionReason::Workspace);
+                }
+            }
+        }
+    }
+
+    None
+}
+
+pub fn read_item(node: roxmltree::Node) -> ItemData {
+    let class_name = node.attribute("class").unwrap_or("Unknown");
+    let mut name = class_name.to_string();
+    let mut source = None;
+    let mut properties = serde_json::Map::new();
+
+    properties.insert("ClassName".to_string(), serde_json::Value::String(class_name.to_string()));
+
+    if let Some(props_node) = node.children().find(|n| n.has_tag_name("Properties")) {
+        for prop in props_node.children() {
+            if !prop.is_element() { continue; }
+            let prop_name = prop.attribute("name").unwrap_or("Unknown");
+            if prop_name == "Source" {
+                source = prop.text().map(|s| s.to_string());
+                continue;
+            }
+            if prop_name == "Name" {
+                if let Some(text) = prop.text() {
+                    name = text.to_string();
+                }
+            }
+            if let Some(text) = prop.text() {
+                properties.insert(prop_name.to_string(), serde_json::Value::String(text.to_string()));
+            }
+        }
+    }
+
+    ItemData {
+        class_name: class_name.to_string(),
+        name,
+        properties,
+        source,
+    }
+}
+
+/// How script sources are written in the folder layout.
+pub struct ScriptOutput<'a> {
+    pub shared: Option<&'a SharedSources>,
+    pub normalizer: Option<&'a Normalizer>,
+}
+
+impl ScriptOutput<'_> {
+    /// The contents of the script file at `file`, normalized if that is enabled.
+    pub fn render(&self, file: &Path, source: &str) -> String {
+        match self.normalizer {
+            Some(normalizer) => normalizer.apply(file, source),
+            None => source.to_string(),
+        }
+    }
+}
+
+/// Files written next to `properties.json` besides scripts, and where package contents go.
+pub struct InstanceOutput<'a> {
+    pub meshes: Option<&'a MeshDataExport<'a>>,
+    pub images: Option<&'a ImageExport<'a>>,
+    pub packages: Option<&'a SharedPackages>,
+}
+
+/// `properties.json`, the script and any decoded payloads of one instance in `dir`.
+fn write_item_files(node: roxmltree::Node, item: &ItemData, dir: &Path, scripts: &ScriptOutput, instances: &InstanceOutput, sink: &dyn OutputSink) -> anyhow::Result<()> {
+    sink.create_dir(dir)?;
+    sink.write_file(&dir.join("properties.json"), &serde_json::to_vec_pretty(&item.properties)?)?;
+
+    if let Some(source) = &item.source {
+        match scripts.shared.and_then(|shared| shared.claim(source)) {
+            Some((path, first)) => {
+                if first {
+                    sink.write_file(path, scripts.render(path, source).as_bytes())?;
+                }
+                let reference = path.to_string_lossy().replace('\\', "/");
+                sink.write_file(&dir.join(SCRIPT_REF_FILE), reference.as_bytes())?;
+            }
+            None => {
+                let file = dir.join("script.lua");
+                sink.write_file(&file, scripts.render(&file, source).as_bytes())?;
+            }
+        }
+    }
+
+    for (name, bytes) in payload_files(node, &item.class_name, instances) {
+        sink.write_file(&dir.join(name), &bytes)?;
+    }
+    Ok(())
+}
+
+/// The decoded mesh payloads and embedded images of one instance, named for its folder.
+pub fn payload_files(node: roxmltree::Node, class_name: &str, instances: &InstanceOutput) -> Vec<(String, Vec<u8>)> {
+    if instances.meshes.is_none() && instances.images.is_none() {
+        return Vec::new();
+    }
+    let properties = read_typed_properties(node);
+    let meshes = instances.meshes.map(|meshes| meshes.files(class_name, &properties)).unwrap_or_default();
+    let images = instances.images.map(|images| images.files(&properties)).unwrap_or_default();
+    meshes.into_iter().chain(images).collect()
+}
+
+/// Reports progress when the count passes a multiple of 20 on its way from `before`, or reaches the total.
+fn report_progress(before: usize, count: usize, total: usize, on_progress: &ProgressFn) {
+    if count / 20 > before / 20 || count == total {
+        on_progress(count as f32 / total as f32, format!("{} / {}", count, total));
+    }
+}
+
+/// The items among `nodes` that aren't filtered out, with their folders under `parent`. Siblings are named
+/// in document order before any of them is processed, so collisions resolve the same way on every run.
+fn allocate_items<'a, 'input>(
+    nodes: impl IntoIterator<Item = roxmltree::Node<'a, 'input>>,
+    parent: &Path,
+    settings: &ExtractionSettings,
+    policy: &PathPolicy
+) -> Vec<(roxmltree::Node<'a, 'input>, ItemData, PathBuf)> {
+    nodes.into_iter()
+        .filter(|node| !should_exclude_node(*node, settings))
+        .map(|node| {
+            let item = read_item(node);
+            let dir = policy.allocate(parent, &item.name, &item.class_name);
+            (node, item, dir)
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_item_recursive_parallel(
+    node: roxmltree::Node,
+    item: ItemData,
+    mut target_dir: PathBuf,
+    current: &Arc<AtomicUsize>,
+    total: usize,
+    on_progress: &ProgressFn,
+    settings: &ExtractionSettings,
+    policy: &PathPolicy,
+    scripts: &ScriptOutput,
+    instances: &InstanceOutput,
+    sink: &dyn OutputSink
+) -> anyhow::Result<()> {
+    let count = current.fetch_add(1, Ordering::SeqCst) + 1;
+    report_progress(count - 1, count, total, on_progress);
+
+    write_item_files(node, &item, &target_dir, scripts, instances, sink)?;
+
+    let package = instances.packages.zip(node.attribute("referent")).and_then(|(packages, referent)| packages.claim(referent));
+    if let Some((folder, writer)) = package {
+        let reference = folder.to_string_lossy().replace('\\', "/");
+        sink.write_file(&target_dir.join(PACKAGE_REF_FILE), reference.as_bytes())?;
+        if !writer {
+            let skipped = count_items_recursive(node, settings) - 1;
+            let count = current.fetch_add(skipped, Ordering::SeqCst) + skipped;
+            report_progress(count - skipped, count, total, on_progress);
+            return Ok(());
+        }
+        // Every copy keeps its own root files; the children of the first one go under packages/.
+        sink.create_dir(folder)?;
+        target_dir = folder.to_path_buf();
+    }
+
+    allocate_items(child_items(node), &target_dir, settings, policy).into_par_iter().try_for_each(|(child, item, dir)| {
+        process_item_recursive_parallel(child, item, dir, current, total, on_progress, settings, policy, scripts, instances, sink)
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub fn settings() -> ExtractionSettings {
+        ExtractionSettings {
+            exclude_workspace: false,
+            exclude_scripts: false,
+            exclude_classes: Vec::new(),
+            max_path_length: crate::path_policy::DEFAULT_MAX_PATH_LENGTH,
+            format: OutputFormat::Folders,
+            dedup_scripts: false,
+            dedup_packages: false,
+            normalize: NormalizeSettings::default(),
+            reports: ReportSettings::default(),
+            exports: ExportSettings::default(),
+        }
+    }
+
+    /// Extracts `xml` into a fresh folder under the temp directory and returns that folder.
+    pub fn extract(name: &str, xml: &str, settings: &ExtractionSettings, on_progress: &ProgressFn) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("rbx_ripper_test_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("place.rbxlx");
+        fs::write(&input, xml).unwrap();
+        let output = dir.join("out");
+        process_file_with_progress(&input, &output, settings, on_progress).unwrap();
+        output
+    }
+
+    #[test]
+    fn same_named_siblings_follow_document_order() {
+        let parts: String = (0..64)
+            .map(|i| format!(r#"<Item class="Part" referent="P{i}"><Properties><string name="Name">Part</string><string name="Tag">{i}</string></Properties></Item>"#))
+            .collect();
+        let xml = format!(r#"<roblox version="4"><Item class="Workspace" referent="W"><Properties><string name="Name">Workspace</string></Properties>{parts}</Item></roblox>"#);
+        // Enough threads for siblings to be picked up out of order even on a single core.
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(8).build().unwrap();
+        for run in 0..3 {
+            let output = pool.install(|| extract(&format!("siblings{}", run), &xml, &settings(), &|_, _| {}));
+            for i in 0..64 {
+                let folder = if i == 0 { "Part".to_string() } else { format!("Part ({})", i) };
+                let properties = fs::read_to_string(output.join("Workspace").join(folder).join("properties.json")).unwrap();
+                let properties: serde_json::Value = serde_json::from_str(&properties).unwrap();
+                assert_eq!(properties["Tag"], i.to_string());
+            }
+            let _ = fs::remove_dir_all(output.parent().unwrap());
+        }
+    }
+
+    #[test]
+    fn skipped_package_copies_still_finish_the_progress() {
+        let children: String = (0..25).map(|i| format!(r#"<Item class="Part" referent="{{id}}{i}"><Properties><string name="Name">Part</string></Properties></Item>"#)).collect();
+        let copy = |id: &str| {
+            format!(
+                r#"<Item class="Model" referent="{id}"><Properties><string name="Name">Lamp</string></Properties><Item class="PackageLink" referent="{id}l"><Properties><string name="Name">PackageLink</string><Content name="PackageIdSerialize"><url>rbxassetid://1</url></Content></Properties></Item>{}</Item>"#,
+                children.replace("{id}", id)
+            )
+        };
+        let xml = format!(r#"<roblox version="4">{}{}{}</roblox>"#, copy("A"), copy("B"), copy("C"));
+        let last = std::sync::Mutex::new(String::new());
+        let settings = ExtractionSettings { dedup_packages: true, ..settings() };
+        let output = extract("progress", &xml, &settings, &|_, message| *last.lock().unwrap() = message);
+        assert_eq!(*last.lock().unwrap(), "81 / 81");
+        assert!(output.join("packages/Lamp_1 [Model]/Part/properties.json").exists());
+        let _ = fs::remove_dir_all(output.parent().unwrap());
+    }
+}
@@ -0,0 +1,468 @@
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TokenKind {
+    Name,
+    Keyword,
+    Number,
+    String,
+    /// A backtick string; `{}` expressions inside it are kept as part of the token text.
+    InterpolatedString,
+    Symbol,
+    Eof,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub offset: usize,
+    pub line: usize,
+    /// 1-based, counted in characters.
+    pub column: usize,
+}
+
+/// A problem in the source with the position it was found at.
+#[derive(Clone, Debug, Serialize)]
+pub struct SyntaxError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+// Longest first so `...` wins over `..` and `//=` over `//`.
+const SYMBOLS: &[&str] = &[
+    "...", "//=", "..=", "==", "~=", "<=", ">=", "::", "->", "..", "+=", "-=", "*=", "/=", "%=",
+    "^=", "//", "+", "-", "*", "/", "%", "^", "#", "&", "|", "<", ">", "=", "(", ")", "{", "}",
+    "[", "]", ";", ":", ",", ".", "?", "@",
+];
+
+impl<'a> Token<'a> {
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        self.kind == TokenKind::Symbol && self.text == symbol
+    }
+
+    pub fn is_keyword(&self, keyword: &str) -> bool {
+        self.kind == TokenKind::Keyword && self.text == keyword
+    }
+
+    pub fn is_name(&self, name: &str) -> bool {
+        self.kind == TokenKind::Name && self.text == name
+    }
+
+    /// The decoded contents of a quoted or long-bracket string.
+    pub fn string_value(&self) -> Option<String> {
+        if self.kind != TokenKind::String {
+            return None;
+        }
+        let text = self.text;
+        if let Some(rest) = text.strip_prefix('[') {
+            let level = rest.bytes().take_while(|b| *b == b'=').count();
+            let body = text.get(level + 2..)?;
+            // An unfinished long string runs to the end of the source without a closing bracket.
+            let closing = format!("]{}]", "=".repeat(level));
+            let body = body.strip_suffix(closing.as_str()).unwrap_or(body);
+            return Some(body.strip_prefix("\r\n").or_else(|| body.strip_prefix('\n')).unwrap_or(body).to_string());
+        }
+        let quote = text.chars().next()?;
+        let inner = text.strip_prefix(quote)?;
+        let inner = inner.strip_suffix(quote).unwrap_or(inner);
+        Some(unescape(inner))
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some('a') => out.push('\x07'),
+            Some('b') => out.push('\x08'),
+            Some('f') => out.push('\x0c'),
+            Some('v') => out.push('\x0b'),
+            Some('z') => {
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+            }
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                if let Ok(v) = u8::from_str_radix(&hex, 16) {
+                    out.push(v as char);
+                }
+            }
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let hex: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    out.push(c);
+                }
+            }
+            Some(d) if d.is_ascii_digit() => {
+                let mut value = d.to_digit(10).unwrap_or(0);
+                for _ in 0..2 {
+                    match chars.peek().and_then(|c| c.to_digit(10)) {
+                        Some(n) => {
+                            value = value * 10 + n;
+                            chars.next();
+                        }
+                        None => break,
+                    }
+                }
+                if let Some(c) = char::from_u32(value) {
+                    out.push(c);
+                }
+            }
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    /// Last offset a column was computed for, so long lines are only counted once.
+    column_mark: (usize, usize),
+    errors: Vec<SyntaxError>,
+}
+
+/// Splits Luau source into tokens, skipping whitespace and comments. Malformed input never
+/// fails: unfinished strings end at the line break and stray characters become symbols.
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+    lex(source).0
+}
+
+/// Like [`tokenize`], but also returns the unfinished strings and comments it had to cut short.
+pub fn lex(source: &str) -> (Vec<Token<'_>>, Vec<SyntaxError>) {
+    let mut lexer = Lexer {
+        source,
+        bytes: source.as_bytes(),
+        pos: 0,
+        line: 1,
+        line_start: 0,
+        column_mark: (0, 1),
+        errors: Vec::new(),
+    };
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        let eof = token.kind == TokenKind::Eof;
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+    (tokens, lexer.errors)
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self, ahead: usize) -> u8 {
+        self.bytes.get(self.pos + ahead).copied().unwrap_or(0)
+    }
+
+    fn advance(&mut self) {
+        if self.peek(0) == b'\n' {
+            self.line += 1;
+            self.line_start = self.pos + 1;
+        }
+        self.pos += 1;
+    }
+
+    fn column(&mut self, offset: usize) -> usize {
+        let (mut start, mut column) = self.column_mark;
+        if start < self.line_start || start > offset {
+            (start, column) = (self.line_start.min(offset), 1);
+        }
+        column += self.source.get(start..offset).map_or(0, |s| s.chars().count());
+        self.column_mark = (offset, column);
+        column
+    }
+
+    fn error(&mut self, line: usize, column: usize, message: &str) {
+        self.errors.push(SyntaxError { line, column, message: message.to_string() });
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let c = self.peek(0);
+            if c == b' ' || c == b'\t' || c == b'\r' || c == b'\n' || c == 0x0b || c == 0x0c {
+                self.advance();
+            } else if c == b'-' && self.peek(1) == b'-' {
+                let (line, column) = (self.line, self.column(self.pos));
+                self.pos += 2;
+                if let Some(level) = self.long_bracket_level() {
+                    if !self.skip_long_bracket(level) {
+                        self.error(line, column, "unfinished long comment");
+                    }
+                } else {
+                    while self.pos < self.bytes.len() && self.peek(0) != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+            } else if self.pos == 0 && c == b'#' && self.peek(1) == b'!' {
+                while self.pos < self.bytes.len() && self.peek(0) != b'\n' {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// If a long bracket (`[[`, `[==[`) starts at the cursor, returns its level without consuming it.
+    fn long_bracket_level(&self) -> Option<usize> {
+        if self.peek(0) != b'[' {
+            return None;
+        }
+        let mut level = 0;
+        while self.peek(1 + level) == b'=' {
+            level += 1;
+        }
+        (self.peek(1 + level) == b'[').then_some(level)
+    }
+
+    /// Returns false when the source ends before the closing bracket.
+    fn skip_long_bracket(&mut self, level: usize) -> bool {
+        self.pos += level + 2;
+        while self.pos < self.bytes.len() {
+            if self.peek(0) == b']' {
+                let mut l = 0;
+                while self.peek(1 + l) == b'=' {
+                    l += 1;
+                }
+                if l == level && self.peek(1 + l) == b']' {
+                    self.pos += level + 2;
+                    return true;
+                }
+            }
+            self.advance();
+        }
+        false
+    }
+
+    fn next_token(&mut self) -> Token<'a> {
+        self.skip_trivia();
+        let start = self.pos;
+        let line = self.line;
+        let column = self.column(start);
+        let (kind, error) = self.scan_token();
+        if let Some(message) = error {
+            self.error(line, column, message);
+        }
+        let end = self.pos.min(self.source.len());
+        Token { kind, text: &self.source[start..end], offset: start, line, column }
+    }
+
+    fn scan_token(&mut self) -> (TokenKind, Option<&'static str>) {
+        let c = self.peek(0);
+        if self.pos >= self.bytes.len() {
+            return (TokenKind::Eof, None);
+        }
+
+        if c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 {
+            let start = self.pos;
+            while self.pos < self.bytes.len() && (self.peek(0).is_ascii_alphanumeric() || self.peek(0) == b'_' || self.peek(0) >= 0x80) {
+                self.pos += 1;
+            }
+            let word = &self.source[start..self.pos];
+            return (if KEYWORDS.contains(&word) { TokenKind::Keyword } else { TokenKind::Name }, None);
+        }
+
+        if c.is_ascii_digit() || (c == b'.' && self.peek(1).is_ascii_digit()) {
+            self.scan_number();
+            return (TokenKind::Number, None);
+        }
+
+        if c == b'"' || c == b'\'' {
+            let closed = self.scan_quoted(c);
+            return (TokenKind::String, (!closed).then_some("unfinished string"));
+        }
+
+        if c == b'`' {
+            let closed = self.scan_interpolated();
+            return (TokenKind::InterpolatedString, (!closed).then_some("unfinished interpolated string"));
+        }
+
+        if let Some(level) = self.long_bracket_level() {
+            let closed = self.skip_long_bracket(level);
+            return (TokenKind::String, (!closed).then_some("unfinished long string"));
+        }
+
+        for symbol in SYMBOLS {
+            if self.source[self.pos..].starts_with(symbol) {
+                self.pos += symbol.len();
+                return (TokenKind::Symbol, None);
+            }
+        }
+
+        let ch = self.source[self.pos..].chars().next().unwrap_or('\0');
+        self.pos += ch.len_utf8();
+        (TokenKind::Symbol, None)
+    }
+
+    fn scan_number(&mut self) {
+        if self.peek(0) == b'0' && matches!(self.peek(1), b'x' | b'X' | b'b' | b'B') {
+            self.pos += 2;
+            while self.peek(0).is_ascii_hexdigit() || self.peek(0) == b'_' {
+                self.pos += 1;
+            }
+            return;
+        }
+        while self.peek(0).is_ascii_digit() || self.peek(0) == b'_' || self.peek(0) == b'.' {
+            if self.peek(0) == b'.' && self.peek(1) == b'.' {
+                break;
+            }
+            self.pos += 1;
+        }
+        if matches!(self.peek(0), b'e' | b'E') {
+            self.pos += 1;
+            if matches!(self.peek(0), b'+' | b'-') {
+                self.pos += 1;
+            }
+            while self.peek(0).is_ascii_digit() {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn scan_quoted(&mut self, quote: u8) -> bool {
+        self.pos += 1;
+        loop {
+            let c = self.peek(0);
+            if self.pos >= self.bytes.len() || c == b'\n' {
+                return false;
+            }
+            if c == b'\\' {
+                self.pos += 1;
+                if self.peek(0) == b'z' {
+                    self.pos += 1;
+                    while self.peek(0).is_ascii_whitespace() {
+                        self.advance();
+                    }
+                } else {
+                    self.advance();
+                }
+                continue;
+            }
+            self.pos += 1;
+            if c == quote {
+                return true;
+            }
+        }
+    }
+
+    fn scan_interpolated(&mut self) -> bool {
+        self.pos += 1;
+        let mut depth = 0usize;
+        loop {
+            let c = self.peek(0);
+            if self.pos >= self.bytes.len() {
+                return false;
+            }
+            match c {
+                b'\\' => {
+                    self.pos += 1;
+                    self.advance();
+                    continue;
+                }
+                b'{' => depth += 1,
+                b'}' => depth = depth.saturating_sub(1),
+                b'`' if depth == 0 => {
+                    self.pos += 1;
+                    return true;
+                }
+                b'\n' if depth == 0 => return false,
+                _ => {}
+            }
+            self.advance();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(source).into_iter().map(|t| (t.kind, t.text)).collect()
+    }
+
+    #[test]
+    fn splits_names_keywords_numbers_and_symbols() {
+        assert_eq!(
+            kinds("local x //= 0x1F..y"),
+            vec![
+                (TokenKind::Keyword, "local"),
+                (TokenKind::Name, "x"),
+                (TokenKind::Symbol, "//="),
+                (TokenKind::Number, "0x1F"),
+                (TokenKind::Symbol, ".."),
+                (TokenKind::Name, "y"),
+                (TokenKind::Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        assert_eq!(kinds("--[==[ a\n]] ]==] x -- y\nz"), vec![(TokenKind::Name, "x"), (TokenKind::Name, "z"), (TokenKind::Eof, "")]);
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let tokens = tokenize("a = \"€\" b\n  c");
+        assert_eq!((tokens[3].text, tokens[3].line, tokens[3].column), ("b", 1, 9));
+        assert_eq!((tokens[4].text, tokens[4].line, tokens[4].column), ("c", 2, 3));
+    }
+
+    #[test]
+    fn decodes_string_values() {
+        let tokens = tokenize(r#"'a\tb' "\65\u{20AC}\x41" [==[
+x]]y]==]"#);
+        let values: Vec<_> = tokens.iter().filter_map(|t| t.string_value()).collect();
+        assert_eq!(values, vec!["a\tb", "A€A", "x]]y"]);
+    }
+
+    #[test]
+    fn reports_unfinished_strings() {
+        let (tokens, errors) = lex("x = 'abc\ny = [[abc");
+        assert_eq!(tokens[2].string_value().as_deref(), Some("abc"));
+        assert_eq!(tokens[5].string_value().as_deref(), Some("abc"));
+        let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, vec!["1:5: unfinished string", "2:5: unfinished long string"]);
+    }
+
+    #[test]
+    fn unfinished_long_string_with_multibyte_text() {
+        for source in ["x = [[€", "x = [=[€]", "x = [[", "x = [==[€ ]=]"] {
+            let tokens = tokenize(source);
+            assert_eq!(tokens[2].kind, TokenKind::String, "{}", source);
+            assert!(tokens[2].string_value().is_some(), "{}", source);
+        }
+        assert_eq!(tokenize("x = [[€")[2].string_value().as_deref(), Some("€"));
+    }
+}
@@ -0,0 +1,1002 @@
+use super::lexer::{lex, SyntaxError, Token, TokenKind};
+use serde::Serialize;
+
+pub type Block = Vec<Stat>;
+
+/// A local, parameter or loop variable. Type annotations are kept as their source text.
+#[derive(Serialize)]
+pub struct Binding {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct FunctionBody {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub generics: Vec<String>,
+    pub params: Vec<Binding>,
+    pub variadic: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_type: Option<String>,
+    pub body: Block,
+}
+
+#[derive(Serialize)]
+pub struct IfBranch {
+    pub condition: Expr,
+    pub body: Block,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum Stat {
+    Local { line: usize, bindings: Vec<Binding>, values: Vec<Expr> },
+    LocalFunction { line: usize, name: String, function: FunctionBody },
+    Function { line: usize, name: Vec<String>, method: Option<String>, function: FunctionBody },
+    Assign { line: usize, targets: Vec<Expr>, values: Vec<Expr> },
+    CompoundAssign { line: usize, op: String, target: Expr, value: Expr },
+    Call { line: usize, call: Expr },
+    Do { line: usize, body: Block },
+    While { line: usize, condition: Expr, body: Block },
+    Repeat { line: usize, body: Block, condition: Expr },
+    If { line: usize, branches: Vec<IfBranch>, else_body: Option<Block> },
+    NumericFor { line: usize, var: Binding, start: Expr, end: Expr, step: Option<Expr>, body: Block },
+    GenericFor { line: usize, vars: Vec<Binding>, values: Vec<Expr>, body: Block },
+    Return { line: usize, values: Vec<Expr> },
+    Break { line: usize },
+    Continue { line: usize },
+    TypeAlias { line: usize, exported: bool, name: String, generics: Vec<String>, value: String },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum TableField {
+    Positional { value: Expr },
+    Named { name: String, value: Expr },
+    Keyed { key: Expr, value: Expr },
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind")]
+pub enum Expr {
+    Nil,
+    Boolean { value: bool },
+    Number { value: String },
+    String { value: String },
+    InterpolatedString { raw: String },
+    Vararg,
+    Function { function: Box<FunctionBody> },
+    Table { fields: Vec<TableField> },
+    Name { name: String },
+    Field { object: Box<Expr>, name: String },
+    Index { object: Box<Expr>, key: Box<Expr> },
+    Call { callee: Box<Expr>, args: Vec<Expr> },
+    MethodCall { object: Box<Expr>, method: String, args: Vec<Expr> },
+    Paren { expr: Box<Expr> },
+    Unary { op: String, operand: Box<Expr> },
+    Binary { op: String, left: Box<Expr>, right: Box<Expr> },
+    IfElse { condition: Box<Expr>, then: Box<Expr>, otherwise: Box<Expr> },
+    TypeAssertion { expr: Box<Expr>, annotation: String },
+}
+
+const COMPOUND_OPS: &[&str] = &["+=", "-=", "*=", "/=", "//=", "%=", "^=", "..="];
+
+/// Left and right binding power; `..` and `^` are right associative.
+fn binary_priority(token: &Token) -> Option<(u8, u8)> {
+    let op = match token.kind {
+        TokenKind::Keyword | TokenKind::Symbol => token.text,
+        _ => return None,
+    };
+    Some(match op {
+        "or" => (1, 1),
+        "and" => (2, 2),
+        "<" | ">" | "<=" | ">=" | "~=" | "==" => (3, 3),
+        ".." => (5, 4),
+        "+" | "-" => (6, 6),
+        "*" | "/" | "//" | "%" => (7, 7),
+        "^" => (10, 9),
+        _ => return None,
+    })
+}
+
+const UNARY_PRIORITY: u8 = 8;
+
+/// Same nesting limit as the Luau parser, which also keeps us clear of stack overflows.
+const MAX_DEPTH: usize = 1000;
+
+type ParseResult<T> = Result<T, SyntaxError>;
+
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    depth: usize,
+}
+
+/// Parses a whole script. Only the first error is reported, as Luau itself does for most errors.
+pub fn parse(source: &str) -> Result<Block, SyntaxError> {
+    let (tokens, errors) = lex(source);
+    if let Some(error) = errors.into_iter().next() {
+        return Err(error);
+    }
+    let mut parser = Parser { source, tokens, pos: 0, depth: 0 };
+    let block = parser.block()?;
+    let token = parser.peek();
+    if token.kind != TokenKind::Eof {
+        return Err(parser.error_at(token, format!("expected <eof>, got {}", describe(&token))));
+    }
+    Ok(block)
+}
+
+fn describe(token: &Token) -> String {
+    match token.kind {
+        TokenKind::Eof => "<eof>".to_string(),
+        TokenKind::String | TokenKind::InterpolatedString => "string".to_string(),
+        _ => format!("'{}'", token.text),
+    }
+}
+
+fn block_ends(token: &Token) -> bool {
+    token.kind == TokenKind::Eof
+        || ["end", "else", "elseif", "until"].iter().any(|k| token.is_keyword(k))
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Token<'a> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, ahead: usize) -> Token<'a> {
+        let index = (self.pos + ahead).min(self.tokens.len() - 1);
+        self.tokens[index]
+    }
+
+    fn next(&mut self) -> Token<'a> {
+        let token = self.peek();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn error_at(&self, token: Token, message: String) -> SyntaxError {
+        SyntaxError { line: token.line, column: token.column, message }
+    }
+
+    fn unexpected(&self, expected: &str) -> SyntaxError {
+        let token = self.peek();
+        self.error_at(token, format!("expected {}, got {}", expected, describe(&token)))
+    }
+
+    fn accept_symbol(&mut self, symbol: &str) -> bool {
+        let matched = self.peek().is_symbol(symbol);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn accept_keyword(&mut self, keyword: &str) -> bool {
+        let matched = self.peek().is_keyword(keyword);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> ParseResult<()> {
+        if self.accept_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("'{}'", symbol)))
+        }
+    }
+
+    /// Expects the keyword closing a construct opened at `opener`, mentioning it in the error.
+    fn expect_closing(&mut self, keyword: &str, opener: Token) -> ParseResult<()> {
+        if self.accept_keyword(keyword) {
+            return Ok(());
+        }
+        let token = self.peek();
+        let mut message = format!("expected '{}'", keyword);
+        if token.line != opener.line {
+            message += &format!(" (to close '{}' at line {})", opener.text, opener.line);
+        }
+        message += &format!(", got {}", describe(&token));
+        Err(self.error_at(token, message))
+    }
+
+    fn name(&mut self) -> ParseResult<String> {
+        let token = self.peek();
+        if token.kind == TokenKind::Name {
+            self.pos += 1;
+            Ok(token.text.to_string())
+        } else {
+            Err(self.unexpected("identifier"))
+        }
+    }
+
+    fn enter(&mut self) -> ParseResult<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error_at(self.peek(), "exceeded allowed recursion depth; simplify the expression".to_string()));
+        }
+        Ok(())
+    }
+
+    fn block(&mut self) -> ParseResult<Block> {
+        self.enter()?;
+        let block = self.statements();
+        self.depth -= 1;
+        block
+    }
+
+    fn statements(&mut self) -> ParseResult<Block> {
+        let mut block = Vec::new();
+        loop {
+            while self.accept_symbol(";") {}
+            let token = self.peek();
+            if block_ends(&token) {
+                break;
+            }
+            if token.is_keyword("return") {
+                self.pos += 1;
+                let values = if block_ends(&self.peek()) || self.peek().is_symbol(";") { Vec::new() } else { self.expr_list()? };
+                self.accept_symbol(";");
+                block.push(Stat::Return { line: token.line, values });
+                if !block_ends(&self.peek()) {
+                    return Err(self.unexpected("end of block after return"));
+                }
+                break;
+            }
+            block.push(self.statement()?);
+        }
+        Ok(block)
+    }
+
+    fn statement(&mut self) -> ParseResult<Stat> {
+        let token = self.peek();
+        let line = token.line;
+
+        if token.kind == TokenKind::Keyword {
+            match token.text {
+                "local" => return self.local(),
+                "function" => {
+                    self.pos += 1;
+                    let mut name = vec![self.name()?];
+                    while self.accept_symbol(".") {
+                        name.push(self.name()?);
+                    }
+                    let method = if self.accept_symbol(":") { Some(self.name()?) } else { None };
+                    let function = self.function_body(token)?;
+                    return Ok(Stat::Function { line, name, method, function });
+                }
+                "do" => {
+                    self.pos += 1;
+                    let body = self.block()?;
+                    self.expect_closing("end", token)?;
+                    return Ok(Stat::Do { line, body });
+                }
+                "while" => {
+                    self.pos += 1;
+                    let condition = self.expr()?;
+                    self.expect_do()?;
+                    let body = self.block()?;
+                    self.expect_closing("end", token)?;
+                    return Ok(Stat::While { line, condition, body });
+                }
+                "repeat" => {
+                    self.pos += 1;
+                    let body = self.block()?;
+                    self.expect_closing("until", token)?;
+                    let condition = self.expr()?;
+                    return Ok(Stat::Repeat { line, body, condition });
+                }
+                "if" => return self.if_statement(),
+                "for" => return self.for_statement(),
+                "break" => {
+                    self.pos += 1;
+                    return Ok(Stat::Break { line });
+                }
+                _ => {}
+            }
+        }
+
+        if token.is_symbol("@") {
+            self.attributes()?;
+            let next = self.peek();
+            if next.is_keyword("function") || (next.is_keyword("local") && self.peek_at(1).is_keyword("function")) {
+                return self.statement();
+            }
+            return Err(self.unexpected("'function' after attribute"));
+        }
+
+        if token.kind == TokenKind::Name {
+            let next = self.peek_at(1);
+            if token.text == "continue" && self.is_statement_end(next) {
+                self.pos += 1;
+                return Ok(Stat::Continue { line });
+            }
+            if token.text == "type" && next.kind == TokenKind::Name {
+                self.pos += 1;
+                return self.type_alias(line, false);
+            }
+            if token.text == "export" && next.is_name("type") {
+                self.pos += 2;
+                return self.type_alias(line, true);
+            }
+        }
+
+        let target = self.suffixed_expr()?;
+        let next = self.peek();
+        if next.is_symbol("=") || next.is_symbol(",") {
+            let mut targets = vec![target];
+            while self.accept_symbol(",") {
+                targets.push(self.suffixed_expr()?);
+            }
+            for target in &targets {
+                if !matches!(target, Expr::Name { .. } | Expr::Field { .. } | Expr::Index { .. }) {
+                    return Err(self.error_at(token, "assigned expression must be a variable or a field".to_string()));
+                }
+            }
+            self.expect_symbol("=")?;
+            let values = self.expr_list()?;
+            return Ok(Stat::Assign { line, targets, values });
+        }
+        if next.kind == TokenKind::Symbol && COMPOUND_OPS.contains(&next.text) {
+            if !matches!(target, Expr::Name { .. } | Expr::Field { .. } | Expr::Index { .. }) {
+                return Err(self.error_at(token, "assigned expression must be a variable or a field".to_string()));
+            }
+            self.pos += 1;
+            let value = self.expr()?;
+            return Ok(Stat::CompoundAssign { line, op: next.text.to_string(), target, value });
+        }
+        if matches!(target, Expr::Call { .. } | Expr::MethodCall { .. }) {
+            return Ok(Stat::Call { line, call: target });
+        }
+        Err(self.error_at(token, "incomplete statement: expected assignment or a function call".to_string()))
+    }
+
+    /// `continue` is only a statement when it can not start an expression.
+    fn is_statement_end(&self, next: Token) -> bool {
+        !(next.is_symbol("(") || next.is_symbol(".") || next.is_symbol("[") || next.is_symbol(":") || next.is_symbol("=") || next.is_symbol(",")
+            || next.is_symbol("{") || next.kind == TokenKind::String
+            || (next.kind == TokenKind::Symbol && COMPOUND_OPS.contains(&next.text)))
+    }
+
+    fn expect_do(&mut self) -> ParseResult<()> {
+        if self.accept_keyword("do") {
+            Ok(())
+        } else {
+            Err(self.unexpected("'do'"))
+        }
+    }
+
+    fn attributes(&mut self) -> ParseResult<()> {
+        while self.accept_symbol("@") {
+            self.name()?;
+        }
+        Ok(())
+    }
+
+    fn local(&mut self) -> ParseResult<Stat> {
+        let token = self.next();
+        let line = token.line;
+        if self.peek().is_keyword("function") {
+            let function_token = self.next();
+            let name = self.name()?;
+            let function = self.function_body(function_token)?;
+            return Ok(Stat::LocalFunction { line, name, function });
+        }
+
+        let mut bindings = vec![self.binding()?];
+        while self.accept_symbol(",") {
+            bindings.push(self.binding()?);
+        }
+        let values = if self.accept_symbol("=") { self.expr_list()? } else { Vec::new() };
+        Ok(Stat::Local { line, bindings, values })
+    }
+
+    fn binding(&mut self) -> ParseResult<Binding> {
+        let name = self.name()?;
+        let annotation = if self.accept_symbol(":") { Some(self.type_text()?) } else { None };
+        Ok(Binding { name, annotation })
+    }
+
+    fn if_statement(&mut self) -> ParseResult<Stat> {
+        let opener = self.next();
+        let mut branches = Vec::new();
+        loop {
+            let condition = self.expr()?;
+            if !self.accept_keyword("then") {
+                return Err(self.unexpected("'then'"));
+            }
+            let body = self.block()?;
+            branches.push(IfBranch { condition, body });
+            if !self.accept_keyword("elseif") {
+                break;
+            }
+        }
+        let else_body = if self.accept_keyword("else") { Some(self.block()?) } else { None };
+        self.expect_closing("end", opener)?;
+        Ok(Stat::If { line: opener.line, branches, else_body })
+    }
+
+    fn for_statement(&mut self) -> ParseResult<Stat> {
+        let opener = self.next();
+        let line = opener.line;
+        let first = self.binding()?;
+
+        if self.accept_symbol("=") {
+            let start = self.expr()?;
+            self.expect_symbol(",")?;
+            let end = self.expr()?;
+            let step = if self.accept_symbol(",") { Some(self.expr()?) } else { None };
+            self.expect_do()?;
+            let body = self.block()?;
+            self.expect_closing("end", opener)?;
+            return Ok(Stat::NumericFor { line, var: first, start, end, step, body });
+        }
+
+        let mut vars = vec![first];
+        while self.accept_symbol(",") {
+            vars.push(self.binding()?);
+        }
+        if !self.accept_keyword("in") {
+            return Err(self.unexpected("'=' or 'in'"));
+        }
+        let values = self.expr_list()?;
+        self.expect_do()?;
+        let body = self.block()?;
+        self.expect_closing("end", opener)?;
+        Ok(Stat::GenericFor { line, vars, values, body })
+    }
+
+    fn type_alias(&mut self, line: usize, exported: bool) -> ParseResult<Stat> {
+        let name = self.name()?;
+        let generics = if self.peek().is_symbol("<") { self.generic_list()? } else { Vec::new() };
+        self.expect_symbol("=")?;
+        let value = self.type_text()?;
+        Ok(Stat::TypeAlias { line, exported, name, generics, value })
+    }
+
+    /// `<T, U..., V = number>`; defaults are kept in the returned text.
+    fn generic_list(&mut self) -> ParseResult<Vec<String>> {
+        self.expect_symbol("<")?;
+        let mut generics = Vec::new();
+        loop {
+            let start = self.peek();
+            self.name()?;
+            self.accept_symbol("...");
+            if self.accept_symbol("=") {
+                self.type_pack_or_type()?;
+            }
+            generics.push(self.text_from(start));
+            if !self.accept_symbol(",") {
+                break;
+            }
+        }
+        self.close_angle()?;
+        Ok(generics)
+    }
+
+    /// A `>` that may have been lexed together with a following `=`, as in `Array<T>= {}`.
+    fn close_angle(&mut self) -> ParseResult<()> {
+        let token = self.peek();
+        if token.is_symbol(">") {
+            self.pos += 1;
+            return Ok(());
+        }
+        if token.is_symbol(">=") {
+            self.tokens[self.pos] = Token { text: &token.text[1..], offset: token.offset + 1, column: token.column + 1, ..token };
+            return Ok(());
+        }
+        Err(self.unexpected("'>'"))
+    }
+
+    fn text_from(&self, start: Token) -> String {
+        let last = self.tokens[self.pos.saturating_sub(1)];
+        if last.offset < start.offset {
+            return String::new();
+        }
+        self.source[start.offset..last.offset + last.text.len()].to_string()
+    }
+
+    fn function_body(&mut self, opener: Token) -> ParseResult<FunctionBody> {
+        let generics = if self.peek().is_symbol("<") { self.generic_list()? } else { Vec::new() };
+        self.expect_symbol("(")?;
+        let mut params = Vec::new();
+        let mut variadic = false;
+        if !self.accept_symbol(")") {
+            loop {
+                if self.accept_symbol("...") {
+                    variadic = true;
+                    if self.accept_symbol(":") {
+                        self.type_pack_or_type()?;
+                    }
+                    break;
+                }
+                params.push(self.binding()?);
+                if !self.accept_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+        }
+        let return_type = if self.accept_symbol(":") {
+            let start = self.peek();
+            self.return_type()?;
+            Some(self.text_from(start))
+        } else {
+            None
+        };
+        let body = self.block()?;
+        self.expect_closing("end", opener)?;
+        Ok(FunctionBody { generics, params, variadic, return_type, body })
+    }
+
+    fn expr_list(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut list = vec![self.expr()?];
+        while self.accept_symbol(",") {
+            list.push(self.expr()?);
+        }
+        Ok(list)
+    }
+
+    fn expr(&mut self) -> ParseResult<Expr> {
+        self.sub_expr(0)
+    }
+
+    fn sub_expr(&mut self, limit: u8) -> ParseResult<Expr> {
+        self.enter()?;
+        let expr = self.operators(limit);
+        self.depth -= 1;
+        expr
+    }
+
+    fn operators(&mut self, limit: u8) -> ParseResult<Expr> {
+        let token = self.peek();
+        let mut left = if token.is_keyword("not") || token.is_symbol("-") || token.is_symbol("#") {
+            self.pos += 1;
+            let operand = self.sub_expr(UNARY_PRIORITY)?;
+            Expr::Unary { op: token.text.to_string(), operand: Box::new(operand) }
+        } else {
+            self.simple_expr()?
+        };
+
+        // Each operator in a left-associative chain nests the tree one level deeper without
+        // recursing, so it counts towards the limit as well.
+        let depth = self.depth;
+        while let Some((left_priority, right_priority)) = binary_priority(&self.peek()) {
+            if left_priority <= limit {
+                break;
+            }
+            if left_priority <= right_priority {
+                self.enter()?;
+            }
+            let op = self.next().text.to_string();
+            let right = self.sub_expr(right_priority)?;
+            left = Expr::Binary { op, left: Box::new(left), right: Box::new(right) };
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn simple_expr(&mut self) -> ParseResult<Expr> {
+        let token = self.peek();
+        let expr = match token.kind {
+            TokenKind::Number => {
+                self.pos += 1;
+                Expr::Number { value: token.text.to_string() }
+            }
+            TokenKind::String => {
+                self.pos += 1;
+                Expr::String { value: token.string_value().unwrap_or_default() }
+            }
+            TokenKind::InterpolatedString => {
+                self.pos += 1;
+                Expr::InterpolatedString { raw: token.text.to_string() }
+            }
+            TokenKind::Keyword => match token.text {
+                "nil" => {
+                    self.pos += 1;
+                    Expr::Nil
+                }
+                "true" | "false" => {
+                    self.pos += 1;
+                    Expr::Boolean { value: token.text == "true" }
+                }
+                "function" => {
+                    self.pos += 1;
+                    Expr::Function { function: Box::new(self.function_body(token)?) }
+                }
+                "if" => {
+                    self.pos += 1;
+                    self.if_else_expr()?
+                }
+                _ => return Err(self.unexpected("expression")),
+            },
+            TokenKind::Symbol if token.text == "..." => {
+                self.pos += 1;
+                Expr::Vararg
+            }
+            TokenKind::Symbol if token.text == "{" => self.table()?,
+            TokenKind::Symbol if token.text == "@" => {
+                self.attributes()?;
+                let function = self.peek();
+                if !self.accept_keyword("function") {
+                    return Err(self.unexpected("'function' after attribute"));
+                }
+                Expr::Function { function: Box::new(self.function_body(function)?) }
+            }
+            _ => self.suffixed_expr()?,
+        };
+
+        if self.accept_symbol("::") {
+            let annotation = self.type_text()?;
+            return Ok(Expr::TypeAssertion { expr: Box::new(expr), annotation });
+        }
+        Ok(expr)
+    }
+
+    fn if_else_expr(&mut self) -> ParseResult<Expr> {
+        let condition = self.expr()?;
+        if !self.accept_keyword("then") {
+            return Err(self.unexpected("'then'"));
+        }
+        let then = self.expr()?;
+        let otherwise = if self.accept_keyword("elseif") {
+            self.if_else_expr()?
+        } else if self.accept_keyword("else") {
+            self.expr()?
+        } else {
+            return Err(self.unexpected("'else'"));
+        };
+        Ok(Expr::IfElse { condition: Box::new(condition), then: Box::new(then), otherwise: Box::new(otherwise) })
+    }
+
+    fn primary_expr(&mut self) -> ParseResult<Expr> {
+        let token = self.peek();
+        if token.kind == TokenKind::Name {
+            self.pos += 1;
+            return Ok(Expr::Name { name: token.text.to_string() });
+        }
+        if token.is_symbol("(") {
+            self.pos += 1;
+            let expr = self.expr()?;
+            if !self.accept_symbol(")") {
+                let close = self.peek();
+                let mut message = "expected ')'".to_string();
+                if close.line != token.line {
+                    message += &format!(" (to close '(' at line {})", token.line);
+                }
+                message += &format!(", got {}", describe(&close));
+                return Err(self.error_at(close, message));
+            }
+            return Ok(Expr::Paren { expr: Box::new(expr) });
+        }
+        Err(self.unexpected("expression"))
+    }
+
+    fn suffixed_expr(&mut self) -> ParseResult<Expr> {
+        let mut expr = self.primary_expr()?;
+        loop {
+            let token = self.peek();
+            if token.is_symbol(".") {
+                self.pos += 1;
+                let name = self.name()?;
+                expr = Expr::Field { object: Box::new(expr), name };
+            } else if token.is_symbol("[") {
+                self.pos += 1;
+                let key = self.expr()?;
+                self.expect_symbol("]")?;
+                expr = Expr::Index { object: Box::new(expr), key: Box::new(key) };
+            } else if token.is_symbol(":") {
+                self.pos += 1;
+                let method = self.name()?;
+                let args = self.call_args()?;
+                expr = Expr::MethodCall { object: Box::new(expr), method, args };
+            } else if token.is_symbol("(") || token.is_symbol("{") || token.kind == TokenKind::String {
+                let args = self.call_args()?;
+                expr = Expr::Call { callee: Box::new(expr), args };
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn call_args(&mut self) -> ParseResult<Vec<Expr>> {
+        let token = self.peek();
+        if token.kind == TokenKind::String {
+            self.pos += 1;
+            return Ok(vec![Expr::String { value: token.string_value().unwrap_or_default() }]);
+        }
+        if token.is_symbol("{") {
+            return Ok(vec![self.table()?]);
+        }
+        if !token.is_symbol("(") {
+            return Err(self.unexpected("function arguments"));
+        }
+        self.pos += 1;
+        if self.accept_symbol(")") {
+            return Ok(Vec::new());
+        }
+        let args = self.expr_list()?;
+        self.expect_symbol(")")?;
+        Ok(args)
+    }
+
+    fn table(&mut self) -> ParseResult<Expr> {
+        let open = self.next();
+        let mut fields = Vec::new();
+        loop {
+            let token = self.peek();
+            if token.is_symbol("}") {
+                self.pos += 1;
+                break;
+            }
+            if token.is_symbol("[") {
+                self.pos += 1;
+                let key = self.expr()?;
+                self.expect_symbol("]")?;
+                self.expect_symbol("=")?;
+                let value = self.expr()?;
+                fields.push(TableField::Keyed { key, value });
+            } else if token.kind == TokenKind::Name && self.peek_at(1).is_symbol("=") {
+                self.pos += 2;
+                let value = self.expr()?;
+                fields.push(TableField::Named { name: token.text.to_string(), value });
+            } else {
+                let value = self.expr()?;
+                fields.push(TableField::Positional { value });
+            }
+            if !(self.accept_symbol(",") || self.accept_symbol(";")) {
+                if self.accept_symbol("}") {
+                    break;
+                }
+                let close = self.peek();
+                let mut message = "expected '}'".to_string();
+                if close.line != open.line {
+                    message += &format!(" (to close '{{' at line {})", open.line);
+                }
+                message += &format!(", got {}", describe(&close));
+                return Err(self.error_at(close, message));
+            }
+        }
+        Ok(Expr::Table { fields })
+    }
+
+    // Types are validated but only their source text is kept.
+
+    fn type_text(&mut self) -> ParseResult<String> {
+        let start = self.peek();
+        self.type_expr()?;
+        Ok(self.text_from(start))
+    }
+
+    fn type_expr(&mut self) -> ParseResult<()> {
+        // A leading `|` or `&` is allowed before the first member.
+        if !self.accept_symbol("|") {
+            self.accept_symbol("&");
+        }
+        self.simple_type()?;
+        loop {
+            if self.accept_symbol("?") {
+                continue;
+            }
+            if self.accept_symbol("|") || self.accept_symbol("&") {
+                self.simple_type()?;
+                continue;
+            }
+            return Ok(());
+        }
+    }
+
+    /// A return annotation: either a type or a parenthesized pack like `(number, ...string)`.
+    fn return_type(&mut self) -> ParseResult<()> {
+        if self.peek().is_symbol("(") {
+            return self.function_or_paren_type(true);
+        }
+        self.type_pack_or_type()
+    }
+
+    fn type_pack_or_type(&mut self) -> ParseResult<()> {
+        if self.accept_symbol("...") {
+            return self.type_expr();
+        }
+        if self.peek().kind == TokenKind::Name && self.peek_at(1).is_symbol("...") {
+            self.pos += 2;
+            return Ok(());
+        }
+        self.type_expr()
+    }
+
+    fn simple_type(&mut self) -> ParseResult<()> {
+        let token = self.peek();
+        match token.kind {
+            TokenKind::Keyword if matches!(token.text, "nil" | "true" | "false") => {
+                self.pos += 1;
+                Ok(())
+            }
+            TokenKind::String => {
+                self.pos += 1;
+                Ok(())
+            }
+            TokenKind::Name if token.text == "typeof" && self.peek_at(1).is_symbol("(") => {
+                self.pos += 2;
+                self.expr()?;
+                self.expect_symbol(")")
+            }
+            TokenKind::Name => {
+                self.pos += 1;
+                if self.accept_symbol(".") {
+                    self.name()?;
+                }
+                if self.accept_symbol("<") {
+                    if !self.peek().is_symbol(">") {
+                        loop {
+                            if self.peek().is_symbol("(") {
+                                self.function_or_paren_type(true)?;
+                            } else {
+                                self.type_pack_or_type()?;
+                            }
+                            if !self.accept_symbol(",") {
+                                break;
+                            }
+                        }
+                    }
+                    self.close_angle()?;
+                }
+                Ok(())
+            }
+            TokenKind::Symbol if token.text == "{" => self.table_type(),
+            TokenKind::Symbol if token.text == "(" || token.text == "<" => self.function_or_paren_type(false),
+            _ => Err(self.unexpected("type")),
+        }
+    }
+
+    /// `(A, B) -> R`, `<T>(T) -> T` or a parenthesized type. Packs like `(A, B)` without an
+    /// arrow are only valid as return types and type arguments.
+    fn function_or_paren_type(&mut self, allow_pack: bool) -> ParseResult<()> {
+        let generic = self.peek().is_symbol("<");
+        if generic {
+            self.generic_list()?;
+        }
+        self.expect_symbol("(")?;
+        let mut count = 0;
+        if !self.accept_symbol(")") {
+            loop {
+                if self.peek().kind == TokenKind::Name && self.peek_at(1).is_symbol(":") {
+                    self.pos += 2;
+                }
+                self.type_pack_or_type()?;
+                count += 1;
+                if !self.accept_symbol(",") {
+                    break;
+                }
+            }
+            self.expect_symbol(")")?;
+        }
+        if self.accept_symbol("->") {
+            return self.return_type();
+        }
+        if generic || (count != 1 && !allow_pack) {
+            return Err(self.unexpected("'->' after function type arguments"));
+        }
+        Ok(())
+    }
+
+    fn table_type(&mut self) -> ParseResult<()> {
+        self.expect_symbol("{")?;
+        if self.accept_symbol("}") {
+            return Ok(());
+        }
+        // `{T}` is an array type.
+        let is_field = |p: &Self| {
+            let (first, second) = (p.peek(), p.peek_at(1));
+            first.is_symbol("[")
+                || (first.kind == TokenKind::Name && second.is_symbol(":"))
+                || ((first.is_name("read") || first.is_name("write")) && second.kind == TokenKind::Name)
+        };
+        if !is_field(self) {
+            self.type_expr()?;
+            return self.expect_symbol("}");
+        }
+        loop {
+            if self.peek().is_symbol("}") {
+                break;
+            }
+            let token = self.peek();
+            if (token.is_name("read") || token.is_name("write")) && (self.peek_at(1).kind == TokenKind::Name || self.peek_at(1).is_symbol("[")) {
+                self.pos += 1;
+            }
+            if self.accept_symbol("[") {
+                if self.peek().kind == TokenKind::String {
+                    self.pos += 1;
+                } else {
+                    self.type_expr()?;
+                }
+                self.expect_symbol("]")?;
+            } else {
+                self.name()?;
+            }
+            self.expect_symbol(":")?;
+            self.type_expr()?;
+            if !(self.accept_symbol(",") || self.accept_symbol(";")) {
+                break;
+            }
+        }
+        self.expect_symbol("}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        match parse(source) {
+            Ok(_) => panic!("{:?} parsed", source),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_luau_syntax() {
+        let source = r#"
+            --!strict
+            export type Point<T> = { x: T, y: T }
+            local Players = game:GetService("Players")
+            local function add(a: number, b: number): number
+                return a + b
+            end
+            function M.new(...): Point<number>
+                local t = { 1, x = 2, ["y"] = 3; }
+                t.x += 1
+                t.name ..= `id {t.x}`
+                for i = 1, 10, 2 do if i % 3 == 0 then continue end end
+                for _, p in Players:GetPlayers() do print(p.Name) end
+                repeat local n = #t until n > 0
+                while false do break end
+                return if t.x > 1 then t :: any else nil
+            end
+        "#;
+        let block = parse(source).unwrap();
+        let kinds: Vec<_> = block.iter().map(|stat| serde_json::to_value(stat).unwrap()["kind"].as_str().unwrap().to_string()).collect();
+        assert_eq!(kinds, vec!["TypeAlias", "Local", "LocalFunction", "Function"]);
+    }
+
+    #[test]
+    fn binary_operators_follow_priority() {
+        let block = parse("x = 1 + 2 * 3 .. 'a' .. 'b'").unwrap();
+        let json = serde_json::to_value(&block).unwrap();
+        let value = &json[0]["values"][0];
+        assert_eq!(value["op"], "..");
+        assert_eq!(value["left"]["op"], "+");
+        assert_eq!(value["left"]["right"]["op"], "*");
+        assert_eq!(value["right"]["op"], "..");
+    }
+
+    #[test]
+    fn reports_the_first_error_with_its_position() {
+        assert_eq!(error("local x = \nend"), "2:1: expected expression, got 'end'");
+        assert_eq!(error("if x then\n  y()\n"), "3:1: expected 'end' (to close 'if' at line 1), got <eof>");
+        assert_eq!(error("x = 'a\n"), "1:5: unfinished string");
+        assert_eq!(error("x = [[€"), "1:5: unfinished long string");
+        assert_eq!(error("return 1\nx = 2"), "2:1: expected end of block after return, got 'x'");
+        assert_eq!(error("x = 1 end"), "1:7: expected <eof>, got 'end'");
+    }
+
+    #[test]
+    fn limits_nesting() {
+        let source = format!("x = {}1{}", "(".repeat(5000), ")".repeat(5000));
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024 * 1024)
+            .spawn(move || parse(&source).map(|_| ()).map_err(|e| e.message))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -1,17 +1,48 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod analysis;
+mod cli;
+mod dom;
+mod dump;
+mod export;
+mod extract;
+mod luau;
+mod normalize;
+mod path_policy;
+mod plan;
+mod properties;
+mod search;
+mod sink;
+mod sqlite;
+
+use analysis::stats::PlaceStats;
+use analysis::ReportSettings;
+use clap::Parser;
 use eframe::egui;
-use std::fs;
-use std::path::{Path, PathBuf};
-use std::sync::mpsc::{channel, Receiver, Sender};
+use export::ExportSettings;
+use extract::{process_file_with_progress, ExtractionSettings, OutputFormat};
+use normalize::{Indentation, LineEndings, NormalizeSettings};
+use path_policy::DEFAULT_MAX_PATH_LENGTH;
+use plan::{build_plan, format_bytes, ExcludedSubtree, Plan, PlanNode};
+use search::{SearchQuery, SearchResults};
+use std::panic;
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
 use std::thread;
 use sys_locale::get_locale;
-use rayon::prelude::*;
-use std::io::{BufWriter, Write};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
 
 fn main() -> eframe::Result<()> {
+    attach_parent_console();
+    let args = cli::Cli::parse();
+    if let Some(command) = args.command {
+        if let Err(e) = cli::run(command) {
+            eprintln!("{}: {:#}", EN.error, e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let mut options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([500.0, 500.0])
@@ -36,6 +67,21 @@ fn main() -> eframe::Result<()> {
     )
 }
 
+// Release builds use the GUI subsystem on Windows, so CLI output needs the console of the launching shell.
+#[cfg(windows)]
+fn attach_parent_console() {
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
 #[derive(Clone, Copy, PartialEq)]
 enum Language {
     Russian,
@@ -57,6 +103,59 @@ struct Translations {
     exclude_workspace: &'static str,
     exclude_scripts: &'static str,
     exclude_classes: &'static str,
+    max_path_length: &'static str,
+    save_as_archive: &'static str,
+    output_format: &'static str,
+    format_folders: &'static str,
+    preview: &'static str,
+    preview_title: &'static str,
+    excluded: &'static str,
+    reports: &'static str,
+    report_dependencies: &'static str,
+    report_audit: &'static str,
+    report_duplicates: &'static str,
+    report_assets: &'static str,
+    report_sounds: &'static str,
+    report_remotes: &'static str,
+    report_packages: &'static str,
+    report_dead_scripts: &'static str,
+    dedup_scripts: &'static str,
+    dedup_packages: &'static str,
+    report_syntax: &'static str,
+    report_syntax_ast: &'static str,
+    report_stats: &'static str,
+    statistics: &'static str,
+    stats_services: &'static str,
+    stats_scripts: &'static str,
+    stats_classes: &'static str,
+    stats_largest: &'static str,
+    stats_deepest: &'static str,
+    stats_property_types: &'static str,
+    stats_sizes: &'static str,
+    search: &'static str,
+    search_hint: &'static str,
+    search_regex: &'static str,
+    search_ignore_case: &'static str,
+    search_title: &'static str,
+    normalize_scripts: &'static str,
+    line_endings: &'static str,
+    keep: &'static str,
+    strip_bom: &'static str,
+    indentation: &'static str,
+    indent_tabs: &'static str,
+    indent_spaces: &'static str,
+    trim_trailing_whitespace: &'static str,
+    formatter: &'static str,
+    exports: &'static str,
+    export_geometry: &'static str,
+    export_terrain: &'static str,
+    export_mesh_data: &'static str,
+    export_images: &'static str,
+    export_animations: &'static str,
+    export_animation_bvh: &'static str,
+    export_gui: &'static str,
+    export_lighting: &'static str,
+    export_map: &'static str,
 }
 
 const RU: Translations = Translations {
@@ -74,6 +173,59 @@ const RU: Translations = Translations {
     exclude_workspace: "Исключить Workspace",
     exclude_scripts: "Исключить скрипты",
     exclude_classes: "Исключить классы (через запятую):",
+    max_path_length: "Макс. длина пути:",
+    save_as_archive: "Сохранить в архив (.zip / .tar.zst)",
+    output_format: "Формат вывода:",
+    format_folders: "Папки",
+    preview: "Предпросмотр",
+    preview_title: "План извлечения",
+    excluded: "пропущено",
+    reports: "Отчёты",
+    report_dependencies: "Граф зависимостей (require)",
+    report_audit: "Аудит безопасности скриптов",
+    report_duplicates: "Поиск одинаковых скриптов",
+    report_assets: "Список ассетов (rbxassetid)",
+    report_sounds: "Звуки с позициями (CSV/JSON)",
+    report_remotes: "Каталог Remote/Bindable",
+    report_packages: "Пакеты и LinkedSource",
+    report_dead_scripts: "Неиспользуемые скрипты",
+    dedup_scripts: "Хранить одинаковые скрипты один раз (sources/)",
+    dedup_packages: "Извлекать каждый пакет один раз (packages/)",
+    report_syntax: "Проверка синтаксиса Luau",
+    report_syntax_ast: "Сохранять AST скриптов (JSON)",
+    report_stats: "Статистика места",
+    statistics: "Статистика",
+    stats_services: "Сервисы",
+    stats_scripts: "Скрипты",
+    stats_classes: "Классы",
+    stats_largest: "Крупнейшие поддеревья",
+    stats_deepest: "Самые глубокие пути",
+    stats_property_types: "Типы свойств",
+    stats_sizes: "Размеры данных",
+    search: "Поиск",
+    search_hint: "Текст или регулярное выражение...",
+    search_regex: "Регулярное выражение",
+    search_ignore_case: "Без учёта регистра",
+    search_title: "Результаты поиска",
+    normalize_scripts: "Нормализация скриптов",
+    line_endings: "Переводы строк:",
+    keep: "Как есть",
+    strip_bom: "Удалять BOM",
+    indentation: "Отступы:",
+    indent_tabs: "Табуляция",
+    indent_spaces: "Пробелы",
+    trim_trailing_whitespace: "Удалять пробелы в конце строк",
+    formatter: "Форматтер (команда):",
+    exports: "Экспорт",
+    export_geometry: "Геометрия Workspace (glTF)",
+    export_terrain: "Ландшафт (воксели, карта высот)",
+    export_mesh_data: "Меши Union и MeshPart (OBJ рядом с properties.json)",
+    export_images: "Встроенные изображения (PNG/JPEG/TGA/DDS/KTX рядом с properties.json)",
+    export_animations: "Анимации KeyframeSequence (JSON)",
+    export_animation_bvh: "Также BVH (30 кадров/с)",
+    export_gui: "Превью ScreenGui/SurfaceGui (HTML)",
+    export_lighting: "Пресет освещения (Lighting и эффекты)",
+    export_map: "Карта Workspace сверху (PNG)",
 };
 
 const EN: Translations = Translations {
@@ -91,27 +243,77 @@ const EN: Translations = Translations {
     exclude_workspace: "Exclude Workspace",
     exclude_scripts: "Exclude Scripts",
     exclude_classes: "Exclude Classes (comma separated):",
+    max_path_length: "Max path length:",
+    save_as_archive: "Save as archive (.zip / .tar.zst)",
+    output_format: "Output format:",
+    format_folders: "Folders",
+    preview: "Preview",
+    preview_title: "Extraction Plan",
+    excluded: "skipped",
+    reports: "Reports",
+    report_dependencies: "Dependency graph (require)",
+    report_audit: "Script security audit",
+    report_duplicates: "Duplicate scripts",
+    report_assets: "Asset ID inventory",
+    report_sounds: "Sound manifest with positions (CSV/JSON)",
+    report_remotes: "Remote/Bindable catalogue",
+    report_packages: "Packages and LinkedSource scripts",
+    report_dead_scripts: "Dead scripts lint",
+    dedup_scripts: "Store identical scripts once (sources/)",
+    dedup_packages: "Extract each package once (packages/)",
+    report_syntax: "Luau syntax check",
+    report_syntax_ast: "Save script ASTs (JSON)",
+    report_stats: "Place statistics",
+    statistics: "Statistics",
+    stats_services: "Services",
+    stats_scripts: "Scripts",
+    stats_classes: "Classes",
+    stats_largest: "Largest subtrees",
+    stats_deepest: "Deepest paths",
+    stats_property_types: "Property types",
+    stats_sizes: "Data sizes",
+    search: "Search",
+    search_hint: "Text or regex...",
+    search_regex: "Regex",
+    search_ignore_case: "Ignore case",
+    search_title: "Search Results",
+    normalize_scripts: "Script normalization",
+    line_endings: "Line endings:",
+    keep: "Keep",
+    strip_bom: "Strip BOM",
+    indentation: "Indentation:",
+    indent_tabs: "Tabs",
+    indent_spaces: "Spaces",
+    trim_trailing_whitespace: "Trim trailing whitespace",
+    formatter: "Formatter command:",
+    exports: "Exports",
+    export_geometry: "Workspace geometry (glTF)",
+    export_terrain: "Terrain (voxels, heightmap)",
+    export_mesh_data: "Union and MeshPart meshes (OBJ next to properties.json)",
+    export_images: "Embedded images (PNG/JPEG/TGA/DDS/KTX next to properties.json)",
+    export_animations: "KeyframeSequence animations (JSON)",
+    export_animation_bvh: "Also BVH (30 fps)",
+    export_gui: "ScreenGui/SurfaceGui preview (HTML)",
+    export_lighting: "Lighting preset (Lighting and effects)",
+    export_map: "Top-down map of Workspace (PNG)",
 };
 
 #[derive(Clone, PartialEq)]
 enum Status {
     Idle,
     Processing { progress: f32, message: String },
-    Done(String),
+    Preview(Arc<Plan>),
+    Search(Arc<SearchResults>),
+    Done(String, Option<Arc<PlaceStats>>),
     Error(String),
 }
 
 enum LogMessage {
     Progress(f32, String),
     Error(String),
-    Finished(String),
-}
-
-#[derive(Clone)]
-struct ExtractionSettings {
-    exclude_workspace: bool,
-    exclude_scripts: bool,
-    exclude_classes: Vec<String>,
+    Planned(Plan),
+    Searched(SearchResults),
+    Finished(String, Option<PlaceStats>),
 }
 
 struct MyApp {
@@ -124,6 +326,17 @@ struct MyApp {
     exclude_workspace: bool,
     exclude_scripts: bool,
     exclude_classes_input: String,
+    max_path_length: usize,
+    output_format: OutputFormat,
+    dedup_scripts: bool,
+    dedup_packages: bool,
+    normalize: NormalizeSettings,
+    formatter_input: String,
+    reports: ReportSettings,
+    exports: ExportSettings,
+    search_pattern: String,
+    search_regex: bool,
+    search_ignore_case: bool,
 }
 
 impl MyApp {
@@ -145,6 +358,17 @@ impl MyApp {
             exclude_workspace: false,
             exclude_scripts: false,
             exclude_classes_input: String::new(),
+            max_path_length: DEFAULT_MAX_PATH_LENGTH,
+            output_format: OutputFormat::Folders,
+            dedup_scripts: false,
+            dedup_packages: false,
+            normalize: NormalizeSettings::default(),
+            formatter_input: String::new(),
+            reports: ReportSettings::default(),
+            exports: ExportSettings::default(),
+            search_pattern: String::new(),
+            search_regex: false,
+            search_ignore_case: true,
         }
     }
 
@@ -155,6 +379,27 @@ impl MyApp {
         }
     }
 
+    fn settings(&self) -> ExtractionSettings {
+        ExtractionSettings {
+            exclude_workspace: self.exclude_workspace,
+            exclude_scripts: self.exclude_scripts,
+            exclude_classes: self.exclude_classes_input.split(',')
+                .map(|s| s.trim().to_lowercase())
+                .filter(|s| !s.is_empty())
+                .collect(),
+            max_path_length: self.max_path_length,
+            format: self.output_format,
+            dedup_scripts: self.dedup_scripts,
+            dedup_packages: self.dedup_packages,
+            normalize: NormalizeSettings {
+                formatter: Some(self.formatter_input.trim().to_string()).filter(|f| !f.is_empty()),
+                ..self.normalize.clone()
+            },
+            reports: self.reports.clone(),
+            exports: self.exports.clone(),
+        }
+    }
+
     fn start_processing(&mut self, ctx: egui::Context) {
         let input = match &self.input_path {
             Some(p) => p.clone(),
@@ -170,26 +415,203 @@ impl MyApp {
         self.status = Status::Processing { progress: 0.0, message: self.t().processing.to_string() };
 
         let worker_ctx = ctx.clone();
-        let settings = ExtractionSettings {
-            exclude_workspace: self.exclude_workspace,
-            exclude_scripts: self.exclude_scripts,
-            exclude_classes: self.exclude_classes_input.split(',')
-                .map(|s| s.trim().to_lowercase())
-                .filter(|s| !s.is_empty())
-                .collect(),
+        let settings = self.settings();
+
+        thread::spawn(move || {
+            let on_progress = |progress: f32, message: String| {
+                if tx.send(LogMessage::Progress(progress, message)).is_ok() {
+                    worker_ctx.request_repaint();
+                }
+            };
+            match catch_panic(|| process_file_with_progress(&input, &output, &settings, &on_progress)) {
+                Ok(summary) => {
+                    let _ = tx.send(LogMessage::Finished(summary.message(), summary.stats));
+                }
+                Err(e) => {
+                    let _ = tx.send(LogMessage::Error(e.to_string()));
+                }
+            }
+            worker_ctx.request_repaint();
+        });
+    }
+
+    fn start_preview(&mut self, ctx: egui::Context) {
+        let input = match &self.input_path {
+            Some(p) => p.clone(),
+            None => return,
+        };
+        let output = match &self.output_path {
+            Some(p) => p.clone(),
+            None => return,
         };
 
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.status = Status::Processing { progress: 0.0, message: self.t().processing.to_string() };
+
+        let settings = self.settings();
+
         thread::spawn(move || {
-            match process_file_with_progress(&input, &output, &tx, worker_ctx, settings) {
-                Ok(count) => {
-                    let _ = tx.send(LogMessage::Finished(format!("{} objects", count)));
+            match catch_panic(|| build_plan(&input, &output, &settings)) {
+                Ok(plan) => {
+                    let _ = tx.send(LogMessage::Planned(plan));
                 }
                 Err(e) => {
                     let _ = tx.send(LogMessage::Error(e.to_string()));
                 }
             }
+            ctx.request_repaint();
         });
     }
+
+    fn start_search(&mut self, ctx: egui::Context) {
+        let input = match &self.input_path {
+            Some(p) => p.clone(),
+            None => return,
+        };
+        if self.search_pattern.is_empty() {
+            return;
+        }
+
+        let (tx, rx) = channel();
+        self.rx = Some(rx);
+        self.status = Status::Processing { progress: 0.0, message: self.t().processing.to_string() };
+
+        let settings = self.settings();
+        let mut query = SearchQuery::new(self.search_pattern.clone());
+        query.fixed_string = !self.search_regex;
+        query.ignore_case = self.search_ignore_case;
+
+        thread::spawn(move || {
+            match catch_panic(|| dom::Dom::load(&input, &settings).and_then(|dom| search::search(&dom, &query))) {
+                Ok(results) => {
+                    let _ = tx.send(LogMessage::Searched(results));
+                }
+                Err(e) => {
+                    let _ = tx.send(LogMessage::Error(e.to_string()));
+                }
+            }
+            ctx.request_repaint();
+        });
+    }
+
+    fn show_search_bar(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let search_label = self.t().search;
+        let hint = self.t().search_hint;
+        let regex_label = self.t().search_regex;
+        let ignore_case_label = self.t().search_ignore_case;
+        let mut submit = false;
+        ui.horizontal(|ui| {
+            let edit = ui.add(egui::TextEdit::singleline(&mut self.search_pattern).hint_text(hint).desired_width(ui.available_width() - 90.0));
+            submit |= edit.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let can_search = self.input_path.is_some() && !self.search_pattern.is_empty();
+            submit |= ui.add_enabled(can_search, egui::Button::new(format!("🔎 {}", search_label))).clicked();
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.search_regex, regex_label);
+            ui.checkbox(&mut self.search_ignore_case, ignore_case_label);
+        });
+        if submit {
+            self.start_search(ctx.clone());
+        }
+    }
+}
+
+/// Runs a worker's job, turning a panic into an error so the window doesn't wait on "Processing" forever.
+fn catch_panic<T>(job: impl FnOnce() -> anyhow::Result<T>) -> anyhow::Result<T> {
+    panic::catch_unwind(panic::AssertUnwindSafe(job)).unwrap_or_else(|payload| {
+        let message = payload.downcast_ref::<&str>().map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown error".to_string());
+        Err(anyhow::anyhow!("Internal error: {}", message))
+    })
+}
+
+fn show_plan_node(ui: &mut egui::Ui, node: &PlanNode, excluded_label: &str) {
+    let bytes: u64 = node.files.iter().map(|f| f.bytes).sum();
+    egui::CollapsingHeader::new(format!("📁 {}  ({})", node.folder, format_bytes(bytes)))
+        .id_salt(&node.path)
+        .show(ui, |ui| {
+            for file in &node.files {
+                ui.label(egui::RichText::new(format!("📄 {}  ({})", file.name, format_bytes(file.bytes))).weak());
+            }
+            for excluded in &node.excluded {
+                show_excluded(ui, excluded, excluded_label);
+            }
+            for child in &node.children {
+                show_plan_node(ui, child, excluded_label);
+            }
+        });
+}
+
+fn show_excluded(ui: &mut egui::Ui, excluded: &ExcludedSubtree, excluded_label: &str) {
+    ui.label(egui::RichText::new(format!(
+        "🚫 {} [{}] — {}: {} ({})",
+        excluded.name, excluded.class, excluded_label, excluded.reason, excluded.instances
+    )).color(egui::Color32::from_rgb(200, 120, 60)));
+}
+
+fn show_search_hit(ui: &mut egui::Ui, hit: &search::SearchHit) {
+    ui.label(egui::RichText::new(search::hit_location(hit)).strong());
+    for line in &hit.before {
+        ui.label(egui::RichText::new(format!("{:>5}  {}", line.line, line.text)).monospace().weak());
+    }
+    ui.label(egui::RichText::new(format!("{:>5}  {}", hit.line, hit.text)).monospace());
+    for line in &hit.after {
+        ui.label(egui::RichText::new(format!("{:>5}  {}", line.line, line.text)).monospace().weak());
+    }
+    ui.add_space(4.0);
+}
+
+fn stats_grid(ui: &mut egui::Ui, id: &str, rows: Vec<Vec<String>>) {
+    egui::Grid::new(id).striped(true).num_columns(rows.first().map_or(0, Vec::len)).show(ui, |ui| {
+        for row in rows {
+            for cell in row {
+                ui.label(cell);
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn show_stats(ui: &mut egui::Ui, stats: &PlaceStats, t: &Translations) {
+    egui::CollapsingHeader::new(format!("📊 {}", t.statistics)).default_open(true).show(ui, |ui| {
+        ui.label(analysis::stats::summary(stats));
+        egui::CollapsingHeader::new(t.stats_services).default_open(true).show(ui, |ui| {
+            stats_grid(ui, "stats_services", stats.services.iter()
+                .map(|s| vec![s.name.clone(), s.class.clone(), s.instances.to_string()])
+                .collect());
+        });
+        egui::CollapsingHeader::new(t.stats_scripts).default_open(true).show(ui, |ui| {
+            stats_grid(ui, "stats_scripts", stats.scripts.iter()
+                .map(|s| vec![s.kind.clone(), s.count.to_string(), format!("{} (~{:.0})", s.lines, s.average_lines), format_bytes(s.bytes)])
+                .collect());
+        });
+        egui::CollapsingHeader::new(t.stats_sizes).show(ui, |ui| {
+            let bytes = &stats.bytes;
+            stats_grid(ui, "stats_sizes", vec![
+                vec!["Source".to_string(), format_bytes(bytes.scripts)],
+                vec![format!("BinaryString × {}", bytes.binary_string_count), format_bytes(bytes.binary_strings)],
+                vec![format!("SharedString × {}", bytes.shared_string_count), format_bytes(bytes.shared_strings)],
+            ]);
+        });
+        egui::CollapsingHeader::new(t.stats_classes).show(ui, |ui| {
+            stats_grid(ui, "stats_classes", stats.classes.iter().map(|c| vec![c.name.clone(), c.count.to_string()]).collect());
+        });
+        egui::CollapsingHeader::new(t.stats_largest).show(ui, |ui| {
+            stats_grid(ui, "stats_largest", stats.largest_subtrees.iter()
+                .map(|p| vec![p.path.clone(), p.class.clone(), p.value.to_string()])
+                .collect());
+        });
+        egui::CollapsingHeader::new(t.stats_deepest).show(ui, |ui| {
+            stats_grid(ui, "stats_deepest", stats.deepest_paths.iter()
+                .map(|p| vec![p.path.clone(), p.class.clone(), p.value.to_string()])
+                .collect());
+        });
+        egui::CollapsingHeader::new(t.stats_property_types).show(ui, |ui| {
+            stats_grid(ui, "stats_property_types", stats.property_types.iter().map(|c| vec![c.name.clone(), c.count.to_string()]).collect());
+        });
+    });
 }
 
 impl eframe::App for MyApp {
@@ -212,8 +634,16 @@ impl eframe::App for MyApp {
                         self.status = Status::Error(text);
                         finished = true;
                     }
-                    LogMessage::Finished(text) => {
-                        self.status = Status::Done(text);
+                    LogMessage::Planned(plan) => {
+                        self.status = Status::Preview(Arc::new(plan));
+                        finished = true;
+                    }
+                    LogMessage::Searched(results) => {
+                        self.status = Status::Search(Arc::new(results));
+                        finished = true;
+                    }
+                    LogMessage::Finished(text, stats) => {
+                        self.status = Status::Done(text, stats.map(Arc::new));
                         finished = true;
                     }
                 }
@@ -261,9 +691,7 @@ impl eframe::App for MyApp {
                                         .pick_file() {
                                         self.input_path = Some(path.clone());
                                         if self.output_path.is_none() {
-                                            let mut out = path.clone();
-                                            out.set_extension("");
-                                            self.output_path = Some(PathBuf::from(format!("{}_extracted", out.display())));
+                                            self.output_path = Some(cli::default_output(&path));
                                         }
                                     }
                                 }
@@ -282,6 +710,20 @@ impl eframe::App for MyApp {
                             let path_str = self.output_path.as_ref().map_or("...".to_string(), |p| p.to_string_lossy().to_string());
                             ui.add(egui::Label::new(egui::RichText::new(path_str).monospace()).truncate());
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button("📦").on_hover_text(self.t().save_as_archive).clicked() {
+                                    let file_name = self.output_path.as_ref()
+                                        .and_then(|p| p.file_name())
+                                        .map(|n| format!("{}.zip", n.to_string_lossy()))
+                                        .unwrap_or_else(|| "extracted.zip".to_string());
+                                    if let Some(path) = rfd::FileDialog::new()
+                                        .add_filter("ZIP", &["zip"])
+                                        .add_filter("tar.zst", &["tar.zst", "tzst"])
+                                        .add_filter("tar", &["tar"])
+                                        .set_file_name(file_name)
+                                        .save_file() {
+                                        self.output_path = Some(path);
+                                    }
+                                }
                                 if ui.button("📂").clicked() {
                                     if let Some(path) = rfd::FileDialog::new().pick_folder() {
                                         self.output_path = Some(path);
@@ -295,6 +737,42 @@ impl eframe::App for MyApp {
                         let ex_ws_label = self.t().exclude_workspace;
                         let ex_sc_label = self.t().exclude_scripts;
                         let ex_cl_label = self.t().exclude_classes;
+                        let max_path_label = self.t().max_path_length;
+                        let format_label = self.t().output_format;
+                        let folders_label = self.t().format_folders;
+                        let reports_label = self.t().reports;
+                        let report_deps_label = self.t().report_dependencies;
+                        let report_audit_label = self.t().report_audit;
+                        let report_dups_label = self.t().report_duplicates;
+                        let report_assets_label = self.t().report_assets;
+                        let report_sounds_label = self.t().report_sounds;
+                        let report_remotes_label = self.t().report_remotes;
+                        let report_packages_label = self.t().report_packages;
+                        let report_dead_label = self.t().report_dead_scripts;
+                        let dedup_label = self.t().dedup_scripts;
+                        let dedup_packages_label = self.t().dedup_packages;
+                        let report_syntax_label = self.t().report_syntax;
+                        let report_ast_label = self.t().report_syntax_ast;
+                        let report_stats_label = self.t().report_stats;
+                        let normalize_label = self.t().normalize_scripts;
+                        let line_endings_label = self.t().line_endings;
+                        let keep_label = self.t().keep;
+                        let strip_bom_label = self.t().strip_bom;
+                        let indentation_label = self.t().indentation;
+                        let tabs_label = self.t().indent_tabs;
+                        let spaces_label = self.t().indent_spaces;
+                        let trim_label = self.t().trim_trailing_whitespace;
+                        let formatter_label = self.t().formatter;
+                        let exports_label = self.t().exports;
+                        let export_geometry_label = self.t().export_geometry;
+                        let export_terrain_label = self.t().export_terrain;
+                        let export_mesh_data_label = self.t().export_mesh_data;
+                        let export_images_label = self.t().export_images;
+                        let export_animations_label = self.t().export_animations;
+                        let export_bvh_label = self.t().export_animation_bvh;
+                        let export_gui_label = self.t().export_gui;
+                        let export_lighting_label = self.t().export_lighting;
+                        let export_map_label = self.t().export_map;
 
                         ui.collapsing(settings_label, |ui| {
                             ui.checkbox(&mut self.exclude_workspace, ex_ws_label);
@@ -304,6 +782,102 @@ impl eframe::App for MyApp {
                             ui.add(egui::TextEdit::singleline(&mut self.exclude_classes_input)
                                 .hint_text("Part, MeshPart, Decal...")
                                 .desired_width(f32::INFINITY));
+                            ui.add_space(5.0);
+                            ui.horizontal(|ui| {
+                                ui.label(max_path_label);
+                                ui.add(egui::DragValue::new(&mut self.max_path_length).range(64..=4096));
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(format_label);
+                                egui::ComboBox::from_id_salt("format_cb")
+                                    .selected_text(match self.output_format {
+                                        OutputFormat::Folders => folders_label,
+                                        OutputFormat::Json => "JSON",
+                                        OutputFormat::Ndjson => "NDJSON",
+                                        OutputFormat::Sqlite => "SQLite",
+                                    })
+                                    .show_ui(ui, |ui| {
+                                        ui.selectable_value(&mut self.output_format, OutputFormat::Folders, folders_label);
+                                        ui.selectable_value(&mut self.output_format, OutputFormat::Json, "JSON");
+                                        ui.selectable_value(&mut self.output_format, OutputFormat::Ndjson, "NDJSON");
+                                        ui.selectable_value(&mut self.output_format, OutputFormat::Sqlite, "SQLite");
+                                    });
+                            });
+                            let folders = self.output_format == OutputFormat::Folders;
+                            ui.add_enabled(folders, egui::Checkbox::new(&mut self.dedup_scripts, dedup_label));
+                            ui.add_enabled(folders, egui::Checkbox::new(&mut self.dedup_packages, dedup_packages_label));
+                            ui.add_enabled_ui(folders, |ui| {
+                                ui.collapsing(normalize_label, |ui| {
+                                    let normalize = &mut self.normalize;
+                                    ui.horizontal(|ui| {
+                                        ui.label(line_endings_label);
+                                        egui::ComboBox::from_id_salt("line_endings_cb")
+                                            .selected_text(match normalize.line_endings {
+                                                LineEndings::Keep => keep_label,
+                                                LineEndings::Lf => "LF",
+                                                LineEndings::Crlf => "CRLF",
+                                            })
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(&mut normalize.line_endings, LineEndings::Keep, keep_label);
+                                                ui.selectable_value(&mut normalize.line_endings, LineEndings::Lf, "LF");
+                                                ui.selectable_value(&mut normalize.line_endings, LineEndings::Crlf, "CRLF");
+                                            });
+                                    });
+                                    ui.horizontal(|ui| {
+                                        ui.label(indentation_label);
+                                        egui::ComboBox::from_id_salt("indentation_cb")
+                                            .selected_text(match normalize.indentation {
+                                                Indentation::Keep => keep_label,
+                                                Indentation::Tabs => tabs_label,
+                                                Indentation::Spaces => spaces_label,
+                                            })
+                                            .show_ui(ui, |ui| {
+                                                ui.selectable_value(&mut normalize.indentation, Indentation::Keep, keep_label);
+                                                ui.selectable_value(&mut normalize.indentation, Indentation::Tabs, tabs_label);
+                                                ui.selectable_value(&mut normalize.indentation, Indentation::Spaces, spaces_label);
+                                            });
+                                        let reindent = normalize.indentation != Indentation::Keep;
+                                        ui.add_enabled(reindent, egui::DragValue::new(&mut normalize.indent_width).range(1..=8));
+                                    });
+                                    ui.checkbox(&mut normalize.strip_bom, strip_bom_label);
+                                    ui.checkbox(&mut normalize.trim_trailing_whitespace, trim_label);
+                                    ui.label(formatter_label);
+                                    ui.add(egui::TextEdit::singleline(&mut self.formatter_input)
+                                        .hint_text("stylua -")
+                                        .desired_width(f32::INFINITY));
+                                });
+                            });
+                        });
+
+                        ui.collapsing(reports_label, |ui| {
+                            ui.checkbox(&mut self.reports.dependencies, report_deps_label);
+                            ui.checkbox(&mut self.reports.audit, report_audit_label);
+                            ui.checkbox(&mut self.reports.duplicates, report_dups_label);
+                            ui.checkbox(&mut self.reports.assets, report_assets_label);
+                            ui.checkbox(&mut self.reports.sounds, report_sounds_label);
+                            ui.checkbox(&mut self.reports.remotes, report_remotes_label);
+                            ui.checkbox(&mut self.reports.packages, report_packages_label);
+                            ui.checkbox(&mut self.reports.dead_scripts, report_dead_label);
+                            ui.checkbox(&mut self.reports.syntax, report_syntax_label);
+                            ui.add_enabled(self.reports.syntax, egui::Checkbox::new(&mut self.reports.syntax_ast, report_ast_label));
+                            ui.checkbox(&mut self.reports.stats, report_stats_label);
+                        });
+
+                        ui.collapsing(exports_label, |ui| {
+                            ui.checkbox(&mut self.exports.geometry, export_geometry_label);
+                            ui.checkbox(&mut self.exports.terrain, export_terrain_label);
+                            let folders = self.output_format == OutputFormat::Folders;
+                            ui.add_enabled(folders, egui::Checkbox::new(&mut self.exports.mesh_data, export_mesh_data_label));
+                            ui.add_enabled(folders, egui::Checkbox::new(&mut self.exports.images, export_images_label));
+                            ui.checkbox(&mut self.exports.animations, export_animations_label);
+                            ui.add_enabled(self.exports.animations, egui::Checkbox::new(&mut self.exports.animation_bvh, export_bvh_label));
+                            ui.checkbox(&mut self.exports.gui, export_gui_label);
+                            ui.checkbox(&mut self.exports.lighting, export_lighting_label);
+                            ui.checkbox(&mut self.exports.map, export_map_label);
+                        });
+
+                        ui.collapsing(self.t().search, |ui| {
+                            self.show_search_bar(ui, ctx);
                         });
 
                         ui.add_space(20.0);
@@ -316,9 +890,54 @@ impl eframe::App for MyApp {
                             if ui.add_enabled(can_start, btn).clicked() {
                                 self.start_processing(ctx.clone());
                             }
+                            ui.add_space(5.0);
+                            if ui.add_enabled(can_start, egui::Button::new(format!("🔍 {}", self.t().preview))).clicked() {
+                                self.start_preview(ctx.clone());
+                            }
                         });
                     });
                 }
+                Status::Preview(plan) => {
+                    let plan = plan.clone();
+                    ui.heading(self.t().preview_title);
+                    ui.label(plan::summary(&plan));
+                    ui.horizontal(|ui| {
+                        if ui.button(self.t().reset).clicked() {
+                            next_status = Some(Status::Idle);
+                        }
+                        if ui.button(self.t().start).clicked() {
+                            self.start_processing(ctx.clone());
+                        }
+                    });
+                    ui.separator();
+                    let excluded_label = self.t().excluded;
+                    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                        for file in &plan.root_files {
+                            ui.label(egui::RichText::new(format!("📄 {}  ({})", file.name, format_bytes(file.bytes))).weak());
+                        }
+                        for excluded in &plan.excluded {
+                            show_excluded(ui, excluded, excluded_label);
+                        }
+                        for node in &plan.roots {
+                            show_plan_node(ui, node, excluded_label);
+                        }
+                    });
+                }
+                Status::Search(results) => {
+                    let results = results.clone();
+                    ui.heading(self.t().search_title);
+                    self.show_search_bar(ui, ctx);
+                    ui.label(search::summary(&results));
+                    if ui.button(self.t().reset).clicked() {
+                        next_status = Some(Status::Idle);
+                    }
+                    ui.separator();
+                    egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                        for hit in &results.hits {
+                            show_search_hit(ui, hit);
+                        }
+                    });
+                }
                 Status::Processing { progress, message } => {
                     ui.vertical_centered(|ui| {
                         ui.add_space(80.0);
@@ -332,7 +951,8 @@ impl eframe::App for MyApp {
                         ui.label(message);
                     });
                 }
-                Status::Done(msg) => {
+                Status::Done(msg, stats) => {
+                    let stats = stats.clone();
                     ui.vertical_centered(|ui| {
                         ui.add_space(60.0);
                         let (rect, _) = ui.allocate_at_least(egui::vec2(80.0, 80.0), egui::Sense::hover());
@@ -356,6 +976,12 @@ impl eframe::App for MyApp {
                             next_status = Some(Status::Idle);
                         }
                     });
+                    if let Some(stats) = stats {
+                        ui.add_space(10.0);
+                        egui::ScrollArea::vertical().auto_shrink(false).show(ui, |ui| {
+                            show_stats(ui, &stats, self.t());
+                        });
+                    }
                 }
                 Status::Error(msg) => {
                     ui.vertical_centered(|ui| {
@@ -389,11 +1015,9 @@ impl eframe::App for MyApp {
                 let dropped = ctx.input(|i| i.raw.dropped_files.clone());
                 if let Some(file) = dropped.first() {
                     if let Some(path) = &file.path {
-                        if path.extension().map_or(false, |e| e == "rbxlx") {
+                        if path.extension().is_some_and(|e| e == "rbxlx") {
                             self.input_path = Some(path.clone());
-                            let mut out = path.clone();
-                            out.set_extension("");
-                            self.output_path = Some(PathBuf::from(format!("{}_extracted", out.display())));
+                            self.output_path = Some(cli::default_output(path));
                         }
                     }
                 }
@@ -405,158 +1029,3 @@ impl eframe::App for MyApp {
         }
     }
 }
-
-fn process_file_with_progress(input: &Path, output: &Path, tx: &Sender<LogMessage>, ctx: egui::Context, settings: ExtractionSettings) -> anyhow::Result<usize> {
-    let text = fs::read_to_string(input)?;
-    let doc = roxmltree::Document::parse(&text)?;
-    
-    let top_items: Vec<_> = doc.root().children().filter(|n| n.has_tag_name("Item")).collect();
-    let roblox_node = doc.root().children().find(|n| n.has_tag_name("roblox"));
-    let mut all_top_items = top_items;
-    if let Some(r) = roblox_node {
-        all_top_items.extend(r.children().filter(|n| n.has_tag_name("Item")));
-    }
-
-    let mut total_items = 0;
-    for node in &all_top_items {
-        total_items += count_items_recursive(*node, &settings);
-    }
-
-    if total_items == 0 {
-        return Ok(0);
-    }
-
-    fs::create_dir_all(output)?;
-    let current_count = Arc::new(AtomicUsize::new(0));
-    
-    all_top_items.into_par_iter().try_for_each(|node| {
-        process_item_recursive_parallel(node, output, &current_count, total_items, tx, &ctx, &settings)
-    })?;
-    
-    ctx.request_repaint();
-    Ok(total_items)
-}
-
-fn count_items_recursive(node: roxmltree::Node, settings: &ExtractionSettings) -> usize {
-    if should_exclude_node(node, settings) {
-        return 0;
-    }
-    
-    let mut count = 1;
-    for child in node.children().filter(|n| n.has_tag_name("Item")) {
-        count += count_items_recursive(child, settings);
-    }
-    count
-}
-
-fn should_exclude_node(node: roxmltree::Node, settings: &ExtractionSettings) -> bool {
-    let class_name = node.attribute("class").unwrap_or("Unknown");
-    
-    if settings.exclude_classes.contains(&class_name.to_lowercase()) {
-        return true;
-    }
-
-    if settings.exclude_scripts {
-        if class_name == "Script" || class_name == "LocalScript" || class_name == "ModuleScript" {
-            return true;
-        }
-    }
-
-    if settings.exclude_workspace {
-        if let Some(props_node) = node.children().find(|n| n.has_tag_name("Properties")) {
-            for prop in props_node.children() {
-                if prop.attribute("name") == Some("Name") {
-                    if prop.text() == Some("Workspace") {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
-
-    false
-}
-
-fn process_item_recursive_parallel(
-    node: roxmltree::Node, 
-    parent_path: &Path, 
-    current: &Arc<AtomicUsize>, 
-    total: usize, 
-    tx: &Sender<LogMessage>,
-    ctx: &egui::Context,
-    settings: &ExtractionSettings
-) -> anyhow::Result<()> {
-    if should_exclude_node(node, settings) {
-        return Ok(());
-    }
-
-    let count = current.fetch_add(1, Ordering::SeqCst) + 1;
-    
-    if count % 20 == 0 || count == total {
-        let progress = count as f32 / total as f32;
-        if tx.send(LogMessage::Progress(progress, format!("{} / {}", count, total))).is_ok() {
-            ctx.request_repaint();
-        }
-    }
-
-    let class_name = node.attribute("class").unwrap_or("Unknown");
-    let mut name = class_name.to_string();
-    let mut source_code = None;
-    let mut properties = serde_json::Map::new();
-    
-    properties.insert("ClassName".to_string(), serde_json::Value::String(class_name.to_string()));
-
-    if let Some(props_node) = node.children().find(|n| n.has_tag_name("Properties")) {
-        for prop in props_node.children() {
-            if !prop.is_element() { continue; }
-            let prop_name = prop.attribute("name").unwrap_or("Unknown");
-            if prop_name == "Source" {
-                source_code = prop.text().map(|s| s.to_string());
-                continue;
-            }
-            if prop_name == "Name" {
-                if let Some(text) = prop.text() {
-                    name = text.to_string();
-                }
-            }
-            if let Some(text) = prop.text() {
-                properties.insert(prop_name.to_string(), serde_json::Value::String(text.to_string()));
-            }
-        }
-    }
-    
-    let safe_name = sanitize_filename::sanitize(&name);
-    let folder_name = if safe_name.to_lowercase() == class_name.to_lowercase() {
-        safe_name
-    } else {
-        format!("{} [{}]", safe_name, class_name)
-    };
-    let mut target_dir = parent_path.join(&folder_name);
-    
-    let mut i = 1;
-    while target_dir.exists() {
-        target_dir = parent_path.join(format!("{} ({})", folder_name, i));
-        i += 1;
-    }
-    
-    fs::create_dir_all(&target_dir)?;
-    
-    let props_path = target_dir.join("properties.json");
-    let mut writer = BufWriter::new(fs::File::create(props_path)?);
-    serde_json::to_writer_pretty(&mut writer, &properties)?;
-    writer.flush()?;
-    
-    if let Some(source) = source_code {
-        let mut s_writer = BufWriter::new(fs::File::create(target_dir.join("script.lua"))?);
-        s_writer.write_all(source.as_bytes())?;
-        s_writer.flush()?;
-    }
-    
-    let children: Vec<_> = node.children().filter(|n| n.has_tag_name("Item")).collect();
-    
-    children.into_par_iter().try_for_each(|child| {
-        process_item_recursive_parallel(child, &target_dir, current, total, tx, ctx, settings)
-    })?;
-    
-    Ok(())
-}
@@ -0,0 +1,320 @@
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+/// Written at the output root when any normalization is enabled; lists the settings and how to undo them
+/// with [`restore_folder`].
+pub const NORMALIZATION_FILE: &str = "normalization.json";
+pub const DEFAULT_INDENT_WIDTH: usize = 4;
+
+const BOM: char = '\u{feff}';
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndings {
+    Keep,
+    Lf,
+    Crlf,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Indentation {
+    Keep,
+    Tabs,
+    Spaces,
+}
+
+/// How script sources are rewritten before they are saved as `script.lua`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NormalizeSettings {
+    pub line_endings: LineEndings,
+    pub strip_bom: bool,
+    /// Converts leading whitespace; `indent_width` spaces make one tab.
+    pub indentation: Indentation,
+    pub indent_width: usize,
+    pub trim_trailing_whitespace: bool,
+    /// A formatter command such as `stylua -` that reads a source on stdin and prints the result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formatter: Option<String>,
+}
+
+impl Default for NormalizeSettings {
+    fn default() -> Self {
+        Self {
+            line_endings: LineEndings::Keep,
+            strip_bom: false,
+            indentation: Indentation::Keep,
+            indent_width: DEFAULT_INDENT_WIDTH,
+            trim_trailing_whitespace: false,
+            formatter: None,
+        }
+    }
+}
+
+impl NormalizeSettings {
+    pub fn is_enabled(&self) -> bool {
+        self.line_endings != LineEndings::Keep
+            || self.strip_bom
+            || self.indentation != Indentation::Keep
+            || self.trim_trailing_whitespace
+            || self.formatter.is_some()
+    }
+}
+
+/// Line endings a source had before it was normalized.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceLineEndings {
+    Lf,
+    Crlf,
+    Mixed,
+    None,
+}
+
+/// What [`restore`] needs to turn a normalized `script.lua` back into the original source.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ScriptRecord {
+    pub line_endings: SourceLineEndings,
+    pub bom: bool,
+    /// The untouched source, kept only when the changes can not be undone from the fields above
+    /// (trailing whitespace, indentation, formatting or mixed line endings).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct NormalizationManifest {
+    pub settings: NormalizeSettings,
+    /// Keyed by the script file relative to the output root; unchanged scripts are not listed.
+    pub scripts: BTreeMap<String, ScriptRecord>,
+    /// Scripts the formatter rejected; they were normalized without it.
+    pub formatter_failures: Vec<String>,
+}
+
+/// Applies [`NormalizeSettings`] to every script written and collects the manifest.
+pub struct Normalizer {
+    settings: NormalizeSettings,
+    scripts: Mutex<BTreeMap<String, ScriptRecord>>,
+    formatter_failures: Mutex<Vec<String>>,
+}
+
+impl Normalizer {
+    pub fn new(settings: NormalizeSettings) -> Self {
+        Self { settings, scripts: Mutex::new(BTreeMap::new()), formatter_failures: Mutex::new(Vec::new()) }
+    }
+
+    /// The normalized text of `source`, which is written to `file`.
+    pub fn apply(&self, file: &Path, source: &str) -> String {
+        let key = file.to_string_lossy().replace('\\', "/");
+        let bom = source.starts_with(BOM);
+        let mut text = if self.settings.strip_bom { source.trim_start_matches(BOM) } else { source }.to_string();
+
+        if let Some(command) = &self.settings.formatter {
+            match run_formatter(command, &text) {
+                Ok(formatted) => text = formatted,
+                Err(_) => self.formatter_failures.lock().unwrap().push(key.clone()),
+            }
+        }
+        let text = self.rewrite_lines(&text);
+        if text == source {
+            return text;
+        }
+
+        let mut record = ScriptRecord { line_endings: detect_line_endings(source), bom, original: None };
+        if restore(&text, &record) != source {
+            record.original = Some(source.to_string());
+        }
+        self.scripts.lock().unwrap().insert(key, record);
+        text
+    }
+
+    fn rewrite_lines(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        for line in text.split_inclusive('\n') {
+            let (content, ending) = match line.strip_suffix("\r\n") {
+                Some(content) => (content, "\r\n"),
+                None => match line.strip_suffix('\n') {
+                    Some(content) => (content, "\n"),
+                    None => (line, ""),
+                },
+            };
+            let content = match self.settings.indentation {
+                Indentation::Keep => content.to_string(),
+                indentation => reindent(content, indentation, self.settings.indent_width.max(1)),
+            };
+            let content = if self.settings.trim_trailing_whitespace { content.trim_end_matches([' ', '\t']) } else { &content };
+            out.push_str(content);
+            out.push_str(match (ending, self.settings.line_endings) {
+                ("", _) => "",
+                (_, LineEndings::Lf) => "\n",
+                (_, LineEndings::Crlf) => "\r\n",
+                (ending, LineEndings::Keep) => ending,
+            });
+        }
+        out
+    }
+
+    pub fn into_manifest(self) -> NormalizationManifest {
+        let mut formatter_failures = self.formatter_failures.into_inner().unwrap();
+        formatter_failures.sort();
+        NormalizationManifest { settings: self.settings, scripts: self.scripts.into_inner().unwrap(), formatter_failures }
+    }
+}
+
+fn detect_line_endings(source: &str) -> SourceLineEndings {
+    let crlf = source.matches("\r\n").count();
+    let lf = source.matches('\n').count() - crlf;
+    match (lf, crlf) {
+        (0, 0) => SourceLineEndings::None,
+        (_, 0) => SourceLineEndings::Lf,
+        (0, _) => SourceLineEndings::Crlf,
+        _ => SourceLineEndings::Mixed,
+    }
+}
+
+/// Undoes the reversible parts of the normalization: line endings and the byte order mark.
+pub fn restore(text: &str, record: &ScriptRecord) -> String {
+    if let Some(original) = &record.original {
+        return original.clone();
+    }
+    let lf = text.replace("\r\n", "\n");
+    let mut out = if record.bom && !lf.starts_with(BOM) { BOM.to_string() } else { String::new() };
+    match record.line_endings {
+        SourceLineEndings::Crlf => out.push_str(&lf.replace('\n', "\r\n")),
+        SourceLineEndings::Lf | SourceLineEndings::None => out.push_str(&lf),
+        SourceLineEndings::Mixed => out.push_str(text),
+    }
+    out
+}
+
+/// Rewrites every script listed in the [`NORMALIZATION_FILE`] of an extracted folder back to its original
+/// source and returns how many there were. Scripts restored from a stored original lose later edits.
+pub fn restore_folder(root: &Path) -> anyhow::Result<usize> {
+    let manifest_path = root.join(NORMALIZATION_FILE);
+    let manifest = fs::read(&manifest_path).with_context(|| format!("reading {}", manifest_path.display()))?;
+    let manifest: NormalizationManifest = serde_json::from_slice(&manifest)?;
+    for (file, record) in &manifest.scripts {
+        let path = root.join(file);
+        let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        fs::write(&path, restore(&text, record))?;
+    }
+    Ok(manifest.scripts.len())
+}
+
+/// Rewrites the leading whitespace of a line with tabs or spaces, keeping its visual width.
+fn reindent(line: &str, indentation: Indentation, width: usize) -> String {
+    let body = line.trim_start_matches([' ', '\t']);
+    let mut column = 0;
+    for c in line[..line.len() - body.len()].chars() {
+        column = if c == '\t' { (column / width + 1) * width } else { column + 1 };
+    }
+    let indent = match indentation {
+        Indentation::Tabs => "\t".repeat(column / width) + &" ".repeat(column % width),
+        _ => " ".repeat(column),
+    };
+    indent + body
+}
+
+fn run_formatter(command: &str, source: &str) -> anyhow::Result<String> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or_else(|| anyhow::anyhow!("empty formatter command"))?;
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Written from another thread so a formatter that streams its output can not block on a full pipe.
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow::anyhow!("formatter stdin is not available"))?;
+    let input = source.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    writer.join().map_err(|_| anyhow::anyhow!("formatter input thread panicked"))??;
+    if !output.status.success() {
+        anyhow::bail!("formatter exited with {}", output.status);
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCES: &[&str] = &[
+        "print(1)\nprint(2)\n",
+        "print(1)\r\nprint(2)\r\n",
+        "\u{feff}local x = 1\r\nreturn x",
+        "if x then\r\n\tprint(x)  \nend\n",
+        "if x then\n  \tprint(x)\t\nend",
+        "",
+    ];
+
+    fn settings() -> Vec<NormalizeSettings> {
+        let base = NormalizeSettings::default;
+        vec![
+            NormalizeSettings { line_endings: LineEndings::Lf, ..base() },
+            NormalizeSettings { line_endings: LineEndings::Crlf, strip_bom: true, ..base() },
+            NormalizeSettings { indentation: Indentation::Tabs, indent_width: 2, ..base() },
+            NormalizeSettings { indentation: Indentation::Spaces, trim_trailing_whitespace: true, line_endings: LineEndings::Lf, ..base() },
+        ]
+    }
+
+    #[test]
+    fn restore_round_trips() {
+        for settings in settings() {
+            let normalizer = Normalizer::new(settings);
+            let normalized: Vec<String> = SOURCES.iter().enumerate().map(|(i, source)| normalizer.apply(Path::new(&format!("{}.lua", i)), source)).collect();
+            let manifest = normalizer.into_manifest();
+            for (i, source) in SOURCES.iter().enumerate() {
+                let restored = match manifest.scripts.get(&format!("{}.lua", i)) {
+                    Some(record) => restore(&normalized[i], record),
+                    None => normalized[i].clone(),
+                };
+                assert_eq!(&restored, source);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_originals_only_when_needed() {
+        let normalizer = Normalizer::new(NormalizeSettings { line_endings: LineEndings::Lf, strip_bom: true, trim_trailing_whitespace: true, ..NormalizeSettings::default() });
+        assert_eq!(normalizer.apply(Path::new("a.lua"), "\u{feff}a\r\nb\r\n"), "a\nb\n");
+        assert_eq!(normalizer.apply(Path::new("b.lua"), "a  \nb"), "a\nb");
+        assert_eq!(normalizer.apply(Path::new("c.lua"), "a\nb"), "a\nb");
+        let scripts = normalizer.into_manifest().scripts;
+        assert_eq!((scripts["a.lua"].line_endings, scripts["a.lua"].bom, scripts["a.lua"].original.is_none()), (SourceLineEndings::Crlf, true, true));
+        assert_eq!(scripts["b.lua"].original.as_deref(), Some("a  \nb"));
+        assert!(!scripts.contains_key("c.lua"));
+    }
+
+    #[test]
+    fn reindents_by_visual_width() {
+        assert_eq!(reindent("\t  x", Indentation::Spaces, 4), "      x");
+        assert_eq!(reindent("      x", Indentation::Tabs, 4), "\t  x");
+        assert_eq!(reindent("x  ", Indentation::Tabs, 4), "x  ");
+    }
+
+    #[test]
+    fn restores_an_extracted_folder() {
+        let root = std::env::temp_dir().join(format!("rbx_ripper_test_{}_restore", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("Script")).unwrap();
+        let normalizer = Normalizer::new(NormalizeSettings { line_endings: LineEndings::Lf, trim_trailing_whitespace: true, ..NormalizeSettings::default() });
+        let files = [("Script/script.lua", "a\r\nb\r\n"), ("b.lua", "c  \n")];
+        for (file, source) in files {
+            fs::write(root.join(file), normalizer.apply(Path::new(file), source)).unwrap();
+        }
+        fs::write(root.join(NORMALIZATION_FILE), serde_json::to_vec_pretty(&normalizer.into_manifest()).unwrap()).unwrap();
+        assert_eq!(restore_folder(&root).unwrap(), 2);
+        for (file, source) in files {
+            assert_eq!(fs::read_to_string(root.join(file)).unwrap(), source);
+        }
+        let _ = fs::remove_dir_all(&root);
+    }
+}
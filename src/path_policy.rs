@@ -0,0 +1,214 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const DEFAULT_MAX_PATH_LENGTH: usize = 240;
+pub const PATH_MAP_FILE: &str = "path_map.json";
+
+// Longest file name written inside an instance folder ("properties.json") plus a separator.
+const RESERVED_FILE_LENGTH: usize = 16;
+const HASH_LENGTH: usize = 8;
+
+#[derive(Clone, Serialize)]
+pub struct PathMapping {
+    pub path: String,
+    pub original_name: String,
+    pub class: String,
+    pub reasons: Vec<&'static str>,
+}
+
+/// Decides the folder name of every instance so the output stays valid on Windows:
+/// reserved names and trailing dots/spaces are stripped, siblings that differ only by case
+/// get a numeric suffix, and segments that would push the path over `max_path_length`
+/// are shortened with a hash of the original name. Parents and results are relative to `root`.
+pub struct PathPolicy {
+    root: PathBuf,
+    max_path_length: usize,
+    check_disk: bool,
+    claimed: Mutex<HashMap<PathBuf, HashSet<String>>>,
+    mappings: Mutex<Vec<PathMapping>>,
+}
+
+impl PathPolicy {
+    pub fn new(root: &Path, max_path_length: usize, check_disk: bool) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            max_path_length,
+            check_disk,
+            claimed: Mutex::new(HashMap::new()),
+            mappings: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Keeps `name` free under `parent` for a folder the extraction writes itself.
+    pub fn reserve(&self, parent: &Path, name: &str) {
+        self.claimed.lock().unwrap().entry(parent.to_path_buf()).or_default().insert(name.to_lowercase());
+    }
+
+    pub fn allocate(&self, parent: &Path, name: &str, class_name: &str) -> PathBuf {
+        let mut reasons = Vec::new();
+
+        let mut safe_name = sanitize_segment(name);
+        if safe_name != name {
+            reasons.push("sanitized");
+        }
+        if safe_name.is_empty() {
+            safe_name = sanitize_segment(class_name);
+        }
+        let folder_name = if safe_name.to_lowercase() == class_name.to_lowercase() {
+            safe_name
+        } else {
+            format!("{} [{}]", safe_name, sanitize_segment(class_name))
+        };
+
+        let full_parent = self.root.join(parent);
+        let parent_len = full_parent.to_string_lossy().chars().count();
+        let budget = self.max_path_length
+            .saturating_sub(parent_len + 1 + RESERVED_FILE_LENGTH)
+            .max(HASH_LENGTH + 1);
+
+        let mut claimed = self.claimed.lock().unwrap();
+        let siblings = claimed.entry(parent.to_path_buf()).or_default();
+
+        let mut i = 0;
+        let candidate = loop {
+            let mut candidate = if i == 0 {
+                folder_name.clone()
+            } else {
+                format!("{} ({})", folder_name, i)
+            };
+            let shortened = candidate.chars().count() > budget;
+            if shortened {
+                candidate = shorten_segment(&candidate, budget, parent, name, class_name, i);
+            }
+
+            let key = candidate.to_lowercase();
+            let taken = siblings.contains(&key) || (self.check_disk && full_parent.join(&candidate).exists());
+            if !taken {
+                siblings.insert(key);
+                if i > 0 {
+                    reasons.push("collision");
+                }
+                if shortened {
+                    reasons.push("shortened");
+                }
+                break candidate;
+            }
+            i += 1;
+        };
+        drop(claimed);
+
+        let target = parent.join(&candidate);
+        if !reasons.is_empty() {
+            self.mappings.lock().unwrap().push(PathMapping {
+                path: target.to_string_lossy().replace('\\', "/"),
+                original_name: name.to_string(),
+                class: class_name.to_string(),
+                reasons,
+            });
+        }
+        target
+    }
+
+    pub fn into_mappings(self) -> Vec<PathMapping> {
+        let mut mappings = self.mappings.into_inner().unwrap();
+        mappings.sort_by(|a, b| a.path.cmp(&b.path));
+        mappings
+    }
+}
+
+/// `name` with everything Windows rejects in a file name stripped, including path separators.
+pub fn sanitize_segment(name: &str) -> String {
+    let options = sanitize_filename::Options {
+        windows: true,
+        truncate: true,
+        replacement: "",
+    };
+    sanitize_filename::sanitize_with_options(name, options)
+}
+
+fn shorten_segment(candidate: &str, budget: usize, parent: &Path, name: &str, class_name: &str, index: usize) -> String {
+    let hash = format!("{:016x}", fnv1a(format!("{}\0{}\0{}\0{}", parent.display(), name, class_name, index).as_bytes()));
+    let hash = &hash[..HASH_LENGTH];
+
+    if budget <= HASH_LENGTH + 1 {
+        return hash.to_string();
+    }
+    let keep = budget - HASH_LENGTH - 1;
+    let prefix: String = candidate.chars().take(keep).collect();
+    let prefix = prefix.trim_end_matches(['.', ' ']);
+    format!("{}~{}", prefix, hash)
+}
+
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in bytes {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(policy: &PathPolicy, parent: &str, items: &[(&str, &str)]) -> Vec<String> {
+        items.iter().map(|(name, class)| policy.allocate(Path::new(parent), name, class).to_string_lossy().replace('\\', "/")).collect()
+    }
+
+    #[test]
+    fn strips_what_windows_rejects() {
+        let policy = PathPolicy::new(Path::new("out"), DEFAULT_MAX_PATH_LENGTH, false);
+        assert_eq!(
+            names(&policy, "", &[("CON", "Folder"), ("a/b", "Folder"), ("x. ", "Part"), ("", "Model")]),
+            vec!["Folder", "ab [Folder]", "x [Part]", "Model"]
+        );
+        let reasons: Vec<_> = policy.into_mappings().into_iter().map(|m| (m.path, m.reasons)).collect();
+        assert_eq!(reasons[0], ("Folder".to_string(), vec!["sanitized"]));
+    }
+
+    #[test]
+    fn numbers_siblings_that_differ_by_case() {
+        let policy = PathPolicy::new(Path::new("out"), DEFAULT_MAX_PATH_LENGTH, false);
+        let items = [("Part", "Part"), ("part", "Part"), ("PART", "Part")];
+        assert_eq!(names(&policy, "Workspace", &items), vec!["Workspace/Part", "Workspace/part (1)", "Workspace/PART (2)"]);
+        // Other parents have their own names.
+        assert_eq!(names(&policy, "Lighting", &items[..1]), vec!["Lighting/Part"]);
+    }
+
+    #[test]
+    fn shortens_long_names_with_a_hash() {
+        let policy = PathPolicy::new(Path::new("out"), 40, false);
+        let long = "n".repeat(30);
+        let shortened = names(&policy, "Workspace", &[(&long, "Folder"), (&long, "Folder")]);
+        assert_ne!(shortened[0], shortened[1]);
+        for path in &shortened {
+            assert!(Path::new("out").join(path).to_string_lossy().len() + RESERVED_FILE_LENGTH <= 40, "{}", path);
+            assert!(path.starts_with("Workspace/n~"), "{}", path);
+        }
+        let mappings = policy.into_mappings();
+        assert_eq!(mappings[1].reasons, vec!["collision", "shortened"]);
+    }
+
+    #[test]
+    fn reserved_names_stay_free() {
+        let policy = PathPolicy::new(Path::new("out"), DEFAULT_MAX_PATH_LENGTH, false);
+        policy.reserve(Path::new(""), "packages");
+        assert_eq!(names(&policy, "", &[("Packages", "Packages")]), vec!["Packages (1)"]);
+    }
+
+    #[test]
+    fn same_order_gives_same_names() {
+        let items: Vec<(String, &str)> = (0..50).map(|i| (if i % 3 == 0 { "Part".to_string() } else { format!("PART{}", i % 2) }, "Part")).collect();
+        let items: Vec<(&str, &str)> = items.iter().map(|(name, class)| (name.as_str(), *class)).collect();
+        let run = || {
+            let policy = PathPolicy::new(Path::new("out"), 32, false);
+            let names = names(&policy, "Workspace", &items);
+            let mappings: Vec<_> = policy.into_mappings().into_iter().map(|m| (m.path, m.original_name)).collect();
+            (names, mappings)
+        };
+        assert_eq!(run(), run());
+    }
+}
@@ -0,0 +1,428 @@
+use crate::analysis::dedup::{SharedSources, SCRIPT_REF_FILE};
+use crate::analysis::packages::{SharedPackages, PACKAGE_REF_FILE};
+use crate::analysis::write_reports;
+use crate::dom::{read_shared_strings, Dom};
+use crate::dump::{write_json, write_ndjson};
+use crate::export::images::ImageExport;
+use crate::export::mesh_data::MeshDataExport;
+use crate::export::write_exports;
+use crate::extract::{child_items, exclusion_reason, payload_files, read_item, top_level_items, ExclusionReason, ExtractionSettings, InstanceOutput, OutputFormat, ScriptOutput};
+use crate::normalize::{Normalizer, NORMALIZATION_FILE};
+use crate::path_policy::{PathPolicy, PATH_MAP_FILE};
+use crate::sink::{path_policy_for, OutputSink};
+use crate::sqlite::estimate_size;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct PlannedFile {
+    pub name: String,
+    pub bytes: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct ExcludedSubtree {
+    pub name: String,
+    pub class: String,
+    pub reason: ExclusionReason,
+    pub instances: usize,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct PlanNode {
+    pub folder: String,
+    pub class: String,
+    pub path: String,
+    pub files: Vec<PlannedFile>,
+    pub excluded: Vec<ExcludedSubtree>,
+    pub children: Vec<PlanNode>,
+}
+
+/// What an extraction would write under the current settings, computed without touching the output folder.
+#[derive(Clone, PartialEq, Serialize)]
+pub struct Plan {
+    pub output: String,
+    pub folders: usize,
+    pub files: usize,
+    pub bytes: u64,
+    pub excluded_instances: usize,
+    pub root_files: Vec<PlannedFile>,
+    pub excluded: Vec<ExcludedSubtree>,
+    pub roots: Vec<PlanNode>,
+}
+
+pub fn build_plan(input: &Path, output: &Path, settings: &ExtractionSettings) -> anyhow::Result<Plan> {
+    let text = fs::read_to_string(input)?;
+    let doc = roxmltree::Document::parse(&text)?;
+    let policy = path_policy_for(output, settings.max_path_length);
+
+    let mut plan = Plan {
+        output: output.to_string_lossy().to_string(),
+        folders: 0,
+        files: 0,
+        bytes: 0,
+        excluded_instances: 0,
+        root_files: Vec::new(),
+        excluded: Vec::new(),
+        roots: Vec::new(),
+    };
+
+    let folders = settings.format.dump_file().is_none();
+    let needs_dom = !folders || settings.dedup_scripts || settings.dedup_packages || settings.reports.any() || settings.exports.any();
+    let dom = needs_dom.then(|| Dom::build(&doc, settings));
+    let shared = dom.as_ref().filter(|_| folders && settings.dedup_scripts).map(SharedSources::collect);
+    let packages = dom.as_ref().filter(|_| folders && settings.dedup_packages).map(|dom| SharedPackages::collect(dom, &policy));
+
+    let normalizer = (settings.normalize.is_enabled() && folders).then(|| Normalizer::new(settings.normalize.clone()));
+    let scripts = ScriptOutput { shared: shared.as_ref(), normalizer: normalizer.as_ref() };
+    let shared_strings = if folders && (settings.exports.mesh_data || settings.exports.images) { read_shared_strings(&doc) } else { Default::default() };
+    let meshes = (folders && settings.exports.mesh_data).then(|| MeshDataExport::new(&shared_strings));
+    let images = (folders && settings.exports.images).then(|| ImageExport::new(&shared_strings));
+    let instances = InstanceOutput { meshes: meshes.as_ref(), images: images.as_ref(), packages: packages.as_ref() };
+
+    for node in top_level_items(&doc) {
+        match plan_item(node, Path::new(""), settings, &policy, &scripts, &instances, &mut plan)? {
+            Planned::Node(planned) => plan.roots.push(planned),
+            Planned::Excluded(excluded) => plan.excluded.push(excluded),
+        }
+    }
+
+    if let Some(file_name) = settings.format.dump_file() {
+        let bytes = dump_size(dom.as_ref().unwrap(), settings.format)?;
+        plan.folders = 0;
+        plan.files = 0;
+        plan.bytes = 0;
+        plan.roots.clear();
+        plan.add_root_file(file_name.to_string(), bytes);
+    } else {
+        // sources/ and packages/ themselves.
+        plan.folders += shared.iter().filter(|shared| !shared.is_empty()).count();
+        plan.folders += packages.iter().filter(|packages| !packages.is_empty()).count();
+
+        if let Some(normalizer) = normalizer {
+            let bytes = serde_json::to_vec_pretty(&normalizer.into_manifest())?.len() as u64;
+            plan.add_root_file(NORMALIZATION_FILE.to_string(), bytes);
+        }
+
+        let mappings = policy.into_mappings();
+        if !mappings.is_empty() {
+            let bytes = serde_json::to_vec_pretty(&mappings)?.len() as u64;
+            plan.add_root_file(PATH_MAP_FILE.to_string(), bytes);
+        }
+    }
+
+    if let Some(dom) = dom.as_ref().filter(|_| settings.reports.any() || settings.exports.any()) {
+        // Reports and exports are generated as they would be and only their sizes kept.
+        let sink = PlanSink::default();
+        if settings.reports.any() {
+            write_reports(dom, &settings.reports, &sink)?;
+        }
+        if settings.exports.any() {
+            write_exports(dom, &settings.exports, &sink)?;
+        }
+        plan.folders += sink.folders.into_inner().unwrap().len();
+        for file in sink.files.into_inner().unwrap() {
+            plan.add_root_file(file.name, file.bytes);
+        }
+    }
+
+    Ok(plan)
+}
+
+impl Plan {
+    fn add_root_file(&mut self, name: String, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+        self.root_files.push(PlannedFile { name, bytes });
+    }
+}
+
+/// Records the folders and file sizes written into it and drops the contents.
+#[derive(Default)]
+struct PlanSink {
+    folders: Mutex<BTreeSet<PathBuf>>,
+    files: Mutex<Vec<PlannedFile>>,
+}
+
+impl OutputSink for PlanSink {
+    fn create_dir(&self, path: &Path) -> anyhow::Result<()> {
+        self.folders.lock().unwrap().insert(path.to_path_buf());
+        Ok(())
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let name = path.to_string_lossy().replace('\\', "/");
+        self.files.lock().unwrap().push(PlannedFile { name, bytes: contents.len() as u64 });
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Counts what is written into it and drops the bytes.
+struct ByteCounter(u64);
+
+impl Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The size of the dump file. JSON is measured as it is written into nothing; the database size is estimated.
+fn dump_size(dom: &Dom, format: OutputFormat) -> anyhow::Result<u64> {
+    let mut counter = ByteCounter(0);
+    match format {
+        OutputFormat::Json => write_json(dom, &mut counter)?,
+        OutputFormat::Ndjson => write_ndjson(dom, &mut counter)?,
+        OutputFormat::Sqlite => return estimate_size(dom),
+        OutputFormat::Folders => {}
+    }
+    Ok(counter.0)
+}
+
+enum Planned {
+    Node(PlanNode),
+    Excluded(ExcludedSubtree),
+}
+
+fn plan_item(
+    node: roxmltree::Node,
+    parent_path: &Path,
+    settings: &ExtractionSettings,
+    policy: &PathPolicy,
+    scripts: &ScriptOutput,
+    instances: &InstanceOutput,
+    plan: &mut Plan
+) -> anyhow::Result<Planned> {
+    let item = read_item(node);
+
+    if let Some(reason) = exclusion_reason(node, settings) {
+        let instances = node.descendants().filter(|n| n.has_tag_name("Item")).count();
+        plan.excluded_instances += instances;
+        return Ok(Planned::Excluded(ExcludedSubtree {
+            name: item.name,
+            class: item.class_name,
+            reason,
+            instances,
+        }));
+    }
+
+    let target_dir = policy.allocate(parent_path, &item.name, &item.class_name);
+
+    let mut files = vec![PlannedFile {
+        name: "properties.json".to_string(),
+        bytes: serde_json::to_vec_pretty(&item.properties)?.len() as u64,
+    }];
+    if let Some(source) = &item.source {
+        match scripts.shared.and_then(|shared| shared.claim(source)) {
+            Some((path, first)) => {
+                let reference = path.to_string_lossy().replace('\\', "/");
+                if first {
+                    let bytes = scripts.render(path, source).len() as u64;
+                    plan.files += 1;
+                    plan.bytes += bytes;
+                    plan.root_files.push(PlannedFile { name: reference.clone(), bytes });
+                }
+                files.push(PlannedFile { name: SCRIPT_REF_FILE.to_string(), bytes: reference.len() as u64 });
+            }
+            None => {
+                let bytes = scripts.render(&target_dir.join("script.lua"), source).len() as u64;
+                files.push(PlannedFile { name: "script.lua".to_string(), bytes });
+            }
+        }
+    }
+    for (name, bytes) in payload_files(node, &item.class_name, instances) {
+        files.push(PlannedFile { name, bytes: bytes.len() as u64 });
+    }
+
+    plan.folders += 1;
+    plan.files += files.len();
+    plan.bytes += files.iter().map(|f| f.bytes).sum::<u64>();
+
+    // Package copies point to packages/; only the children of the first one are planned there.
+    let mut children_dir = Some(target_dir.clone());
+    if let Some((folder, writer)) = instances.packages.zip(node.attribute("referent")).and_then(|(packages, referent)| packages.claim(referent)) {
+        let reference = folder.to_string_lossy().replace('\\', "/");
+        if writer {
+            plan.folders += 1;
+            children_dir = Some(folder.to_path_buf());
+        } else {
+            children_dir = None;
+        }
+        plan.files += 1;
+        plan.bytes += reference.len() as u64;
+        files.push(PlannedFile { name: PACKAGE_REF_FILE.to_string(), bytes: reference.len() as u64 });
+    }
+
+    let mut planned = PlanNode {
+        folder: target_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        class: item.class_name,
+        path: target_dir.to_string_lossy().replace('\\', "/"),
+        files,
+        excluded: Vec::new(),
+        children: Vec::new(),
+    };
+
+    if let Some(children_dir) = &children_dir {
+        for child in child_items(node) {
+            match plan_item(child, children_dir, settings, policy, scripts, instances, plan)? {
+                Planned::Node(child) => planned.children.push(child),
+                Planned::Excluded(excluded) => planned.excluded.push(excluded),
+            }
+        }
+    }
+
+    Ok(Planned::Node(planned))
+}
+
+pub fn render_text(plan: &Plan) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", plan.output);
+    for file in &plan.root_files {
+        let _ = writeln!(out, "  {} ({})", file.name, format_bytes(file.bytes));
+    }
+    for excluded in &plan.excluded {
+        render_excluded(&mut out, excluded, 1);
+    }
+    for node in &plan.roots {
+        render_node(&mut out, node, 1);
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", summary(plan));
+    out
+}
+
+pub fn summary(plan: &Plan) -> String {
+    format!(
+        "{} folders, {} files, {} total, {} instances excluded",
+        plan.folders,
+        plan.files,
+        format_bytes(plan.bytes),
+        plan.excluded_instances
+    )
+}
+
+fn render_node(out: &mut String, node: &PlanNode, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let bytes: u64 = node.files.iter().map(|f| f.bytes).sum();
+    let _ = writeln!(out, "{}{}/ ({} files, {})", indent, node.folder, node.files.len(), format_bytes(bytes));
+    for excluded in &node.excluded {
+        render_excluded(out, excluded, depth + 1);
+    }
+    for child in &node.children {
+        render_node(out, child, depth + 1);
+    }
+}
+
+fn render_excluded(out: &mut String, excluded: &ExcludedSubtree, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let _ = writeln!(
+        out,
+        "{}- {} [{}] skipped: {} ({} instances)",
+        indent, excluded.name, excluded.class, excluded.reason, excluded.instances
+    );
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::tests::{extract, settings};
+    use crate::normalize::LineEndings;
+
+    /// A 1×1 PNG.
+    const PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAIAAACQd1PeAAAADElEQVR4nGP4z8AAAAMBAQDJ/pLvAAAAAElFTkSuQmCC";
+
+    fn written(dir: &Path, root: &Path, folders: &mut usize, files: &mut BTreeSet<(String, u64)>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                *folders += 1;
+                written(&path, root, folders, files);
+            } else {
+                let name = path.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/");
+                files.insert((name, fs::metadata(&path).unwrap().len()));
+            }
+        }
+    }
+
+    fn planned(node: &PlanNode, files: &mut BTreeSet<(String, u64)>) {
+        for file in &node.files {
+            files.insert((format!("{}/{}", node.path, file.name), file.bytes));
+        }
+        for child in &node.children {
+            planned(child, files);
+        }
+    }
+
+    #[test]
+    fn matches_what_extraction_writes() {
+        let xml = format!(r#"<roblox version="4">
+<Item class="Workspace" referent="W"><Properties><string name="Name">Workspace</string></Properties>
+<Item class="Decal" referent="D"><Properties><string name="Name">D</string><Content name="Texture"><url>rbxassetid://123</url></Content><BinaryString name="Texture">{PNG}</BinaryString></Properties></Item>
+<Item class="Part" referent="P"><Properties><string name="Name">a:b</string></Properties></Item>
+<Item class="Script" referent="S"><Properties><string name="Name">Main</string><ProtectedString name="Source">print(1)&#13;
+print(2)&#13;
+</ProtectedString></Properties></Item>
+</Item>
+<Item class="Lighting" referent="L"><Properties><string name="Name">Lighting</string><float name="Brightness">2</float></Properties>
+<Item class="Sky" referent="K"><Properties><string name="Name">Sky</string></Properties></Item>
+</Item>
+</roblox>"#);
+        let mut settings = settings();
+        settings.normalize.line_endings = LineEndings::Lf;
+        settings.reports.assets = true;
+        settings.reports.stats = true;
+        settings.exports.images = true;
+        settings.exports.mesh_data = true;
+        settings.exports.lighting = true;
+        // Planned first: folders already on disk would be taken into account.
+        let dir = std::env::temp_dir().join(format!("rbx_ripper_test_{}_plan", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("place.rbxlx"), &xml).unwrap();
+        let plan = build_plan(&dir.join("place.rbxlx"), &dir.join("out"), &settings).unwrap();
+        let output = extract("plan", &xml, &settings, &|_, _| {});
+
+        let mut folders = 0;
+        let mut files = BTreeSet::new();
+        written(&output, &output, &mut folders, &mut files);
+        let mut planned_files: BTreeSet<(String, u64)> = plan.root_files.iter().map(|f| (f.name.clone(), f.bytes)).collect();
+        for root in &plan.roots {
+            planned(root, &mut planned_files);
+        }
+
+        assert_eq!(planned_files, files);
+        assert!(files.iter().any(|(name, _)| name == "Workspace/D [Decal]/Texture.png"));
+        assert!(files.iter().any(|(name, _)| name == "reports/stats.md"));
+        assert!(files.iter().any(|(name, _)| name == "exports/lighting.json"));
+        assert_eq!((plan.folders, plan.files), (folders, files.len()));
+        assert_eq!(plan.bytes, files.iter().map(|(_, bytes)| bytes).sum::<u64>());
+        let _ = fs::remove_dir_all(output.parent().unwrap());
+    }
+}
@@ -0,0 +1,362 @@
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt::Write as _;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct UDim {
+    pub scale: f64,
+    pub offset: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SequenceKeypoint {
+    pub time: f64,
+    pub value: f64,
+    pub envelope: f64,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorKeypoint {
+    pub time: f64,
+    pub color: [f64; 3],
+}
+
+/// A property value decoded from its rbxlx XML tag. Unknown tags keep their raw text so nothing is lost.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum PropertyValue {
+    String(String),
+    ProtectedString(String),
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Token(u32),
+    BrickColor(u32),
+    Vector2 { x: f64, y: f64 },
+    Vector3 { x: f64, y: f64, z: f64 },
+    CFrame { position: [f64; 3], orientation: [[f64; 3]; 3] },
+    Color3 { r: f64, g: f64, b: f64 },
+    Color3uint8 { r: u8, g: u8, b: u8 },
+    UDim(UDim),
+    UDim2 { x: UDim, y: UDim },
+    Rect { min: [f64; 2], max: [f64; 2] },
+    NumberRange { min: f64, max: f64 },
+    NumberSequence(Vec<SequenceKeypoint>),
+    ColorSequence(Vec<ColorKeypoint>),
+    Content(Option<String>),
+    Ref(Option<String>),
+    BinaryString(String),
+    SharedString(String),
+    Font { family: Option<String>, weight: Option<u32>, style: Option<String> },
+    PhysicalProperties(Option<[f64; 5]>),
+    Faces(u32),
+    Axes(u32),
+    Raw { tag: String, text: String },
+}
+
+#[derive(Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub value: PropertyValue,
+}
+
+impl PropertyValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            PropertyValue::String(_) => "String",
+            PropertyValue::ProtectedString(_) => "ProtectedString",
+            PropertyValue::Bool(_) => "Bool",
+            PropertyValue::Int(_) => "Int",
+            PropertyValue::Float(_) => "Float",
+            PropertyValue::Token(_) => "Token",
+            PropertyValue::BrickColor(_) => "BrickColor",
+            PropertyValue::Vector2 { .. } => "Vector2",
+            PropertyValue::Vector3 { .. } => "Vector3",
+            PropertyValue::CFrame { .. } => "CFrame",
+            PropertyValue::Color3 { .. } => "Color3",
+            PropertyValue::Color3uint8 { .. } => "Color3uint8",
+            PropertyValue::UDim(_) => "UDim",
+            PropertyValue::UDim2 { .. } => "UDim2",
+            PropertyValue::Rect { .. } => "Rect",
+            PropertyValue::NumberRange { .. } => "NumberRange",
+            PropertyValue::NumberSequence(_) => "NumberSequence",
+            PropertyValue::ColorSequence(_) => "ColorSequence",
+            PropertyValue::Content(_) => "Content",
+            PropertyValue::Ref(_) => "Ref",
+            PropertyValue::BinaryString(_) => "BinaryString",
+            PropertyValue::SharedString(_) => "SharedString",
+            PropertyValue::Font { .. } => "Font",
+            PropertyValue::PhysicalProperties(_) => "PhysicalProperties",
+            PropertyValue::Faces(_) => "Faces",
+            PropertyValue::Axes(_) => "Axes",
+            PropertyValue::Raw { .. } => "Raw",
+        }
+    }
+
+    /// The value on its own, without the `type` tag used in the JSON dumps.
+    pub fn value_json(&self) -> serde_json::Value {
+        match serde_json::to_value(self) {
+            Ok(serde_json::Value::Object(mut map)) => map.remove("value").unwrap_or(serde_json::Value::Null),
+            _ => serde_json::Value::Null,
+        }
+    }
+}
+
+pub fn serialize_properties<S: Serializer>(properties: &[Property], serializer: S) -> Result<S::Ok, S::Error> {
+    let mut map = serializer.serialize_map(Some(properties.len()))?;
+    for property in properties {
+        map.serialize_entry(&property.name, &property.value)?;
+    }
+    map.end()
+}
+
+pub fn read_typed_properties(node: roxmltree::Node) -> Vec<Property> {
+    let mut properties = Vec::new();
+    if let Some(props_node) = node.children().find(|n| n.has_tag_name("Properties")) {
+        for prop in props_node.children() {
+            if !prop.is_element() { continue; }
+            let name = prop.attribute("name").unwrap_or("Unknown").to_string();
+            properties.push(Property { name, value: parse_value(prop) });
+        }
+    }
+    properties
+}
+
+/// Decodes the standard base64 alphabet used by `BinaryString` and `<SharedStrings>` payloads.
+/// Whitespace is ignored; any other character outside the alphabet makes the payload invalid.
+pub fn decode_base64(data: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 4 * 3);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in data.bytes() {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            b if b.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The rbxlx element for a property, the inverse of reading it. Values that lost their exact tag when read
+/// (`int64`, `double`, packed `Color3`) are written in the tag Studio accepts for them.
+pub fn property_xml(name: &str, value: &PropertyValue) -> String {
+    let name = escape_xml(name);
+    let element = |tag: &str, body: String| format!("<{} name=\"{}\">{}</{}>", tag, name, body, tag);
+    let fields = |pairs: &[(&str, f64)]| pairs.iter().map(|(tag, v)| format!("<{}>{}</{}>", tag, v, tag)).collect::<String>();
+    match value {
+        PropertyValue::String(v) => element("string", escape_xml(v)),
+        PropertyValue::ProtectedString(v) => element("ProtectedString", escape_xml(v)),
+        PropertyValue::Bool(v) => element("bool", v.to_string()),
+        PropertyValue::Int(v) => element(if i32::try_from(*v).is_ok() { "int" } else { "int64" }, v.to_string()),
+        PropertyValue::Float(v) => element("float", v.to_string()),
+        PropertyValue::Token(v) => element("token", v.to_string()),
+        PropertyValue::BrickColor(v) => element("BrickColor", v.to_string()),
+        PropertyValue::Vector2 { x, y } => element("Vector2", fields(&[("X", *x), ("Y", *y)])),
+        PropertyValue::Vector3 { x, y, z } => element("Vector3", fields(&[("X", *x), ("Y", *y), ("Z", *z)])),
+        PropertyValue::CFrame { position: p, orientation: r } => element(
+            "CoordinateFrame",
+            fields(&[
+                ("X", p[0]), ("Y", p[1]), ("Z", p[2]),
+                ("R00", r[0][0]), ("R01", r[0][1]), ("R02", r[0][2]),
+                ("R10", r[1][0]), ("R11", r[1][1]), ("R12", r[1][2]),
+                ("R20", r[2][0]), ("R21", r[2][1]), ("R22", r[2][2]),
+            ]),
+        ),
+        PropertyValue::Color3 { r, g, b } => element("Color3", fields(&[("R", *r), ("G", *g), ("B", *b)])),
+        PropertyValue::Color3uint8 { r, g, b } => {
+            element("Color3uint8", (0xFF00_0000u32 | (*r as u32) << 16 | (*g as u32) << 8 | *b as u32).to_string())
+        }
+        PropertyValue::UDim(v) => element("UDim", fields(&[("S", v.scale), ("O", v.offset)])),
+        PropertyValue::UDim2 { x, y } => element("UDim2", fields(&[("XS", x.scale), ("XO", x.offset), ("YS", y.scale), ("YO", y.offset)])),
+        PropertyValue::Rect { min, max } => element(
+            "Rect2D",
+            format!("<min>{}</min><max>{}</max>", fields(&[("X", min[0]), ("Y", min[1])]), fields(&[("X", max[0]), ("Y", max[1])])),
+        ),
+        PropertyValue::NumberRange { min, max } => element("NumberRange", format!("{} {} ", min, max)),
+        PropertyValue::NumberSequence(points) => {
+            let mut body = String::new();
+            for p in points {
+                let _ = write!(body, "{} {} {} ", p.time, p.value, p.envelope);
+            }
+            element("NumberSequence", body)
+        }
+        PropertyValue::ColorSequence(points) => {
+            let mut body = String::new();
+            for p in points {
+                let _ = write!(body, "{} {} {} {} 0 ", p.time, p.color[0], p.color[1], p.color[2]);
+            }
+            element("ColorSequence", body)
+        }
+        PropertyValue::Content(url) => element("Content", match url {
+            Some(url) => format!("<url>{}</url>", escape_xml(url)),
+            None => "<null></null>".to_string(),
+        }),
+        PropertyValue::Ref(referent) => element("Ref", referent.as_deref().map(escape_xml).unwrap_or_else(|| "null".to_string())),
+        PropertyValue::BinaryString(v) => element("BinaryString", v.clone()),
+        PropertyValue::SharedString(v) => element("SharedString", escape_xml(v)),
+        PropertyValue::Font { family, weight, style } => {
+            let mut body = String::new();
+            if let Some(family) = family {
+                let _ = write!(body, "<Family><url>{}</url></Family>", escape_xml(family));
+            }
+            if let Some(weight) = weight {
+                let _ = write!(body, "<Weight>{}</Weight>", weight);
+            }
+            if let Some(style) = style {
+                let _ = write!(body, "<Style>{}</Style>", escape_xml(style));
+            }
+            element("Font", body)
+        }
+        PropertyValue::PhysicalProperties(values) => element("PhysicalProperties", match values {
+            Some([density, friction, elasticity, friction_weight, elasticity_weight]) => format!(
+                "<CustomPhysics>true</CustomPhysics>{}",
+                fields(&[
+                    ("Density", *density), ("Friction", *friction), ("Elasticity", *elasticity),
+                    ("FrictionWeight", *friction_weight), ("ElasticityWeight", *elasticity_weight),
+                ])
+            ),
+            None => "<CustomPhysics>false</CustomPhysics>".to_string(),
+        }),
+        PropertyValue::Faces(v) => element("Faces", format!("<faces>{}</faces>", v)),
+        PropertyValue::Axes(v) => element("Axes", format!("<axes>{}</axes>", v)),
+        PropertyValue::Raw { tag, text } => element(tag, escape_xml(text)),
+    }
+}
+
+fn text<'a>(node: roxmltree::Node<'a, '_>) -> &'a str {
+    node.text().unwrap_or("")
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, tag: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+fn child_f64(node: roxmltree::Node, tag: &str) -> f64 {
+    child(node, tag).map(|n| parse_f64(text(n))).unwrap_or(0.0)
+}
+
+fn parse_f64(s: &str) -> f64 {
+    s.trim().parse().unwrap_or(0.0)
+}
+
+fn numbers(s: &str) -> Vec<f64> {
+    s.split_whitespace().filter_map(|v| v.parse().ok()).collect()
+}
+
+fn parse_cframe(node: roxmltree::Node) -> PropertyValue {
+    let r = |tag: &str| child_f64(node, tag);
+    PropertyValue::CFrame {
+        position: [r("X"), r("Y"), r("Z")],
+        orientation: [
+            [r("R00"), r("R01"), r("R02")],
+            [r("R10"), r("R11"), r("R12")],
+            [r("R20"), r("R21"), r("R22")],
+        ],
+    }
+}
+
+fn parse_content(node: roxmltree::Node) -> Option<String> {
+    node.children()
+        .find(|n| n.has_tag_name("url") || n.has_tag_name("uri"))
+        .map(|n| text(n).to_string())
+        .or_else(|| {
+            let t = text(node).trim();
+            (!t.is_empty()).then(|| t.to_string())
+        })
+}
+
+fn parse_value(prop: roxmltree::Node) -> PropertyValue {
+    let tag = prop.tag_name().name();
+    let raw = text(prop);
+    match tag {
+        "string" => PropertyValue::String(raw.to_string()),
+        "ProtectedString" => PropertyValue::ProtectedString(raw.to_string()),
+        "bool" => PropertyValue::Bool(raw.trim() == "true"),
+        "int" | "int64" | "SecurityCapabilities" => PropertyValue::Int(raw.trim().parse().unwrap_or(0)),
+        "float" | "double" => PropertyValue::Float(parse_f64(raw)),
+        "token" => PropertyValue::Token(raw.trim().parse().unwrap_or(0)),
+        "BrickColor" => PropertyValue::BrickColor(raw.trim().parse().unwrap_or(0)),
+        "Vector2" => PropertyValue::Vector2 { x: child_f64(prop, "X"), y: child_f64(prop, "Y") },
+        "Vector3" => PropertyValue::Vector3 { x: child_f64(prop, "X"), y: child_f64(prop, "Y"), z: child_f64(prop, "Z") },
+        "CoordinateFrame" => parse_cframe(prop),
+        "OptionalCoordinateFrame" => match child(prop, "CFrame") {
+            Some(cf) => parse_cframe(cf),
+            None => PropertyValue::Raw { tag: tag.to_string(), text: String::new() },
+        },
+        "Color3" => {
+            if child(prop, "R").is_some() {
+                PropertyValue::Color3 { r: child_f64(prop, "R"), g: child_f64(prop, "G"), b: child_f64(prop, "B") }
+            } else {
+                let packed: u32 = raw.trim().parse().unwrap_or(0);
+                PropertyValue::Color3uint8 { r: (packed >> 16) as u8, g: (packed >> 8) as u8, b: packed as u8 }
+            }
+        }
+        "Color3uint8" => {
+            let packed: u32 = raw.trim().parse().unwrap_or(0);
+            PropertyValue::Color3uint8 { r: (packed >> 16) as u8, g: (packed >> 8) as u8, b: packed as u8 }
+        }
+        "UDim" => PropertyValue::UDim(UDim { scale: child_f64(prop, "S"), offset: child_f64(prop, "O") }),
+        "UDim2" => PropertyValue::UDim2 {
+            x: UDim { scale: child_f64(prop, "XS"), offset: child_f64(prop, "XO") },
+            y: UDim { scale: child_f64(prop, "YS"), offset: child_f64(prop, "YO") },
+        },
+        "Rect2D" => {
+            let corner = |tag: &str| child(prop, tag).map(|c| [child_f64(c, "X"), child_f64(c, "Y")]).unwrap_or([0.0; 2]);
+            PropertyValue::Rect { min: corner("min"), max: corner("max") }
+        }
+        "NumberRange" => {
+            let v = numbers(raw);
+            PropertyValue::NumberRange { min: v.first().copied().unwrap_or(0.0), max: v.get(1).copied().unwrap_or(0.0) }
+        }
+        "NumberSequence" => PropertyValue::NumberSequence(
+            numbers(raw).chunks_exact(3).map(|c| SequenceKeypoint { time: c[0], value: c[1], envelope: c[2] }).collect(),
+        ),
+        "ColorSequence" => PropertyValue::ColorSequence(
+            numbers(raw).chunks_exact(5).map(|c| ColorKeypoint { time: c[0], color: [c[1], c[2], c[3]] }).collect(),
+        ),
+        "Content" | "ContentId" => PropertyValue::Content(parse_content(prop)),
+        "Ref" => {
+            let r = raw.trim();
+            PropertyValue::Ref((!r.is_empty() && r != "null").then(|| r.to_string()))
+        }
+        "BinaryString" => PropertyValue::BinaryString(raw.split_whitespace().collect()),
+        "SharedString" => PropertyValue::SharedString(raw.trim().to_string()),
+        "Font" => PropertyValue::Font {
+            family: child(prop, "Family").and_then(parse_content),
+            weight: child(prop, "Weight").and_then(|n| text(n).trim().parse().ok()),
+            style: child(prop, "Style").map(|n| text(n).trim().to_string()),
+        },
+        "PhysicalProperties" => {
+            if child(prop, "CustomPhysics").is_some_and(|n| text(n).trim() == "true") {
+                PropertyValue::PhysicalProperties(Some([
+                    child_f64(prop, "Density"),
+                    child_f64(prop, "Friction"),
+                    child_f64(prop, "Elasticity"),
+                    child_f64(prop, "FrictionWeight"),
+                    child_f64(prop, "ElasticityWeight"),
+                ]))
+            } else {
+                PropertyValue::PhysicalProperties(None)
+            }
+        }
+        "Faces" => PropertyValue::Faces(child(prop, "faces").and_then(|n| text(n).trim().parse().ok()).unwrap_or(0)),
+        "Axes" => PropertyValue::Axes(child(prop, "axes").and_then(|n| text(n).trim().parse().ok()).unwrap_or(0)),
+        _ => PropertyValue::Raw { tag: tag.to_string(), text: raw.to_string() },
+    }
+}
@@ -0,0 +1,195 @@
+use crate::dom::Dom;
+use crate::properties::PropertyValue;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+
+pub const DEFAULT_CONTEXT_LINES: usize = 2;
+pub const DEFAULT_MAX_RESULTS: usize = 1000;
+
+/// Which parts of an instance are searched.
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SearchField {
+    Sources,
+    Properties,
+    Names,
+}
+
+#[derive(Clone)]
+pub struct SearchQuery {
+    pub pattern: String,
+    /// Match `pattern` as plain text instead of a regex.
+    pub fixed_string: bool,
+    pub ignore_case: bool,
+    pub fields: Vec<SearchField>,
+    /// Lines shown before and after every match in a script source.
+    pub context: usize,
+    pub max_results: usize,
+}
+
+impl SearchQuery {
+    pub fn new(pattern: String) -> Self {
+        Self {
+            pattern,
+            fixed_string: false,
+            ignore_case: false,
+            fields: vec![SearchField::Sources, SearchField::Properties, SearchField::Names],
+            context: DEFAULT_CONTEXT_LINES,
+            max_results: DEFAULT_MAX_RESULTS,
+        }
+    }
+
+    fn regex(&self) -> anyhow::Result<Regex> {
+        let pattern = if self.fixed_string { regex::escape(&self.pattern) } else { self.pattern.clone() };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .map_err(|e| anyhow::anyhow!("invalid search pattern: {}", e))
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "name", rename_all = "snake_case")]
+pub enum MatchLocation {
+    Name,
+    Property(String),
+    Source,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct ContextLine {
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    pub path: String,
+    pub class: String,
+    pub location: MatchLocation,
+    pub line: usize,
+    /// 1-based, in characters.
+    pub column: usize,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub before: Vec<ContextLine>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<ContextLine>,
+}
+
+#[derive(Clone, PartialEq, Serialize)]
+pub struct SearchResults {
+    pub pattern: String,
+    pub instances: usize,
+    pub hits: Vec<SearchHit>,
+    /// Set when the search stopped at `max_results`.
+    pub truncated: bool,
+}
+
+/// Searchable text of a property. Binary payloads and shared string keys are skipped.
+fn property_text(value: &PropertyValue) -> Option<Cow<'_, str>> {
+    match value {
+        PropertyValue::String(s) | PropertyValue::ProtectedString(s) => Some(Cow::Borrowed(s)),
+        PropertyValue::Content(url) => url.as_deref().map(Cow::Borrowed),
+        PropertyValue::Ref(_) | PropertyValue::BinaryString(_) | PropertyValue::SharedString(_) => None,
+        PropertyValue::Raw { text, .. } => Some(Cow::Borrowed(text)),
+        other => Some(Cow::Owned(other.value_json().to_string())),
+    }
+}
+
+/// Matches `pattern` line by line against the instance names, property values and script sources of `dom`.
+pub fn search(dom: &Dom, query: &SearchQuery) -> anyhow::Result<SearchResults> {
+    let regex = query.regex()?;
+    let mut results = SearchResults { pattern: query.pattern.clone(), instances: 0, hits: Vec::new(), truncated: false };
+
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        let mut texts: Vec<(MatchLocation, Cow<str>)> = Vec::new();
+        if query.fields.contains(&SearchField::Names) {
+            texts.push((MatchLocation::Name, Cow::Borrowed(&instance.name)));
+        }
+        if query.fields.contains(&SearchField::Properties) {
+            for property in &instance.properties {
+                if property.name == "Name" {
+                    continue;
+                }
+                if let Some(text) = property_text(&property.value) {
+                    texts.push((MatchLocation::Property(property.name.clone()), text));
+                }
+            }
+        }
+        if query.fields.contains(&SearchField::Sources) {
+            if let Some(source) = &instance.source {
+                texts.push((MatchLocation::Source, Cow::Borrowed(source)));
+            }
+        }
+
+        let before_count = results.hits.len();
+        for (location, text) in texts {
+            let context = if location == MatchLocation::Source { query.context } else { 0 };
+            let lines: Vec<&str> = text.lines().collect();
+            for (index, line) in lines.iter().enumerate() {
+                let Some(found) = regex.find(line) else { continue };
+                if results.hits.len() == query.max_results {
+                    results.truncated = true;
+                    results.instances += usize::from(results.hits.len() > before_count);
+                    return Ok(results);
+                }
+                let context_line = |i: usize| ContextLine { line: i + 1, text: lines[i].to_string() };
+                results.hits.push(SearchHit {
+                    path: dom.path(id),
+                    class: instance.class.clone(),
+                    location: location.clone(),
+                    line: index + 1,
+                    column: line[..found.start()].chars().count() + 1,
+                    text: line.to_string(),
+                    before: (index.saturating_sub(context)..index).map(context_line).collect(),
+                    after: (index + 1..lines.len().min(index + 1 + context)).map(context_line).collect(),
+                });
+            }
+        }
+        if results.hits.len() > before_count {
+            results.instances += 1;
+        }
+    }
+    Ok(results)
+}
+
+pub fn summary(results: &SearchResults) -> String {
+    format!(
+        "Search: {} matches in {} instances{}",
+        results.hits.len(),
+        results.instances,
+        if results.truncated { " (stopped at the result limit)" } else { "" }
+    )
+}
+
+/// Where a hit is, e.g. `Workspace.Script:12:5` or `Workspace.Part [Material]`.
+pub fn hit_location(hit: &SearchHit) -> String {
+    match &hit.location {
+        MatchLocation::Source => format!("{}:{}:{}", hit.path, hit.line, hit.column),
+        MatchLocation::Property(name) => format!("{} [{}]", hit.path, name),
+        MatchLocation::Name => format!("{} [Name]", hit.path),
+    }
+}
+
+/// grep-style output: the location and line of every hit, context lines indented, groups separated by `--`.
+pub fn render_text(results: &SearchResults) -> String {
+    let mut out = String::new();
+    for (i, hit) in results.hits.iter().enumerate() {
+        let has_context = !hit.before.is_empty() || !hit.after.is_empty();
+        if i > 0 && has_context {
+            let _ = writeln!(out, "--");
+        }
+        for line in &hit.before {
+            let _ = writeln!(out, "  {:>5}  {}", line.line, line.text);
+        }
+        let _ = writeln!(out, "{}: {}", hit_location(hit), hit.text.trim_end());
+        for line in &hit.after {
+            let _ = writeln!(out, "  {:>5}  {}", line.line, line.text);
+        }
+    }
+    let _ = writeln!(out, "{}", summary(results));
+    out
+}
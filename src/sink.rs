@@ -0,0 +1,328 @@
+use crate::analysis::dedup::SOURCES_DIR;
+use crate::analysis::packages::PACKAGES_DIR;
+use crate::analysis::REPORTS_DIR;
+use crate::export::EXPORTS_DIR;
+use crate::path_policy::PathPolicy;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Destination for the extracted tree. Paths are relative to the output root and use the
+/// folder names decided by `PathPolicy`, so the same traversal can target a folder or an archive.
+pub trait OutputSink: Sync {
+    fn create_dir(&self, path: &Path) -> anyhow::Result<()>;
+    fn write_file(&self, path: &Path, contents: &[u8]) -> anyhow::Result<()>;
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OutputTarget {
+    Folder,
+    Zip,
+    Tar,
+    TarZst,
+}
+
+impl OutputTarget {
+    pub fn from_path(path: &Path) -> Self {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_lowercase()).unwrap_or_default();
+        if name.ends_with(".zip") {
+            OutputTarget::Zip
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            OutputTarget::TarZst
+        } else if name.ends_with(".tar") {
+            OutputTarget::Tar
+        } else {
+            OutputTarget::Folder
+        }
+    }
+
+    pub fn is_archive(self) -> bool {
+        self != OutputTarget::Folder
+    }
+}
+
+pub fn open_sink(output: &Path) -> anyhow::Result<Box<dyn OutputSink>> {
+    Ok(match OutputTarget::from_path(output) {
+        OutputTarget::Folder => Box::new(FsSink::new(output)?),
+        OutputTarget::Zip => Box::new(ZipSink::new(output)?),
+        OutputTarget::Tar => Box::new(TarSink::new(output, false)?),
+        OutputTarget::TarZst => Box::new(TarSink::new(output, true)?),
+    })
+}
+
+/// Archives have no existing entries to collide with and no absolute location to measure,
+/// so their paths are checked relative to the archive root. Top-level instances never get
+/// the names of the folders reports, exports and shared files are written to.
+pub fn path_policy_for(output: &Path, max_path_length: usize) -> PathPolicy {
+    let policy = if OutputTarget::from_path(output).is_archive() {
+        PathPolicy::new(Path::new(""), max_path_length, false)
+    } else {
+        PathPolicy::new(output, max_path_length, true)
+    };
+    for dir in [REPORTS_DIR, EXPORTS_DIR, SOURCES_DIR, PACKAGES_DIR] {
+        policy.reserve(Path::new(""), dir);
+    }
+    policy
+}
+
+/// The file an archive is written to until `finish` renames it into place, so a failed or cancelled
+/// extraction neither leaves a truncated archive behind nor destroys an existing one.
+struct PartialFile {
+    temp: PathBuf,
+    target: PathBuf,
+}
+
+impl PartialFile {
+    fn create(target: &Path) -> anyhow::Result<(Self, BufWriter<fs::File>)> {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut name = target.file_name().unwrap_or_default().to_os_string();
+        name.push(".partial");
+        let temp = target.with_file_name(name);
+        let file = BufWriter::new(fs::File::create(&temp)?);
+        Ok((Self { temp, target: target.to_path_buf() }, file))
+    }
+
+    fn commit(self) -> anyhow::Result<()> {
+        fs::rename(&self.temp, &self.target)?;
+        Ok(())
+    }
+}
+
+impl Drop for PartialFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+fn archive_name(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+pub struct FsSink {
+    root: PathBuf,
+}
+
+impl FsSink {
+    pub fn new(root: &Path) -> anyhow::Result<Self> {
+        fs::create_dir_all(root)?;
+        Ok(Self { root: root.to_path_buf() })
+    }
+}
+
+impl OutputSink for FsSink {
+    fn create_dir(&self, path: &Path) -> anyhow::Result<()> {
+        fs::create_dir_all(self.root.join(path))?;
+        Ok(())
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(fs::File::create(self.root.join(path))?);
+        writer.write_all(contents)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+pub struct ZipSink {
+    writer: Mutex<zip::ZipWriter<BufWriter<fs::File>>>,
+    file: PartialFile,
+}
+
+impl ZipSink {
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        let (file, writer) = PartialFile::create(path)?;
+        Ok(Self { writer: Mutex::new(zip::ZipWriter::new(writer)), file })
+    }
+
+    fn options() -> zip::write::SimpleFileOptions {
+        zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(false)
+    }
+}
+
+impl OutputSink for ZipSink {
+    fn create_dir(&self, path: &Path) -> anyhow::Result<()> {
+        self.writer.lock().unwrap().add_directory(archive_name(path), Self::options())?;
+        Ok(())
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        writer.start_file(archive_name(path), Self::options())?;
+        writer.write_all(contents)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        let writer = self.writer.into_inner().unwrap();
+        writer.finish()?.into_inner()?.sync_all()?;
+        self.file.commit()
+    }
+}
+
+enum TarStream {
+    Plain(BufWriter<fs::File>),
+    Zstd(zstd::Encoder<'static, BufWriter<fs::File>>),
+}
+
+impl Write for TarStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            TarStream::Plain(w) => w.write(buf),
+            TarStream::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            TarStream::Plain(w) => w.flush(),
+            TarStream::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+pub struct TarSink {
+    builder: Mutex<tar::Builder<TarStream>>,
+    mtime: u64,
+    file: PartialFile,
+}
+
+impl TarSink {
+    pub fn new(path: &Path, compress: bool) -> anyhow::Result<Self> {
+        let (partial, file) = PartialFile::create(path)?;
+        let stream = if compress {
+            TarStream::Zstd(zstd::Encoder::new(file, 3)?)
+        } else {
+            TarStream::Plain(file)
+        };
+        let mtime = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        Ok(Self { builder: Mutex::new(tar::Builder::new(stream)), mtime, file: partial })
+    }
+
+    fn header(&self, entry_type: tar::EntryType, size: u64, mode: u32) -> tar::Header {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(entry_type);
+        header.set_size(size);
+        header.set_mode(mode);
+        header.set_mtime(self.mtime);
+        header
+    }
+}
+
+impl OutputSink for TarSink {
+    fn create_dir(&self, path: &Path) -> anyhow::Result<()> {
+        let mut header = self.header(tar::EntryType::Directory, 0, 0o755);
+        let name = format!("{}/", archive_name(path));
+        self.builder.lock().unwrap().append_data(&mut header, name, std::io::empty())?;
+        Ok(())
+    }
+
+    fn write_file(&self, path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+        let mut header = self.header(tar::EntryType::Regular, contents.len() as u64, 0o644);
+        self.builder.lock().unwrap().append_data(&mut header, archive_name(path), contents)?;
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> anyhow::Result<()> {
+        let stream = self.builder.into_inner().unwrap().into_inner()?;
+        let file = match stream {
+            TarStream::Plain(w) => w,
+            TarStream::Zstd(w) => w.finish()?,
+        };
+        file.into_inner()?.sync_all()?;
+        self.file.commit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rbx_ripper_test_{}_{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn fill(sink: &dyn OutputSink) {
+        sink.create_dir(Path::new("Workspace [Workspace]")).unwrap();
+        sink.write_file(&Path::new("Workspace [Workspace]").join("properties.json"), b"{}").unwrap();
+    }
+
+    #[test]
+    fn picks_the_target_by_extension() {
+        let target = |name: &str| OutputTarget::from_path(Path::new(name));
+        assert!(target("out/Place.ZIP") == OutputTarget::Zip);
+        assert!(target("place.tar") == OutputTarget::Tar);
+        assert!(target("place.tar.zst") == OutputTarget::TarZst);
+        assert!(target("place.tzst") == OutputTarget::TarZst);
+        assert!(target("place.zst") == OutputTarget::Folder);
+        assert!(target("place_extracted") == OutputTarget::Folder);
+    }
+
+    #[test]
+    fn archives_appear_only_when_finished() {
+        let dir = temp_dir("sink_zip");
+        let path = dir.join("place.zip");
+        let sink = open_sink(&path).unwrap();
+        fill(sink.as_ref());
+        assert!(!path.exists());
+        sink.finish().unwrap();
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive.by_name("Workspace [Workspace]/properties.json").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "{}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unfinished_archives_keep_the_old_file() {
+        let dir = temp_dir("sink_unfinished");
+        let path = dir.join("place.tar");
+        fs::write(&path, b"previous").unwrap();
+        let sink = open_sink(&path).unwrap();
+        fill(sink.as_ref());
+        drop(sink);
+        assert_eq!(fs::read(&path).unwrap(), b"previous");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn writes_compressed_tarballs() {
+        let dir = temp_dir("sink_tzst");
+        let path = dir.join("place.tar.zst");
+        let sink = open_sink(&path).unwrap();
+        fill(sink.as_ref());
+        sink.finish().unwrap();
+
+        let mut archive = tar::Archive::new(zstd::Decoder::new(fs::File::open(&path).unwrap()).unwrap());
+        let entries: Vec<(String, Vec<u8>)> = archive.entries().unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut contents = Vec::new();
+                entry.read_to_end(&mut contents).unwrap();
+                (entry.path().unwrap().to_string_lossy().into_owned(), contents)
+            })
+            .collect();
+        assert_eq!(entries, [
+            ("Workspace [Workspace]/".to_string(), Vec::new()),
+            ("Workspace [Workspace]/properties.json".to_string(), b"{}".to_vec()),
+        ]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
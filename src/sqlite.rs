@@ -0,0 +1,89 @@
+use crate::dom::Dom;
+use rusqlite::{params, Connection, DatabaseName};
+
+pub const SQLITE_DUMP_FILE: &str = "place.sqlite";
+
+const SCHEMA: &str = "
+CREATE TABLE instances (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER REFERENCES instances(id),
+    class TEXT NOT NULL,
+    name TEXT NOT NULL,
+    path TEXT NOT NULL
+);
+CREATE TABLE properties (
+    instance_id INTEGER NOT NULL REFERENCES instances(id),
+    name TEXT NOT NULL,
+    type TEXT NOT NULL,
+    value_json TEXT NOT NULL
+);
+CREATE TABLE scripts (
+    instance_id INTEGER NOT NULL REFERENCES instances(id),
+    kind TEXT NOT NULL,
+    source TEXT NOT NULL
+);
+CREATE INDEX instances_parent ON instances(parent_id);
+CREATE INDEX instances_class ON instances(class);
+CREATE INDEX instances_path ON instances(path);
+CREATE INDEX properties_instance ON properties(instance_id);
+CREATE INDEX properties_name ON properties(name);
+";
+
+/// Builds the database in memory and returns the serialized file, so it can go through any output sink.
+pub fn write_database(dom: &Dom) -> anyhow::Result<Vec<u8>> {
+    let mut conn = Connection::open_in_memory()?;
+    conn.execute_batch(SCHEMA)?;
+
+    let tx = conn.transaction()?;
+    {
+        let mut insert_instance = tx.prepare("INSERT INTO instances (id, parent_id, class, name, path) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+        let mut insert_property = tx.prepare("INSERT INTO properties (instance_id, name, type, value_json) VALUES (?1, ?2, ?3, ?4)")?;
+        let mut insert_script = tx.prepare("INSERT INTO scripts (instance_id, kind, source) VALUES (?1, ?2, ?3)")?;
+
+        for id in dom.iter_depth_first() {
+            let instance = &dom.instances[id];
+            insert_instance.execute(params![id as i64, instance.parent.map(|p| p as i64), instance.class, instance.name, dom.path(id)])?;
+            for property in &instance.properties {
+                let value_json = serde_json::to_string(&property.value.value_json())?;
+                insert_property.execute(params![id as i64, property.name, property.value.type_name(), value_json])?;
+            }
+            if let Some(source) = &instance.source {
+                insert_script.execute(params![id as i64, instance.class, source])?;
+            }
+        }
+    }
+    tx.commit()?;
+
+    Ok(conn.serialize(DatabaseName::Main)?.to_vec())
+}
+
+/// Bytes SQLite adds to every row and index entry: cell header, record header and rowid.
+const ROW_OVERHEAD: u64 = 12;
+const PAGE_SIZE: u64 = 4096;
+/// One page for the schema and a root page for each table and index.
+const SCHEMA_PAGES: u64 = 9;
+
+/// Approximately the size of the file [`write_database`] produces, without building the database.
+pub fn estimate_size(dom: &Dom) -> anyhow::Result<u64> {
+    let mut rows = 0;
+    let mut indexes = 0;
+    for id in dom.iter_depth_first() {
+        let instance = &dom.instances[id];
+        let path = dom.path(id).len() as u64;
+        let (class, name) = (instance.class.len() as u64, instance.name.len() as u64);
+        rows += ROW_OVERHEAD + 6 + class + name + path;
+        // instances_parent, instances_class and instances_path
+        indexes += 3 * ROW_OVERHEAD + 6 + class + path;
+        for property in &instance.properties {
+            let value_json = serde_json::to_string(&property.value.value_json())?.len() as u64;
+            let name = property.name.len() as u64;
+            rows += ROW_OVERHEAD + 3 + name + property.value.type_name().len() as u64 + value_json;
+            // properties_instance and properties_name
+            indexes += 2 * ROW_OVERHEAD + 3 + name;
+        }
+        if let Some(source) = &instance.source {
+            rows += ROW_OVERHEAD + 3 + class + source.len() as u64;
+        }
+    }
+    Ok(((rows + indexes).div_ceil(PAGE_SIZE) + SCHEMA_PAGES) * PAGE_SIZE)
+}